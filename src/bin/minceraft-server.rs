@@ -0,0 +1,3 @@
+fn main() {
+    minceraft::run_server();
+}
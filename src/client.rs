@@ -0,0 +1,771 @@
+use super::*;
+use bevy::{
+    math::I64Vec3,
+    input::mouse::{MouseMotion, MouseWheel},
+    window::{CursorGrabMode, WindowMode, PresentMode, WindowPosition, MonitorSelection},
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    ecs::{entity::Entities, system::SystemParam},
+    pbr::{FogFalloff, FogSettings, NotShadowCaster},
+    render::{
+        mesh::Indices,
+        primitives::{Aabb, Frustum},
+        render_resource::{Extent3d, PrimitiveTopology, TextureDimension, TextureFormat},
+        view::VisibilitySystems,
+        texture::{CompressedImageFormats, ImageFilterMode, ImageSampler, ImageSamplerDescriptor, ImageType},
+    },
+    tasks::AsyncComputeTaskPool,
+};
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use std::hash::Hasher;
+use std::net::{Ipv4Addr, SocketAddrV4, ToSocketAddrs};
+
+// Loading and saving the options file.
+mod settings;
+// The client side of multiplayer: joining servers, hosting LAN games and drawing remote players.
+mod network;
+// The main, multiplayer, loading and pause menus.
+mod menus;
+// The crosshair, FPS counter, debug overlay and keystrokes display.
+mod hud;
+// Chunk meshing, the block atlas, distant LOD meshes and cave culling.
+mod render;
+// Day and night, fog, clouds, rain and snow.
+mod sky;
+// Resource packs and the language file they can override.
+mod resource_pack;
+// The inventory, the hotbar and items lying in the world.
+mod inventory;
+// Recipes and the crafting menu.
+mod crafting;
+// Movement, block picking, placing and mining.
+mod player;
+// The chat command line, its argument parsing and the built-in commands.
+mod commands;
+// Region selections and the undoable bulk edits behind /fill and /clone.
+mod world_edit;
+// Sponge schematic files for /schem.
+mod schematic;
+// Importing regions of vanilla Anvil worlds.
+mod anvil;
+
+use self::{
+    anvil::*, commands::*, crafting::*, hud::*, inventory::*, menus::*, network::*,
+    player::*, render::*, resource_pack::*, schematic::*, settings::*, sky::*, world_edit::*,
+};
+
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+enum GameState {
+    #[default]
+    MainMenu,
+    Multiplayer,
+    Loading,
+    Playing,
+    Paused,
+    Crafting,
+    Console,
+}
+
+#[derive(Component)]
+struct Player {
+    yaw: f32,
+    pitch: f32,
+    last_jump_time: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloudMode {
+    Off,
+    Flat,
+    Blocky,
+}
+
+#[derive(Resource)]
+struct GameSettings {
+    fov: f32,
+    show_keystrokes: bool,
+    keybinds: KeyBinds,
+    currently_binding: Option<KeyBind>,
+    // Handed to the simulation when the game starts.
+    simulation: SimulationSettings,
+    // Enabled resource packs, highest priority first.
+    resource_packs: Vec<String>,
+    // In chunks, measured horizontally from the camera's chunk.
+    render_distance: i32,
+    fog: FogMode,
+    clouds: CloudMode,
+    cloud_height: f32,
+    // Addresses added on the multiplayer screen, in the order they were added.
+    servers: Vec<String>,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            fov: 100.0,
+            show_keystrokes: true,
+            keybinds: KeyBinds::default(),
+            currently_binding: None,
+            simulation: SimulationSettings::default(),
+            resource_packs: Vec::new(),
+            render_distance: RENDER_DISTANCE,
+            fog: FogMode::Linear,
+            clouds: CloudMode::Blocky,
+            cloud_height: CLOUD_HEIGHT,
+            servers: Vec::new(),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct KeyBinds {
+    forward: KeyCode,
+    backward: KeyCode,
+    left: KeyCode,
+    right: KeyCode,
+    jump: KeyCode,
+    sprint: KeyCode,
+    sneak: KeyCode,
+    inventory: KeyCode,
+    drop: KeyCode,
+}
+
+impl Default for KeyBinds {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::W,
+            backward: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+            jump: KeyCode::Space,
+            sprint: KeyCode::Space,
+            sneak: KeyCode::ShiftLeft,
+            inventory: KeyCode::E,
+            drop: KeyCode::Q,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, EnumString)]
+enum KeyBind {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Jump,
+    Sprint,
+    Inventory,
+    Drop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FogMode {
+    Linear,
+    Exponential,
+}
+
+impl BlockKind {
+    fn color(&self) -> Color {
+        match self {
+            BlockKind::Grass => Color::rgb(0.3, 0.5, 0.3),
+            BlockKind::Dirt => Color::rgb(0.5, 0.3, 0.2),
+            BlockKind::Stone => Color::rgb(0.5, 0.5, 0.5),
+            BlockKind::Cobblestone => Color::rgb(0.4, 0.4, 0.4),
+            BlockKind::CoalOre => Color::rgb(0.3, 0.3, 0.3),
+            BlockKind::Log => Color::rgb(0.4, 0.3, 0.15),
+            BlockKind::Leaves => Color::rgb(0.2, 0.6, 0.2),
+            BlockKind::Planks => Color::rgb(0.7, 0.55, 0.3),
+            BlockKind::CraftingTable => Color::rgb(0.6, 0.4, 0.2),
+            BlockKind::Torch => Color::rgb(1.0, 0.8, 0.3),
+            BlockKind::Lava => Color::rgb(1.0, 0.4, 0.0),
+            BlockKind::Glass => Color::rgba(0.8, 0.9, 1.0, 0.4),
+            BlockKind::Water => Color::rgba(0.2, 0.4, 0.9, 0.6),
+        }
+    }
+}
+
+impl ToolTier {
+    fn speed(&self) -> f32 {
+        match self {
+            ToolTier::Wood => 2.0,
+            ToolTier::Stone => 4.0,
+        }
+    }
+
+    fn durability(&self) -> u32 {
+        match self {
+            ToolTier::Wood => 59,
+            ToolTier::Stone => 131,
+        }
+    }
+}
+
+// Which chunk mesh a block's faces go into, since each needs its own blending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
+enum MeshLayer {
+    Opaque,
+    // Alpha-tested: texels are either fully drawn or discarded.
+    Cutout,
+    // Alpha-blended and sorted back-to-front.
+    Translucent,
+}
+
+// Atlas texture names for each side of a block.
+#[derive(Debug, Clone, Copy)]
+struct BlockTextures {
+    top: &'static str,
+    side: &'static str,
+    bottom: &'static str,
+}
+
+impl BlockTextures {
+    fn all(name: &'static str) -> Self {
+        Self { top: name, side: name, bottom: name }
+    }
+
+    fn face(&self, normal: IVec3) -> &'static str {
+        match normal {
+            IVec3::Y => self.top,
+            IVec3::NEG_Y => self.bottom,
+            _ => self.side,
+        }
+    }
+}
+
+// How long a block takes to break and what it leaves behind.
+#[derive(Debug, Clone, Copy)]
+struct BlockMining {
+    hardness: f32,
+    preferred_tool: Option<ToolType>,
+    // Without at least this tier of the preferred tool the block breaks slowly and drops nothing.
+    required_tier: Option<ToolTier>,
+    drop: Option<ItemKind>,
+}
+
+impl BlockKind {
+    fn textures(&self) -> BlockTextures {
+        match self {
+            BlockKind::Grass => BlockTextures { top: "grass_top", side: "grass_side", bottom: "dirt" },
+            BlockKind::Dirt => BlockTextures::all("dirt"),
+            BlockKind::Stone => BlockTextures::all("stone"),
+            BlockKind::Cobblestone => BlockTextures::all("cobblestone"),
+            BlockKind::CoalOre => BlockTextures::all("coal_ore"),
+            BlockKind::Log => BlockTextures { top: "log_top", side: "log_side", bottom: "log_top" },
+            BlockKind::Leaves => BlockTextures::all("leaves"),
+            BlockKind::Planks => BlockTextures::all("planks"),
+            BlockKind::CraftingTable => BlockTextures { top: "crafting_table_top", side: "crafting_table_side", bottom: "planks" },
+            BlockKind::Torch => BlockTextures::all("torch"),
+            BlockKind::Lava => BlockTextures::all("lava"),
+            BlockKind::Glass => BlockTextures::all("glass"),
+            BlockKind::Water => BlockTextures::all("water"),
+        }
+    }
+
+    fn layer(&self) -> MeshLayer {
+        match self {
+            BlockKind::Leaves | BlockKind::Torch | BlockKind::Glass => MeshLayer::Cutout,
+            BlockKind::Water => MeshLayer::Translucent,
+            _ => MeshLayer::Opaque,
+        }
+    }
+
+    fn mining(&self) -> BlockMining {
+        let mining = |hardness, preferred_tool, required_tier, drop| BlockMining { hardness, preferred_tool, required_tier, drop };
+        match self {
+            BlockKind::Grass => mining(0.6, Some(ToolType::Shovel), None, Some(ItemKind::Dirt)),
+            BlockKind::Dirt => mining(0.5, Some(ToolType::Shovel), None, Some(ItemKind::Dirt)),
+            BlockKind::Stone => mining(1.5, Some(ToolType::Pickaxe), Some(ToolTier::Wood), Some(ItemKind::Cobblestone)),
+            BlockKind::Cobblestone => mining(2.0, Some(ToolType::Pickaxe), Some(ToolTier::Wood), Some(ItemKind::Cobblestone)),
+            BlockKind::CoalOre => mining(3.0, Some(ToolType::Pickaxe), Some(ToolTier::Wood), Some(ItemKind::Coal)),
+            BlockKind::Log => mining(2.0, Some(ToolType::Axe), None, Some(ItemKind::Log)),
+            BlockKind::Leaves => mining(0.2, None, None, Some(ItemKind::Leaves)),
+            BlockKind::Planks => mining(2.0, Some(ToolType::Axe), None, Some(ItemKind::Planks)),
+            BlockKind::CraftingTable => mining(2.5, Some(ToolType::Axe), None, Some(ItemKind::CraftingTable)),
+            BlockKind::Torch => mining(0.0, None, None, Some(ItemKind::Torch)),
+            BlockKind::Lava | BlockKind::Water => mining(f32::INFINITY, None, None, None),
+            BlockKind::Glass => mining(0.3, None, None, None),
+        }
+    }
+
+    fn can_harvest(&self, tool: Option<Tool>) -> bool {
+        let mining = self.mining();
+        match mining.required_tier {
+            None => true,
+            Some(tier) => tool.is_some_and(|tool| {
+                Some(tool.tool_type) == mining.preferred_tool && tool.tier >= tier
+            }),
+        }
+    }
+
+    // Seconds of continuous mining needed to break the block.
+    fn break_time(&self, tool: Option<Tool>) -> f32 {
+        let mining = self.mining();
+        let speed = match tool {
+            Some(tool) if Some(tool.tool_type) == mining.preferred_tool => tool.tier.speed(),
+            _ => 1.0,
+        };
+        let penalty = if self.can_harvest(tool) { 1.5 } else { 5.0 };
+        mining.hardness * penalty / speed
+    }
+}
+
+impl ItemKind {
+    fn block(&self) -> Option<BlockKind> {
+        match self {
+            ItemKind::Grass => Some(BlockKind::Grass),
+            ItemKind::Dirt => Some(BlockKind::Dirt),
+            ItemKind::Stone => Some(BlockKind::Stone),
+            ItemKind::Cobblestone => Some(BlockKind::Cobblestone),
+            ItemKind::Log => Some(BlockKind::Log),
+            ItemKind::Leaves => Some(BlockKind::Leaves),
+            ItemKind::Planks => Some(BlockKind::Planks),
+            ItemKind::CraftingTable => Some(BlockKind::CraftingTable),
+            ItemKind::Torch => Some(BlockKind::Torch),
+            ItemKind::Glass => Some(BlockKind::Glass),
+            _ => None,
+        }
+    }
+}
+
+impl ItemKind {
+    fn color(&self) -> Color {
+        match (self.block(), self.tool()) {
+            (Some(block), _) => block.color(),
+            (None, Some(Tool { tier: ToolTier::Stone, .. })) => Color::rgb(0.45, 0.45, 0.45),
+            _ if *self == ItemKind::Coal => Color::rgb(0.1, 0.1, 0.1),
+            _ => Color::rgb(0.55, 0.4, 0.2),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct BlockAssets {
+    cube_mesh: Handle<Mesh>,
+    chunk_materials: HashMap<MeshLayer, Handle<StandardMaterial>>,
+    item_mesh: Handle<Mesh>,
+    item_materials: HashMap<ItemKind, Handle<StandardMaterial>>,
+    crack_materials: Vec<Handle<StandardMaterial>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, EnumIter)]
+#[strum(serialize_all = "lowercase")]
+enum GameMode {
+    Survival,
+    Creative,
+}
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+struct CurrentGameMode(GameMode);
+
+impl Default for CurrentGameMode {
+    fn default() -> Self {
+        Self(GameMode::Survival)
+    }
+}
+
+#[derive(Component)]
+struct Flight;
+
+const FILL_LIMIT: i32 = 32768;
+
+const INVENTORY_SIZE: usize = 36;
+
+const HOTBAR_SIZE: usize = 9;
+
+const CLOUD_HEIGHT: f32 = 64.0;
+
+const MAX_CLOUD_HEIGHT: f32 = 256.0;
+
+const CLOUD_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+const RENDER_DISTANCE: i32 = 8;
+
+const MIN_RENDER_DISTANCE: i32 = 2;
+
+const MAX_RENDER_DISTANCE: i32 = 32;
+
+const ITEM_THROW_SPEED: f32 = 6.0;
+
+const CRACK_STAGES: usize = 10;
+
+const RESOURCE_PACK_DIR: &str = "resourcepacks";
+
+const BLOCK_TEXTURE_DIR: &str = "textures/blocks";
+
+pub fn run_client() {
+    let mut join = None;
+    let mut name = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--connect", Some(address)) => join = Some(address),
+            ("--name", Some(value)) => name = Some(value),
+            _ => {
+                eprintln!("Usage: Minceraft [--connect <address> [--name <name>]]");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let settings = load_settings().unwrap_or_default();
+    let resource_packs = ResourcePacks::load(&settings.resource_packs);
+    let language = Language::load(&resource_packs);
+    let player_name = PlayerName(name.unwrap_or_else(|| format!("Player{}", random::<u16>() % 1000)));
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                present_mode: PresentMode::AutoVsync,
+                mode: WindowMode::Windowed,
+                resizable: true,
+                resolution: (1920., 1080.).into(),
+                title: "Minceraft".to_string(),
+                position: WindowPosition::Centered(MonitorSelection::Primary),
+                ..default()
+            }),
+            ..default()
+        }))
+        .add_plugins(FrameTimeDiagnosticsPlugin)
+        .add_state::<GameState>()
+        .insert_resource(settings.simulation)
+        .insert_resource(settings)
+        .add_plugins(SimulationPlugin)
+        .configure_sets(Update, SimulationSet.run_if(in_state(GameState::Playing)))
+        .configure_sets(FixedUpdate, SimulationSet.run_if(in_state(GameState::Playing)))
+        .insert_resource(WorldGenProgress {
+            blocks_completed: 0,
+            total_blocks: (WORLD_SIZE * WORLD_SIZE) as usize,
+        })
+        .insert_resource(language)
+        .insert_resource(resource_packs)
+        .insert_resource(BreakProgress::default())
+        .insert_resource(CullingStats::default())
+        .init_resource::<ChunkRenderState>()
+        .init_resource::<LightningFlash>()
+        .insert_resource(DebugOverlay::default())
+        .insert_resource(LodMeshes::default())
+        .insert_resource(CommandLine::default())
+        .insert_resource(CommandRegistry::default())
+        .insert_resource(CurrentGameMode::default())
+        .insert_resource(Selection::default())
+        .insert_resource(EditHistory::default())
+        .insert_resource(ClearColor(sky_color(0.0)))
+        .insert_resource(Inventory::default())
+        .insert_resource(CraftingGrid::new(2))
+        .insert_resource(load_recipes())
+        .init_resource::<LocalInput>()
+        .add_event::<BlockEdited>()
+        .add_systems(Startup, (setup, spawn_fps_counter))
+        .add_systems(Update, (
+            main_menu.run_if(in_state(GameState::MainMenu)),
+            multiplayer_menu.run_if(in_state(GameState::Multiplayer)),
+            loading_screen.run_if(in_state(GameState::Loading).and_then(not(resource_exists::<JoinRequest>()))),
+            join_server.run_if(in_state(GameState::Loading).and_then(resource_exists::<JoinRequest>())),
+            (
+                player_control.run_if(in_state(GameState::Playing)),
+                physics_system.run_if(in_state(GameState::Playing)),
+                client_network.run_if(resource_exists::<NetworkClient>().and_then(not(in_state(GameState::Loading)))),
+            ).chain(),
+            host_lan_game.run_if(resource_exists::<NetworkServer>()),
+            update_remote_avatars,
+            toggle_pause,
+        ))
+        .add_systems(FixedUpdate, send_snapshots.run_if(resource_exists::<NetworkServer>()))
+        .add_systems(Update, (
+            pause_menu,
+            open_to_lan,
+            adjust_fov,
+            adjust_render_distance,
+        ).run_if(in_state(GameState::Paused)))
+        .add_systems(Update, (toggle_debug_overlay, update_fps_text, update_frame_time_graph).chain())
+        .add_systems(Update, update_window_title)
+        .add_systems(OnEnter(GameState::Loading), cleanup_main_menu)
+        .add_systems(OnEnter(GameState::Multiplayer), open_server_browser)
+        .add_systems(OnExit(GameState::Multiplayer), close_server_browser)
+        .add_systems(OnEnter(GameState::Playing), (
+            cleanup_loading_screen,
+            cleanup_pause_menu,
+            spawn_crosshair
+        ))
+        .add_systems(Update, keystrokes_display.run_if(in_state(GameState::Playing)))
+        .add_systems(Update, (
+            block_interaction,
+            mine_blocks,
+            hotbar_selection,
+            hotbar_display,
+        ).run_if(in_state(GameState::Playing)))
+        .add_systems(Update, toggle_inventory.run_if(
+            in_state(GameState::Playing).or_else(in_state(GameState::Crafting))
+        ))
+        .add_systems(Update, (
+            crafting_interaction,
+            crafting_menu,
+        ).chain().run_if(in_state(GameState::Crafting)))
+        .add_systems(Update, (
+            throw_item,
+            animate_dropped_items,
+            pickup_dropped_items,
+        ).run_if(in_state(GameState::Playing)))
+        .add_systems(Update, (
+            reload_resource_packs,
+            remesh_chunks,
+            sort_translucent_faces,
+            request_lod_meshes,
+            receive_lod_meshes,
+        ).chain())
+        .add_systems(PostUpdate, cull_chunks
+            .after(VisibilitySystems::UpdateProjectionFrusta)
+            .before(VisibilitySystems::VisibilityPropagate))
+        .add_systems(Update, update_precipitation.run_if(in_state(GameState::Playing)))
+        .add_systems(Update, (
+            (fade_weather, update_sky, update_fog).chain(),
+            update_clouds,
+            open_command_line.run_if(in_state(GameState::Playing)),
+            command_line_input,
+            command_line_display,
+            draw_selection,
+        ))
+        .add_systems(OnEnter(GameState::Loading), load_world)
+        .add_systems(OnEnter(GameState::Paused), (save_world, rescan_resource_packs))
+        .add_systems(Last, (save_world_on_exit, disconnect_on_exit, close_server_on_exit))
+        .add_systems(OnExit(GameState::Playing), cleanup_hotbar)
+        .add_systems(OnExit(GameState::Crafting), (close_crafting, cleanup_crafting_menu));
+
+    if let Some(address) = join {
+        app.insert_resource(JoinRequest { address, name: player_name.0.clone() })
+            .insert_resource(NextState(Some(GameState::Loading)));
+    }
+    app.insert_resource(player_name);
+    app.run();
+}
+
+#[derive(Resource, Default)]
+struct LocalInput {
+    sequence: u32,
+    // The input built this frame, if the player is in control.
+    current: Option<MoveInput>,
+}
+
+#[derive(Event)]
+struct BlockEdited {
+    pos: IVec3,
+    kind: Option<BlockKind>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut windows: Query<&mut Window>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    settings: Res<GameSettings>,
+    resource_packs: Res<ResourcePacks>,
+) {
+    let mut window = windows.single_mut();
+    window.cursor.visible = false;
+    window.cursor.grab_mode = CursorGrabMode::Locked;
+
+    let (atlas, atlas_image) = build_block_atlas(&resource_packs);
+    commands.insert_resource(atlas);
+    let atlas_image = images.add(atlas_image);
+
+    let block_assets = BlockAssets {
+        cube_mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        chunk_materials: MeshLayer::iter()
+            .map(|layer| {
+                let alpha_mode = match layer {
+                    MeshLayer::Opaque => AlphaMode::Opaque,
+                    MeshLayer::Cutout => AlphaMode::Mask(0.5),
+                    MeshLayer::Translucent => AlphaMode::Blend,
+                };
+                (layer, materials.add(StandardMaterial {
+                    base_color: Color::WHITE,
+                    base_color_texture: Some(atlas_image.clone()),
+                    perceptual_roughness: 1.0,
+                    alpha_mode,
+                    ..default()
+                }))
+            })
+            .collect(),
+        item_mesh: meshes.add(Mesh::from(shape::Cube { size: ITEM_SIZE })),
+        item_materials: ItemKind::iter()
+            .map(|item| (item, materials.add(item.color().into())))
+            .collect(),
+        crack_materials: (0..CRACK_STAGES)
+            .map(|stage| materials.add(StandardMaterial {
+                base_color: Color::rgba(0.0, 0.0, 0.0, 0.1 + stage as f32 * 0.06),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }))
+            .collect(),
+    };
+    
+    let seed = random::<u32>();
+    let perlin = Perlin::new(seed);
+    
+    for x in -10..10 {
+        for z in -10..10 {
+            for (pos, kind) in generate_column(&perlin, x, z) {
+                world.set_raw(pos, Some(kind));
+            }
+        }
+    }
+    initialize_light(&mut world, &registry);
+
+    commands.spawn((
+        PbrBundle {
+            mesh: block_assets.cube_mesh.clone(),
+            material: block_assets.crack_materials[0].clone(),
+            transform: Transform::from_scale(Vec3::splat(1.01)),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        NotShadowCaster,
+        BreakOverlay,
+    ));
+    commands.insert_resource(block_assets);
+
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 15.0, 0.0),
+            projection: Projection::Perspective(PerspectiveProjection {
+                fov: settings.fov.to_radians(),
+                ..default()
+            }),
+            ..default()
+        },
+        FogSettings::default(),
+        Player {
+            yaw: 0.0,
+            pitch: 0.0,
+            last_jump_time: None,
+        },
+        Velocity(Vec3::ZERO),
+        Gravity(GRAVITY),
+    ));
+
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                shadows_enabled: true,
+                illuminance: 50000.0,
+                ..default()
+            },
+            transform: Transform::from_xyz(4.0, 8.0, 4.0)
+                .looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        Sun,
+    ));
+
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                color: Color::rgb(0.6, 0.7, 1.0),
+                illuminance: 0.0,
+                ..default()
+            },
+            ..default()
+        },
+        Moon,
+    ));
+
+    commands.spawn((
+        PbrBundle {
+            material: materials.add(StandardMaterial {
+                base_color: CLOUD_COLOR,
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                cull_mode: None,
+                ..default()
+            }),
+            ..default()
+        },
+        NotShadowCaster,
+        Clouds { built_for: None },
+    ));
+
+    commands.insert_resource(PrecipitationAssets {
+        rain_mesh: meshes.add(Mesh::from(shape::Box::new(0.03, 0.6, 0.03))),
+        rain_material: materials.add(StandardMaterial {
+            base_color: Color::rgba(0.5, 0.6, 0.9, 0.6),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        }),
+        snow_mesh: meshes.add(Mesh::from(shape::Cube { size: 0.1 })),
+        snow_material: materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            unlit: true,
+            ..default()
+        }),
+    });
+
+    commands.insert_resource(AmbientLight {
+        color: Color::WHITE,
+        brightness: 0.3,
+    });
+}
+
+fn load_world(mut commands: Commands) {
+    let level = load_level(&level_path(DEFAULT_LEVEL_NAME)).unwrap_or_default();
+    commands.insert_resource(level.time);
+    commands.insert_resource(level.seed);
+    commands.insert_resource(level.weather);
+}
+
+// Nothing is saved while playing on a server; the world there isn't ours.
+fn save_world(world_time: Res<WorldTime>, world_seed: Res<WorldSeed>, weather: Res<Weather>, client: Option<Res<NetworkClient>>) {
+    if client.is_some() {
+        return;
+    }
+    save_level(&level_path(DEFAULT_LEVEL_NAME), &world_time, &world_seed, &weather).unwrap_or_else(|e| eprintln!("Failed to save world: {}", e));
+}
+
+fn save_world_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    world_time: Res<WorldTime>,
+    world_seed: Res<WorldSeed>,
+    weather: Res<Weather>,
+    state: Res<State<GameState>>,
+    client: Option<Res<NetworkClient>>,
+) {
+    if exit_events.read().next().is_some() && client.is_none() && !matches!(state.get(), GameState::MainMenu | GameState::Multiplayer | GameState::Loading) {
+        save_level(&level_path(DEFAULT_LEVEL_NAME), &world_time, &world_seed, &weather).unwrap_or_else(|e| eprintln!("Failed to save world: {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn break_time_depends_on_tool_and_tier() {
+        let wooden_pickaxe = ItemKind::WoodenPickaxe.tool();
+        let stone_pickaxe = ItemKind::StonePickaxe.tool();
+        // Hardness 1.5 stone: bare hands get the 5x penalty, pickaxes 1.5x divided by their tier's speed.
+        assert_eq!(BlockKind::Stone.break_time(None), 7.5);
+        assert_eq!(BlockKind::Stone.break_time(wooden_pickaxe), 1.125);
+        assert_eq!(BlockKind::Stone.break_time(stone_pickaxe), 0.5625);
+        // The wrong tool is no faster than a hand.
+        assert_eq!(BlockKind::Stone.break_time(ItemKind::StoneAxe.tool()), 7.5);
+        // Dirt needs no tool, so a hand only pays the normal 1.5x and a shovel speeds it up.
+        assert_eq!(BlockKind::Dirt.break_time(None), 0.75);
+        assert_eq!(BlockKind::Dirt.break_time(ItemKind::WoodenShovel.tool()), 0.375);
+        assert_eq!(BlockKind::Torch.break_time(None), 0.0);
+        assert!(BlockKind::Water.break_time(stone_pickaxe).is_infinite());
+    }
+
+    #[test]
+    fn harvesting_needs_the_required_tool() {
+        assert!(!BlockKind::Stone.can_harvest(None));
+        assert!(!BlockKind::CoalOre.can_harvest(ItemKind::StoneShovel.tool()));
+        assert!(BlockKind::Stone.can_harvest(ItemKind::WoodenPickaxe.tool()));
+        assert!(BlockKind::CoalOre.can_harvest(ItemKind::StonePickaxe.tool()));
+        assert!(BlockKind::Log.can_harvest(None));
+        assert!(BlockKind::Dirt.can_harvest(ItemKind::WoodenAxe.tool()));
+    }
+}
@@ -0,0 +1,249 @@
+use super::*;
+
+const REGION_SECTOR: usize = 4096;
+
+// Minecraft data version (20w17a) from which packed block states no longer run across longs.
+const ANVIL_PADDED_DATA_VERSION: i64 = 2529;
+
+// A decoded Minecraft chunk column: chunk coordinates and its 16³ sections by section y,
+// each indexed like Minecraft's: x fastest, then z, then y.
+struct AnvilChunk {
+    x: i32,
+    z: i32,
+    sections: Vec<(i32, Vec<Option<BlockKind>>)>,
+}
+
+// How Minecraft block states become ours: ids in `BLOCK_IDS` map directly, anything else to `fallback`.
+struct BlockMapping {
+    fallback: Option<BlockKind>,
+    // Blocks that fell back so far.
+    unknown: usize,
+}
+
+// Returns the NBT of the chunk at `local` (0..32 on each axis) in a region file, or `None` if it was never generated.
+fn region_chunk(bytes: &[u8], local: IVec2) -> Result<Option<nbt::Tag>, String> {
+    if bytes.len() < 2 * REGION_SECTOR {
+        return Err("Region file is missing its header".to_string());
+    }
+    let entry = 4 * (local.x + local.y * 32) as usize;
+    let location = u32::from_be_bytes(bytes[entry..entry + 4].try_into().unwrap());
+    if location == 0 {
+        return Ok(None);
+    }
+    let start = (location >> 8) as usize * REGION_SECTOR;
+    let header = bytes.get(start..start + 5).ok_or("Chunk lies past the end of the region file")?;
+    let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+    let data = length
+        .checked_sub(1)
+        .and_then(|length| bytes.get(start + 5..start + 5 + length))
+        .ok_or("Chunk lies past the end of the region file")?;
+    let (_, root) = match header[4] {
+        1 => nbt::read_gzip(data),
+        2 => nbt::read_zlib(data),
+        3 => nbt::read(&mut &data[..]),
+        compression => return Err(format!("Unsupported chunk compression {compression}")),
+    }
+    .map_err(|err| format!("Invalid chunk data: {err}"))?;
+    Ok(Some(root))
+}
+
+// Unpacks 4096 palette indices. Worlds from before 1.16 let values run across two longs; later ones pad each long.
+fn unpack_indices(data: &[i64], palette_len: usize, spanning: bool) -> Result<Vec<usize>, String> {
+    let bits = (usize::BITS - (palette_len - 1).leading_zeros()).max(4) as usize;
+    let needed = if spanning { (4096 * bits).div_ceil(64) } else { 4096usize.div_ceil(64 / bits) };
+    if data.len() < needed {
+        return Err(format!("Section has {} longs of block data, expected {needed}", data.len()));
+    }
+    let mask = (1u64 << bits) - 1;
+    (0..4096)
+        .map(|i| {
+            let (long, shift) = if spanning { (i * bits / 64, i * bits % 64) } else { (i / (64 / bits), i % (64 / bits) * bits) };
+            let mut value = data[long] as u64 >> shift;
+            if shift + bits > 64 {
+                value |= (data[long + 1] as u64) << (64 - shift);
+            }
+            let index = (value & mask) as usize;
+            if index < palette_len {
+                Ok(index)
+            } else {
+                Err(format!("Block data refers to palette entry {index}, which doesn't exist"))
+            }
+        })
+        .collect()
+}
+
+// Reads the block states of a chunk saved by 1.13 or later; older numeric-id chunks are rejected.
+fn decode_anvil_chunk(root: &nbt::Tag, mapping: &mut BlockMapping) -> Result<AnvilChunk, String> {
+    let data_version = root.get("DataVersion").and_then(nbt::Tag::as_i64).unwrap_or(0);
+    // 1.18 moved everything out of `Level` and renamed the section fields.
+    let (level, sections, palette_key, data_key) = match root.get("Level") {
+        Some(level) => (level, "Sections", "Palette", "BlockStates"),
+        None => (root, "sections", "palette", "data"),
+    };
+    let coordinate = |name: &str| {
+        level.get(name).and_then(nbt::Tag::as_i64).map(|value| value as i32).ok_or_else(|| format!("Chunk is missing {name}"))
+    };
+    let mut chunk = AnvilChunk { x: coordinate("xPos")?, z: coordinate("zPos")?, sections: Vec::new() };
+
+    for section in level.get(sections).and_then(nbt::Tag::as_list).unwrap_or_default() {
+        let states = section.get("block_states").unwrap_or(section);
+        // Sections holding only light, or from before 1.13, have no palette.
+        let Some(palette) = states.get(palette_key).and_then(nbt::Tag::as_list) else {
+            if section.get("Blocks").is_some() {
+                return Err("Chunks from before Minecraft 1.13 are not supported".to_string());
+            }
+            continue;
+        };
+        if palette.is_empty() {
+            continue;
+        }
+        let y = section.get("Y").and_then(nbt::Tag::as_i64).ok_or("Chunk section is missing Y")? as i32;
+
+        let mut unknown = vec![false; palette.len()];
+        let kinds: Vec<Option<BlockKind>> = palette
+            .iter()
+            .zip(&mut unknown)
+            .map(|(state, unknown)| {
+                let name = state.get("Name").and_then(nbt::Tag::as_str).ok_or("Palette entry is missing its name")?;
+                Ok(block_from_id(name).unwrap_or_else(|| {
+                    *unknown = true;
+                    mapping.fallback
+                }))
+            })
+            .collect::<Result<_, String>>()?;
+
+        let blocks = match states.get(data_key) {
+            Some(nbt::Tag::LongArray(data)) => {
+                let indices = unpack_indices(data, palette.len(), data_version < ANVIL_PADDED_DATA_VERSION)?;
+                mapping.unknown += indices.iter().filter(|&&index| unknown[index]).count();
+                indices.into_iter().map(|index| kinds[index]).collect()
+            }
+            // A single-entry palette fills the section without any data.
+            _ => {
+                if unknown[0] {
+                    mapping.unknown += 4096;
+                }
+                vec![kinds[0]; 4096]
+            }
+        };
+        chunk.sections.push((y, blocks));
+    }
+    Ok(chunk)
+}
+
+// Replaces the world with the Minecraft world whose region files are in `region_dir`. The column at `center`
+// lands on x = z = 0 and `center.y` on y = 0. Returns how many chunks were found and how many blocks were unknown.
+pub(super) fn import_anvil(
+    world: &mut WorldBlocks,
+    registry: &BlockRegistry,
+    region_dir: &Path,
+    center: IVec3,
+    fallback: Option<BlockKind>,
+) -> Result<(usize, usize), String> {
+    let half = WORLD_SIZE / 2;
+    let min = IVec3::new(center.x - half, center.y + WORLD_MIN_Y, center.z - half);
+    let max = IVec3::new(center.x + half - 1, center.y + WORLD_MAX_Y, center.z + half - 1);
+    let mut mapping = BlockMapping { fallback, unknown: 0 };
+    let mut regions: HashMap<IVec2, Option<Vec<u8>>> = HashMap::new();
+    let mut chunks = Vec::new();
+    for chunk_x in min.x.div_euclid(CHUNK_SIZE)..=max.x.div_euclid(CHUNK_SIZE) {
+        for chunk_z in min.z.div_euclid(CHUNK_SIZE)..=max.z.div_euclid(CHUNK_SIZE) {
+            let chunk = IVec2::new(chunk_x, chunk_z);
+            let region = chunk.div_euclid(IVec2::splat(32));
+            let bytes = regions.entry(region).or_insert_with(|| {
+                std::fs::read(region_dir.join(format!("r.{}.{}.mca", region.x, region.y))).ok()
+            });
+            let Some(bytes) = bytes else {
+                continue;
+            };
+            if let Some(root) = region_chunk(bytes, chunk.rem_euclid(IVec2::splat(32)))? {
+                let decoded = decode_anvil_chunk(&root, &mut mapping)?;
+                if (decoded.x, decoded.z) != (chunk_x, chunk_z) {
+                    return Err(format!("Chunk {chunk_x} {chunk_z} claims to be at {} {}", decoded.x, decoded.z));
+                }
+                chunks.push(decoded);
+            }
+        }
+    }
+    if chunks.is_empty() {
+        return Err(format!("No chunks around {} {} in {}", center.x, center.z, region_dir.display()));
+    }
+
+    world.clear();
+    for chunk in &chunks {
+        // Section Y is a byte in Minecraft; anything else is corrupt.
+        for (section_y, blocks) in chunk.sections.iter().filter(|(y, _)| i8::try_from(*y).is_ok()) {
+            let origin = IVec3::new(chunk.x, *section_y, chunk.z) * CHUNK_SIZE;
+            for (index, kind) in blocks.iter().enumerate() {
+                let index = index as i32;
+                let pos = origin + IVec3::new(index % 16, index / 256, index / 16 % 16);
+                if kind.is_some() && pos.cmpge(min).all() && pos.cmple(max).all() {
+                    world.set_raw(pos - center, *kind);
+                }
+            }
+        }
+    }
+    initialize_light(world, registry);
+    Ok((chunks.len(), mapping.unknown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANVIL_FIXTURE: &str = "tests/fixtures/anvil/region";
+
+    #[test]
+    fn decodes_modern_and_legacy_anvil_chunks() {
+        let bytes = std::fs::read(Path::new(ANVIL_FIXTURE).join("r.0.0.mca")).unwrap();
+        assert_eq!(region_chunk(&bytes, IVec2::new(5, 5)), Ok(None));
+        let mut mapping = BlockMapping { fallback: Some(BlockKind::Glass), unknown: 0 };
+
+        // 1.20 layout with padded longs and a zlib stream.
+        let modern = decode_anvil_chunk(&region_chunk(&bytes, IVec2::ZERO).unwrap().unwrap(), &mut mapping).unwrap();
+        assert_eq!((modern.x, modern.z), (0, 0));
+        let (y, blocks) = &modern.sections[1];
+        assert_eq!(*y, 4);
+        assert_eq!(modern.sections[0].1, vec![None; 4096]);
+        assert_eq!(blocks[0], Some(BlockKind::Stone));
+        assert_eq!(blocks[256 + 17], Some(BlockKind::Grass));
+        assert_eq!(blocks[2 * 256 + 5 * 16 + 3], Some(BlockKind::Log));
+        assert_eq!(blocks[2 * 256 + 5 * 16 + 4], Some(BlockKind::Glass));
+        assert_eq!(blocks[3 * 256], None);
+        assert_eq!(mapping.unknown, 1);
+
+        // 1.14 layout inside `Level`, gzipped, with 5-bit values running across longs.
+        let legacy = decode_anvil_chunk(&region_chunk(&bytes, IVec2::X).unwrap().unwrap(), &mut mapping).unwrap();
+        assert_eq!((legacy.x, legacy.z), (1, 0));
+        let (_, blocks) = &legacy.sections[0];
+        assert_eq!(blocks[11], Some(BlockKind::Stone));
+        assert_eq!(blocks[12], Some(BlockKind::Glass));
+        assert_eq!(blocks[13], Some(BlockKind::Stone));
+        assert_eq!(blocks[256], None);
+    }
+
+    #[test]
+    fn imports_anvil_worlds_around_a_position() {
+        let mut world = WorldBlocks::default();
+        let registry = BlockRegistry::default();
+        let (chunks, unknown) = import_anvil(&mut world, &registry, Path::new(ANVIL_FIXTURE), IVec3::new(16, 64, 0), None).unwrap();
+        assert_eq!((chunks, unknown), (2, 1));
+        assert_eq!(world.get(IVec3::new(-16, 0, 0)), Some(BlockKind::Stone));
+        assert_eq!(world.get(IVec3::new(-13, 2, 5)), Some(BlockKind::Log));
+        assert_eq!(world.get(IVec3::new(-12, 2, 5)), None);
+        assert_eq!(world.get(IVec3::new(12, 0, 0)), Some(BlockKind::Glass));
+        assert_eq!(world.get(IVec3::new(20, 0, 0)), None);
+        assert_eq!(world.light(IVec3::new(0, 3, 0), LightChannel::Sky), MAX_LIGHT);
+        assert!(import_anvil(&mut world, &registry, Path::new(ANVIL_FIXTURE), IVec3::new(5000, 64, 0), None).is_err());
+
+        // Damaged region files fail cleanly.
+        let bytes = std::fs::read(Path::new(ANVIL_FIXTURE).join("r.0.0.mca")).unwrap();
+        assert!(region_chunk(&bytes[..100], IVec2::ZERO).is_err());
+        assert!(region_chunk(&bytes[..REGION_SECTOR * 2 + 10], IVec2::ZERO).is_err());
+        let mut bad_compression = bytes.clone();
+        bad_compression[REGION_SECTOR * 2 + 4] = 9;
+        assert!(region_chunk(&bad_compression, IVec2::ZERO).is_err());
+        assert!(unpack_indices(&[0; 10], 5, false).is_err());
+        assert!(unpack_indices(&[-1; 256], 5, false).is_err());
+    }
+}
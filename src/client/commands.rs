@@ -0,0 +1,1399 @@
+use super::*;
+
+#[derive(Resource, Default)]
+pub(super) struct CommandLine {
+    text: String,
+    // Oldest first, with the time each line was added.
+    messages: VecDeque<(String, f32)>,
+    history: Vec<String>,
+    // Position while stepping back through `history` with the arrow keys.
+    history_index: Option<usize>,
+    completions: Vec<String>,
+}
+
+impl CommandLine {
+    pub(super) fn push_message(&mut self, message: String, now: f32) {
+        for line in message.lines() {
+            self.messages.push_back((line.to_string(), now));
+        }
+        while self.messages.len() > CHAT_SCROLLBACK {
+            self.messages.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArgKind {
+    Int,
+    Number,
+    // Any single token, for commands that interpret it themselves.
+    Word,
+    // Three tokens: x y z, each absolute, `~` relative or `^` local.
+    Position,
+    // An entity selector such as `@e[type=item]`.
+    Selector,
+    // Either a position or a selector.
+    Target,
+    // A block name, or `air` for no block.
+    Block,
+    Item,
+    Choice(&'static [&'static str]),
+}
+
+struct ArgSpec {
+    name: &'static str,
+    kind: ArgKind,
+    optional: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Arg {
+    Int(i64),
+    Number(f64),
+    Word(String),
+    Position(Coordinates),
+    Selector(Selector),
+    Block(Option<BlockKind>),
+    Item(ItemKind),
+}
+
+// The resources behind `CommandContext`, grouped to keep `command_line_input` under Bevy's parameter limit.
+#[derive(SystemParam)]
+pub(super) struct CommandResources<'w> {
+    world_time: ResMut<'w, WorldTime>,
+    weather: ResMut<'w, Weather>,
+    world: ResMut<'w, WorldBlocks>,
+    blocks: Res<'w, BlockRegistry>,
+    inventory: ResMut<'w, Inventory>,
+    seed: ResMut<'w, WorldSeed>,
+    game_mode: ResMut<'w, CurrentGameMode>,
+    selection: ResMut<'w, Selection>,
+    history: ResMut<'w, EditHistory>,
+}
+
+// Everything a command is allowed to touch.
+pub(super) struct CommandContext<'a> {
+    world_time: &'a mut WorldTime,
+    weather: &'a mut Weather,
+    world: &'a mut WorldBlocks,
+    blocks: &'a BlockRegistry,
+    inventory: &'a mut Inventory,
+    seed: &'a mut WorldSeed,
+    game_mode: &'a mut CurrentGameMode,
+    pub(super) selection: &'a mut Selection,
+    history: &'a mut EditHistory,
+    pub(super) player: &'a mut Transform,
+    velocity: &'a mut Vec3,
+    // Every entity a selector can pick, with its type name and position.
+    entities: &'a [(Entity, &'static str, Vec3)],
+    // Filled by commands that remove entities.
+    killed: Vec<Entity>,
+    sharing: WorldSharing,
+    // Every block written, so a hosted game can pass the edits on to its guests.
+    written: Vec<(IVec3, Option<BlockKind>)>,
+}
+
+// Whether anyone else sees this world, which limits what commands may do to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum WorldSharing {
+    #[default]
+    Local,
+    // Open to LAN: edits reach the guests, but they can't be sent a whole new world.
+    Hosting,
+    // Playing on someone else's server, which owns the world.
+    Joined,
+}
+
+impl CommandContext<'_> {
+    // Edits made here would never reach the server, so the world would silently drift from everyone else's.
+    fn check_editable(&self) -> Result<(), String> {
+        match self.sharing {
+            WorldSharing::Joined => Err("World edits aren't available on a server".to_string()),
+            WorldSharing::Local | WorldSharing::Hosting => Ok(()),
+        }
+    }
+
+    fn check_replaceable(&self) -> Result<(), String> {
+        match self.sharing {
+            WorldSharing::Hosting => Err("The world can't be replaced while it's open to LAN".to_string()),
+            _ => self.check_editable(),
+        }
+    }
+
+    fn write(&mut self, changes: impl Iterator<Item = (IVec3, Option<BlockKind>)>) {
+        let changes: Vec<_> = changes.collect();
+        apply_changes(self.world, self.blocks, changes.iter().copied());
+        self.written.extend(changes);
+    }
+
+    // Applies the changes as one undoable step, skipping blocks that already match. Returns how many changed.
+    fn edit(&mut self, changes: Vec<(IVec3, Option<BlockKind>)>) -> Result<usize, String> {
+        self.check_editable()?;
+        let changes: Vec<BlockChange> = changes
+            .into_iter()
+            .filter(|(pos, _)| (WORLD_MIN_Y..=WORLD_MAX_Y).contains(&pos.y))
+            .map(|(pos, after)| BlockChange { pos, before: self.world.get(pos), after })
+            .filter(|change| change.before != change.after)
+            .collect();
+        if changes.is_empty() {
+            return Ok(0);
+        }
+        self.write(changes.iter().map(|change| (change.pos, change.after)));
+        let count = changes.len();
+        self.history.undo.push(changes);
+        if self.history.undo.len() > EDIT_HISTORY_LIMIT {
+            self.history.undo.remove(0);
+        }
+        self.history.redo.clear();
+        Ok(count)
+    }
+
+    fn selected_region(&self) -> Result<(IVec3, IVec3), String> {
+        let (min, max) = self.selection.bounds().ok_or("Set both corners with /pos1 and /pos2 first")?;
+        clamp_region(min, max)
+    }
+
+    pub(super) fn resolve(&self, coordinates: &Coordinates) -> Vec3 {
+        let (yaw, pitch, _) = self.player.rotation.to_euler(EulerRot::YXZ);
+        coordinates.resolve(self.player.translation, yaw, pitch)
+    }
+
+    fn select(&self, selector: &Selector) -> Vec<(Entity, &'static str, Vec3)> {
+        selector.select(self.player.translation, self.entities)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Coordinate {
+    Absolute(f32),
+    // `~` or `~5`: an offset from the player along that world axis.
+    Relative(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Coordinates {
+    World([Coordinate; 3]),
+    // `^left ^up ^forward`, measured from where the player is looking.
+    Local(Vec3),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SelectorBase {
+    // `@p`: the nearest player.
+    Nearest,
+    // `@s`: whoever ran the command.
+    Executor,
+    // `@a`: every player.
+    Players,
+    // `@e`: every entity.
+    Entities,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Selector {
+    base: SelectorBase,
+    // The type name and whether it was negated with `type=!name`.
+    entity_type: Option<(String, bool)>,
+    // Inclusive bounds; either end may be open, as in `..5` or `2..`.
+    distance: Option<(Option<f32>, Option<f32>)>,
+    limit: Option<usize>,
+}
+
+struct CommandSpec {
+    name: &'static str,
+    description: &'static str,
+    args: Vec<ArgSpec>,
+    run: fn(&[Arg], &mut CommandContext) -> Result<String, String>,
+}
+
+impl CommandSpec {
+    fn usage(&self) -> String {
+        let mut usage = format!("/{}", self.name);
+        for arg in &self.args {
+            let name = match arg.kind {
+                ArgKind::Choice(choices) => choices.join("|"),
+                ArgKind::Position => format!("{} x y z", arg.name),
+                ArgKind::Target => format!("{} x y z|selector", arg.name),
+                _ => arg.name.to_string(),
+            };
+            if arg.optional {
+                usage.push_str(&format!(" [{name}]"));
+            } else {
+                usage.push_str(&format!(" <{name}>"));
+            }
+        }
+        usage
+    }
+}
+
+#[derive(Resource)]
+pub(super) struct CommandRegistry {
+    commands: Vec<CommandSpec>,
+}
+
+#[derive(Component)]
+pub(super) struct CommandLineUI;
+
+const CHAT_SCROLLBACK: usize = 100;
+
+const CHAT_VISIBLE_LINES: usize = 10;
+
+// Seconds a message stays on screen after the console is closed.
+const CHAT_FADE_TIME: f32 = 10.0;
+
+const SCHEMATIC_DIR: &str = "schematics";
+
+const IMPORT_DIR: &str = "imports";
+
+// Bounds of a Minecraft world, which keep `/import` arithmetic well inside i32.
+const MINECRAFT_WORLD_BORDER: i64 = 30_000_000;
+
+const MINECRAFT_HEIGHT_LIMIT: i64 = 2032;
+
+const EDIT_HISTORY_LIMIT: usize = 32;
+
+pub(super) fn open_command_line(
+    keyboard: Res<Input<KeyCode>>,
+    mut command_line: ResMut<CommandLine>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut windows: Query<&mut Window>,
+) {
+    let text = if keyboard.just_pressed(KeyCode::Slash) {
+        "/"
+    } else if keyboard.just_pressed(KeyCode::T) {
+        ""
+    } else {
+        return;
+    };
+    command_line.text = text.to_string();
+    command_line.history_index = None;
+    command_line.completions.clear();
+    let mut window = windows.single_mut();
+    window.cursor.visible = true;
+    window.cursor.grab_mode = CursorGrabMode::None;
+    next_state.set(GameState::Console);
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(super) fn command_line_input(
+    mut chars: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+    mut command_line: ResMut<CommandLine>,
+    command_registry: Res<CommandRegistry>,
+    mut resources: CommandResources,
+    mut player_query: Query<(Entity, &mut Transform, &mut Velocity), With<Player>>,
+    item_query: Query<(Entity, &Transform), (With<DroppedItem>, Without<Player>)>,
+    mut windows: Query<&mut Window>,
+    client: Option<Res<NetworkClient>>,
+    server: Option<Res<NetworkServer>>,
+    mut edits: EventWriter<BlockEdited>,
+    time: Res<Time>,
+) {
+    if *state.get() != GameState::Console {
+        chars.clear();
+        return;
+    }
+
+    for ev in chars.read() {
+        if !ev.char.is_control() {
+            command_line.text.push(ev.char);
+            command_line.completions.clear();
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        command_line.text.pop();
+        command_line.completions.clear();
+    }
+
+    if keyboard.just_pressed(KeyCode::Up) && !command_line.history.is_empty() {
+        let index = command_line.history_index.map_or(command_line.history.len() - 1, |i| i.saturating_sub(1));
+        command_line.history_index = Some(index);
+        command_line.text = command_line.history[index].clone();
+    }
+    if keyboard.just_pressed(KeyCode::Down) {
+        if let Some(index) = command_line.history_index {
+            if index + 1 < command_line.history.len() {
+                command_line.history_index = Some(index + 1);
+                command_line.text = command_line.history[index + 1].clone();
+            } else {
+                command_line.history_index = None;
+                command_line.text.clear();
+            }
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::Tab) {
+        let (text, completions) = command_registry.complete(&command_line.text);
+        command_line.text = text;
+        command_line.completions = completions;
+    }
+
+    if keyboard.just_pressed(KeyCode::Return) {
+        let text = std::mem::take(&mut command_line.text);
+        let now = time.elapsed_seconds();
+        if !text.trim().is_empty() {
+            command_line.history.push(text.clone());
+        }
+        command_line.history_index = None;
+        command_line.completions.clear();
+
+        if text.starts_with('/') {
+            let (player, mut transform, mut velocity) = player_query.single_mut();
+            let entities: Vec<(Entity, &'static str, Vec3)> = std::iter::once((player, "player", transform.translation))
+                .chain(item_query.iter().map(|(entity, item)| (entity, "item", item.translation)))
+                .collect();
+            let mut context = CommandContext {
+                world_time: &mut resources.world_time,
+                weather: &mut resources.weather,
+                world: &mut resources.world,
+                blocks: &resources.blocks,
+                inventory: &mut resources.inventory,
+                seed: &mut resources.seed,
+                game_mode: &mut resources.game_mode,
+                selection: &mut resources.selection,
+                history: &mut resources.history,
+                player: &mut transform,
+                velocity: &mut velocity.0,
+                entities: &entities,
+                killed: Vec::new(),
+                sharing: match (&client, &server) {
+                    (Some(_), _) => WorldSharing::Joined,
+                    (None, Some(_)) => WorldSharing::Hosting,
+                    (None, None) => WorldSharing::Local,
+                },
+                written: Vec::new(),
+            };
+            let reply = command_registry.run(&text, &mut context).unwrap_or_else(|message| message);
+            for entity in context.killed {
+                commands.entity(entity).despawn_recursive();
+            }
+            edits.send_batch(context.written.into_iter().map(|(pos, kind)| BlockEdited { pos, kind }));
+            command_line.push_message(reply, now);
+        } else if !text.trim().is_empty() {
+            command_line.push_message(format!("<Player> {}", text.trim()), now);
+        }
+
+        let mut window = windows.single_mut();
+        window.cursor.visible = false;
+        window.cursor.grab_mode = CursorGrabMode::Locked;
+        next_state.set(GameState::Playing);
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        fn arg(name: &'static str, kind: ArgKind) -> ArgSpec {
+            ArgSpec { name, kind, optional: false }
+        }
+        fn optional(name: &'static str, kind: ArgKind) -> ArgSpec {
+            ArgSpec { name, kind, optional: true }
+        }
+
+        let commands = vec![
+            CommandSpec {
+                name: "help",
+                description: "Lists commands",
+                args: Vec::new(),
+                run: |_, _| Err("help is handled by the registry".to_string()),
+            },
+            CommandSpec {
+                name: "tp",
+                description: "Teleports the player to a position or entity",
+                args: vec![arg("destination", ArgKind::Target)],
+                run: |args, context| {
+                    let destination = match args {
+                        [Arg::Position(coordinates)] => context.resolve(coordinates),
+                        [Arg::Selector(selector)] => match context.select(selector).first() {
+                            Some(&(_, _, position)) => position,
+                            None => return Err("No entity was found".to_string()),
+                        },
+                        _ => unreachable!(),
+                    };
+                    context.player.translation = destination;
+                    *context.velocity = Vec3::ZERO;
+                    Ok(format!("Teleported to {:.1} {:.1} {:.1}", destination.x, destination.y, destination.z))
+                },
+            },
+            CommandSpec {
+                name: "kill",
+                description: "Removes entities",
+                args: vec![arg("targets", ArgKind::Selector)],
+                run: |args, context| {
+                    let [Arg::Selector(selector)] = args else { unreachable!() };
+                    let removed: Vec<Entity> = context
+                        .select(selector)
+                        .into_iter()
+                        .filter(|&(_, kind, _)| kind != "player")
+                        .map(|(entity, _, _)| entity)
+                        .collect();
+                    if removed.is_empty() {
+                        return Err("No entity was found".to_string());
+                    }
+                    let count = removed.len();
+                    context.killed.extend(removed);
+                    Ok(format!("Removed {count} entities"))
+                },
+            },
+            CommandSpec {
+                name: "seed",
+                description: "Shows the world seed",
+                args: Vec::new(),
+                run: |_, context| Ok(format!("Seed: {}", context.seed.0)),
+            },
+            CommandSpec {
+                name: "gamemode",
+                description: "Switches between survival and creative",
+                args: vec![arg("mode", ArgKind::Choice(&["survival", "creative"]))],
+                run: |args, context| {
+                    let [Arg::Word(mode)] = args else { unreachable!() };
+                    context.game_mode.0 = mode.parse().map_err(|_| format!("Unknown game mode: {mode}"))?;
+                    Ok(format!("Set game mode to {mode}"))
+                },
+            },
+            CommandSpec {
+                name: "give",
+                description: "Puts items in the inventory",
+                args: vec![arg("item", ArgKind::Item), optional("count", ArgKind::Int)],
+                run: |args, context| {
+                    let (item, count) = match args {
+                        [Arg::Item(item)] => (*item, 1),
+                        [Arg::Item(item), Arg::Int(count)] => (*item, *count),
+                        _ => unreachable!(),
+                    };
+                    if !(1..=(INVENTORY_SIZE as i64 * item.max_stack() as i64)).contains(&count) {
+                        return Err(format!("Invalid count: {count}"));
+                    }
+                    let mut left = count as u32;
+                    while left > 0 {
+                        let batch = left.min(item.max_stack());
+                        let rest = context.inventory.add(ItemStack::new(item, batch)).map_or(0, |stack| stack.count);
+                        left -= batch - rest;
+                        if rest > 0 {
+                            return Err(format!("Inventory full; gave {} {}", count as u32 - left, enum_name(&item)));
+                        }
+                    }
+                    Ok(format!("Gave {count} {}", enum_name(&item)))
+                },
+            },
+            CommandSpec {
+                name: "time",
+                description: "Sets or shows the time of day",
+                args: vec![arg("action", ArgKind::Choice(&["set", "add", "query"])), optional("value", ArgKind::Word)],
+                run: |args, context| {
+                    let ticks_of = |value: &str| match value {
+                        "day" => Ok(1000.0),
+                        "noon" => Ok(6000.0),
+                        "night" => Ok(13000.0),
+                        "midnight" => Ok(18000.0),
+                        value => value.parse::<f64>().map_err(|_| format!("Invalid time: {value}")),
+                    };
+                    match args {
+                        [Arg::Word(action), Arg::Word(value)] if action == "set" => {
+                            let ticks = ticks_of(value)?;
+                            let day_start = (context.world_time.ticks / TICKS_PER_DAY).floor() * TICKS_PER_DAY;
+                            context.world_time.ticks = day_start + ticks.rem_euclid(TICKS_PER_DAY);
+                            Ok(format!("Set the time to {}", ticks))
+                        }
+                        [Arg::Word(action), Arg::Word(value)] if action == "add" => {
+                            let ticks = value.parse::<f64>().map_err(|_| format!("Invalid time: {value}"))?;
+                            context.world_time.ticks += ticks;
+                            Ok(format!("Added {} ticks", ticks))
+                        }
+                        [Arg::Word(action)] if action == "query" => Ok(format!(
+                            "The time is {:.0}",
+                            context.world_time.ticks.rem_euclid(TICKS_PER_DAY)
+                        )),
+                        _ => Err("Usage: /time set <day|noon|night|midnight|ticks>, /time add <ticks> or /time query".to_string()),
+                    }
+                },
+            },
+            CommandSpec {
+                name: "weather",
+                description: "Changes the weather",
+                args: vec![arg("weather", ArgKind::Choice(&["clear", "rain", "thunder"])), optional("seconds", ArgKind::Number)],
+                run: |args, context| {
+                    let (kind, seconds) = match args {
+                        [Arg::Word(kind)] => (kind, None),
+                        [Arg::Word(kind), Arg::Number(seconds)] => (kind, Some(*seconds)),
+                        _ => unreachable!(),
+                    };
+                    let kind: WeatherKind = kind.parse().map_err(|_| format!("Unknown weather: {kind}"))?;
+                    if seconds.is_some_and(|seconds| seconds <= 0.0) {
+                        return Err("The duration must be positive".to_string());
+                    }
+                    context.weather.kind = kind;
+                    context.weather.remaining = match seconds {
+                        Some(seconds) => seconds * TICKS_PER_SECOND,
+                        None => weather_duration(kind, random()),
+                    };
+                    Ok(format!("Set the weather to {:?}", kind))
+                },
+            },
+            CommandSpec {
+                name: "setblock",
+                description: "Places or removes a single block",
+                args: vec![arg("position", ArgKind::Position), arg("block", ArgKind::Block)],
+                run: |args, context| {
+                    let [Arg::Position(pos), Arg::Block(kind)] = args else { unreachable!() };
+                    let pos = context.resolve(pos).round().as_ivec3();
+                    if !(WORLD_MIN_Y..=WORLD_MAX_Y).contains(&pos.y) {
+                        return Err(format!("Y must be between {WORLD_MIN_Y} and {WORLD_MAX_Y}"));
+                    }
+                    context.edit(vec![(pos, *kind)])?;
+                    Ok(format!("Set block at {} {} {}", pos.x, pos.y, pos.z))
+                },
+            },
+            CommandSpec {
+                name: "fill",
+                description: "Fills a box with one block",
+                args: vec![arg("from", ArgKind::Position), arg("to", ArgKind::Position), arg("block", ArgKind::Block)],
+                run: |args, context| {
+                    let [Arg::Position(from), Arg::Position(to), Arg::Block(kind)] = args else { unreachable!() };
+                    let (from, to) = (context.resolve(from).round().as_ivec3(), context.resolve(to).round().as_ivec3());
+                    let (min, max) = clamp_region(from.min(to), from.max(to))?;
+                    let changed = context.edit(region_positions(min, max).map(|pos| (pos, *kind)).collect())?;
+                    Ok(format!("Filled {changed} blocks"))
+                },
+            },
+            CommandSpec {
+                name: "pos1",
+                description: "Sets the first selection corner, at the player by default",
+                args: vec![optional("position", ArgKind::Position)],
+                run: |args, context| set_corner(args, context, 0),
+            },
+            CommandSpec {
+                name: "pos2",
+                description: "Sets the second selection corner, at the player by default",
+                args: vec![optional("position", ArgKind::Position)],
+                run: |args, context| set_corner(args, context, 1),
+            },
+            CommandSpec {
+                name: "desel",
+                description: "Clears the selection",
+                args: Vec::new(),
+                run: |_, context| {
+                    *context.selection = Selection::default();
+                    Ok("Cleared the selection".to_string())
+                },
+            },
+            CommandSpec {
+                name: "set",
+                description: "Fills the selection with one block",
+                args: vec![arg("block", ArgKind::Block)],
+                run: |args, context| {
+                    let [Arg::Block(kind)] = args else { unreachable!() };
+                    let (min, max) = context.selected_region()?;
+                    let changed = context.edit(region_positions(min, max).map(|pos| (pos, *kind)).collect())?;
+                    Ok(format!("Set {changed} blocks"))
+                },
+            },
+            CommandSpec {
+                name: "replace",
+                description: "Replaces one block with another inside the selection",
+                args: vec![arg("from", ArgKind::Block), arg("to", ArgKind::Block)],
+                run: |args, context| {
+                    let [Arg::Block(from), Arg::Block(to)] = args else { unreachable!() };
+                    let (min, max) = context.selected_region()?;
+                    let changes = region_positions(min, max)
+                        .filter(|&pos| context.world.get(pos) == *from)
+                        .map(|pos| (pos, *to))
+                        .collect();
+                    let changed = context.edit(changes)?;
+                    Ok(format!("Replaced {changed} blocks"))
+                },
+            },
+            CommandSpec {
+                name: "hollow",
+                description: "Turns the selection into a shell of one block with air inside",
+                args: vec![arg("block", ArgKind::Block)],
+                run: |args, context| {
+                    let [Arg::Block(kind)] = args else { unreachable!() };
+                    let (min, max) = context.selected_region()?;
+                    let changes = region_positions(min, max)
+                        .map(|pos| {
+                            let on_shell = (0..3).any(|axis| pos[axis] == min[axis] || pos[axis] == max[axis]);
+                            (pos, if on_shell { *kind } else { None })
+                        })
+                        .collect();
+                    let changed = context.edit(changes)?;
+                    Ok(format!("Hollowed the selection, changing {changed} blocks"))
+                },
+            },
+            CommandSpec {
+                name: "clone",
+                description: "Copies the selection so its lowest corner lands on the destination",
+                args: vec![
+                    arg("destination", ArgKind::Position),
+                    optional("rotation", ArgKind::Choice(&["0", "90", "180", "270"])),
+                    optional("mirror", ArgKind::Choice(&["none", "x", "z"])),
+                ],
+                run: |args, context| {
+                    let (destination, rotation, mirror) = match args {
+                        [Arg::Position(destination)] => (destination, "0", "none"),
+                        [Arg::Position(destination), Arg::Word(rotation)] => (destination, rotation.as_str(), "none"),
+                        [Arg::Position(destination), Arg::Word(rotation), Arg::Word(mirror)] => {
+                            (destination, rotation.as_str(), mirror.as_str())
+                        }
+                        _ => unreachable!(),
+                    };
+                    let quarter_turns = rotation.parse::<u32>().map_err(|_| format!("Invalid rotation: {rotation}"))? / 90;
+                    let mirror: Mirror = mirror.parse().map_err(|_| format!("Invalid mirror: {mirror}"))?;
+                    let destination = context.resolve(destination).round().as_ivec3();
+                    let (min, max) = context.selected_region()?;
+                    let changes = clone_region(context.world, min, max, destination, quarter_turns, mirror)?;
+                    let changed = context.edit(changes)?;
+                    Ok(format!("Cloned the selection, changing {changed} blocks"))
+                },
+            },
+            CommandSpec {
+                name: "schem",
+                description: "Saves the selection to a schematic or pastes one at a position",
+                args: vec![
+                    arg("action", ArgKind::Choice(&["save", "load"])),
+                    arg("name", ArgKind::Word),
+                    optional("position", ArgKind::Position),
+                ],
+                run: |args, context| {
+                    let (action, name, position) = match args {
+                        [Arg::Word(action), Arg::Word(name)] => (action, name, None),
+                        [Arg::Word(action), Arg::Word(name), Arg::Position(position)] => (action, name, Some(position)),
+                        _ => unreachable!(),
+                    };
+                    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        return Err(format!("Invalid schematic name: {name}"));
+                    }
+                    let path = Path::new(SCHEMATIC_DIR).join(format!("{name}.schem"));
+                    if action == "save" {
+                        let (min, max) = context.selected_region()?;
+                        let bytes = nbt::write_gzip("Schematic", &copy_schematic(context.world, min, max).to_nbt())
+                            .map_err(|err| format!("Could not encode schematic: {err}"))?;
+                        std::fs::create_dir_all(SCHEMATIC_DIR)
+                            .and_then(|_| std::fs::write(&path, bytes))
+                            .map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+                        return Ok(format!("Saved {}", path.display()));
+                    }
+
+                    let bytes = std::fs::read(&path).map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+                    let (_, root) = nbt::read_compressed(&bytes).map_err(|err| format!("Invalid schematic: {err}"))?;
+                    let (schematic, unknown) = Schematic::from_nbt(&root)?;
+                    let origin = match position {
+                        Some(position) => context.resolve(position),
+                        None => context.player.translation,
+                    }
+                    .round()
+                    .as_ivec3();
+                    region_at(origin, schematic.size)?;
+                    let changes = schematic.positions().map(|(offset, kind)| (origin + offset, kind)).collect();
+                    let changed = context.edit(changes)?;
+                    let mut message = format!("Pasted {name}, changing {changed} blocks");
+                    if unknown > 0 {
+                        message.push_str(&format!(" ({unknown} unknown blocks became air)"));
+                    }
+                    Ok(message)
+                },
+            },
+            CommandSpec {
+                name: "import",
+                description: "Replaces the world with part of a Minecraft Java world, centred on the given Minecraft position",
+                args: vec![
+                    arg("world", ArgKind::Word),
+                    arg("x", ArgKind::Int),
+                    arg("y", ArgKind::Int),
+                    arg("z", ArgKind::Int),
+                    optional("fallback", ArgKind::Block),
+                ],
+                run: |args, context| {
+                    let (name, center, fallback) = match args {
+                        [Arg::Word(name), Arg::Int(x), Arg::Int(y), Arg::Int(z), rest @ ..] => {
+                            let fallback = match rest {
+                                [Arg::Block(kind)] => *kind,
+                                _ => None,
+                            };
+                            (name, [*x, *y, *z], fallback)
+                        }
+                        _ => unreachable!(),
+                    };
+                    let [x, y, z] = center;
+                    if x.abs() > MINECRAFT_WORLD_BORDER || z.abs() > MINECRAFT_WORLD_BORDER {
+                        return Err(format!("X and Z must be between -{MINECRAFT_WORLD_BORDER} and {MINECRAFT_WORLD_BORDER}"));
+                    }
+                    if !(-MINECRAFT_HEIGHT_LIMIT..=MINECRAFT_HEIGHT_LIMIT).contains(&y) {
+                        return Err(format!("Y must be between -{MINECRAFT_HEIGHT_LIMIT} and {MINECRAFT_HEIGHT_LIMIT}"));
+                    }
+                    let center = IVec3::new(x as i32, y as i32, z as i32);
+                    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        return Err(format!("Invalid world name: {name}"));
+                    }
+                    context.check_replaceable()?;
+                    let region_dir = Path::new(IMPORT_DIR).join(name).join("region");
+                    let (chunks, unknown) = import_anvil(context.world, context.blocks, &region_dir, center, fallback)?;
+                    *context.history = EditHistory::default();
+                    let mut message = format!("Imported {chunks} chunks from {name}");
+                    if unknown > 0 {
+                        message.push_str(&format!(" ({unknown} unknown blocks became {})", block_id(fallback)));
+                    }
+                    Ok(message)
+                },
+            },
+            CommandSpec {
+                name: "undo",
+                description: "Reverts the last edit",
+                args: Vec::new(),
+                run: |_, context| {
+                    context.check_editable()?;
+                    let changes = context.history.undo.pop().ok_or("Nothing to undo")?;
+                    context.write(changes.iter().map(|change| (change.pos, change.before)));
+                    let count = changes.len();
+                    context.history.redo.push(changes);
+                    Ok(format!("Undid {count} block changes"))
+                },
+            },
+            CommandSpec {
+                name: "redo",
+                description: "Reapplies the last undone edit",
+                args: Vec::new(),
+                run: |_, context| {
+                    context.check_editable()?;
+                    let changes = context.history.redo.pop().ok_or("Nothing to redo")?;
+                    context.write(changes.iter().map(|change| (change.pos, change.after)));
+                    let count = changes.len();
+                    context.history.undo.push(changes);
+                    Ok(format!("Redid {count} block changes"))
+                },
+            },
+            CommandSpec {
+                name: "regen",
+                description: "Regenerates the world from a new or given seed",
+                args: vec![optional("seed", ArgKind::Int)],
+                run: |args, context| {
+                    let seed = match args {
+                        [Arg::Int(seed)] => u32::try_from(*seed).map_err(|_| format!("Invalid seed: {seed}"))?,
+                        _ => random(),
+                    };
+                    context.check_replaceable()?;
+                    regenerate_world(context.world, context.blocks, seed);
+                    // Undoing would write the old world's blocks into the new one.
+                    *context.history = EditHistory::default();
+                    *context.seed = WorldSeed(seed);
+                    Ok(format!("Regenerated the world with seed {seed}"))
+                },
+            },
+        ];
+        Self { commands }
+    }
+}
+
+impl CommandRegistry {
+    fn get(&self, name: &str) -> Option<&CommandSpec> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    fn run(&self, text: &str, context: &mut CommandContext) -> Result<String, String> {
+        let tokens = tokenize(text.trim().trim_start_matches('/'));
+        let Some((&name, tokens)) = tokens.split_first() else {
+            return Err("Empty command".to_string());
+        };
+        if name == "help" {
+            let lines: Vec<String> = self
+                .commands
+                .iter()
+                .map(|command| format!("{} - {}", command.usage(), command.description))
+                .collect();
+            return Ok(lines.join("\n"));
+        }
+        let command = self.get(name).ok_or_else(|| format!("Unknown command: {name}"))?;
+        let args = parse_args(&command.args, tokens)
+            .map_err(|message| format!("{message}\nUsage: {}", command.usage()))?;
+        (command.run)(&args, context)
+    }
+
+    // Completes the last word of `text`. Returns the new text and, when the word is ambiguous, every candidate.
+    fn complete(&self, text: &str) -> (String, Vec<String>) {
+        let Some(command_text) = text.strip_prefix('/') else {
+            return (text.to_string(), Vec::new());
+        };
+        let mut tokens: Vec<&str> = command_text.split(' ').collect();
+        let partial = tokens.pop().unwrap_or_default();
+
+        let candidates: Vec<String> = if tokens.is_empty() {
+            self.commands.iter().map(|command| command.name.to_string()).collect()
+        } else {
+            match self.get(tokens[0]).and_then(|command| arg_at(&command.args, tokens.len() - 1)) {
+                Some(ArgKind::Choice(choices)) => choices.iter().map(|choice| choice.to_string()).collect(),
+                Some(ArgKind::Block) => std::iter::once("air".to_string())
+                    .chain(BlockKind::iter().map(|kind| enum_name(&kind)))
+                    .collect(),
+                Some(ArgKind::Item) => ItemKind::iter().map(|kind| enum_name(&kind)).collect(),
+                Some(ArgKind::Selector | ArgKind::Target) => ["@p", "@s", "@a", "@e"].map(String::from).to_vec(),
+                _ => Vec::new(),
+            }
+        };
+        let matches: Vec<String> = candidates.into_iter().filter(|candidate| candidate.starts_with(partial)).collect();
+
+        let completed = match matches.as_slice() {
+            [] => partial.to_string(),
+            [only] => format!("{only} "),
+            [first, rest @ ..] => {
+                let mut prefix = first.clone();
+                for candidate in rest {
+                    while !candidate.starts_with(&prefix) {
+                        prefix.pop();
+                    }
+                }
+                prefix
+            }
+        };
+        tokens.push(&completed);
+        let shown = if matches.len() > 1 { matches } else { Vec::new() };
+        (format!("/{}", tokens.join(" ")), shown)
+    }
+}
+
+// The argument that the token at `index` belongs to; positions take three tokens.
+fn arg_at(specs: &[ArgSpec], mut index: usize) -> Option<ArgKind> {
+    for spec in specs {
+        let width = if matches!(spec.kind, ArgKind::Position | ArgKind::Target) { 3 } else { 1 };
+        if index < width {
+            return Some(spec.kind);
+        }
+        index -= width;
+    }
+    None
+}
+
+fn parse_args(specs: &[ArgSpec], tokens: &[&str]) -> Result<Vec<Arg>, String> {
+    let mut args = Vec::new();
+    let mut rest = tokens;
+    for spec in specs {
+        if rest.is_empty() {
+            if spec.optional {
+                break;
+            }
+            return Err(format!("Missing argument: {}", spec.name));
+        }
+        let arg = match spec.kind {
+            ArgKind::Int => Arg::Int(rest[0].parse().map_err(|_| format!("Expected a whole number for {}: {}", spec.name, rest[0]))?),
+            ArgKind::Number => Arg::Number(
+                parse_number(rest[0]).map_err(|_| format!("Expected a number for {}: {}", spec.name, rest[0]))? as f64,
+            ),
+            ArgKind::Word => Arg::Word(rest[0].to_string()),
+            ArgKind::Choice(choices) => {
+                if !choices.contains(&rest[0]) {
+                    return Err(format!("Expected one of {} for {}: {}", choices.join(", "), spec.name, rest[0]));
+                }
+                Arg::Word(rest[0].to_string())
+            }
+            ArgKind::Block if rest[0] == "air" => Arg::Block(None),
+            ArgKind::Block => Arg::Block(Some(parse_enum_name(rest[0]).ok_or_else(|| format!("Unknown block: {}", rest[0]))?)),
+            ArgKind::Item => Arg::Item(parse_enum_name(rest[0]).ok_or_else(|| format!("Unknown item: {}", rest[0]))?),
+            ArgKind::Selector | ArgKind::Target if rest[0].starts_with('@') => Arg::Selector(parse_selector(rest[0])?),
+            ArgKind::Selector => return Err(format!("Expected a selector for {}: {}", spec.name, rest[0])),
+            ArgKind::Position | ArgKind::Target => {
+                let [x, y, z, ..] = rest else {
+                    return Err(format!("Expected x y z for {}", spec.name));
+                };
+                rest = &rest[2..];
+                Arg::Position(parse_coordinates([x, y, z])?)
+            }
+        };
+        args.push(arg);
+        rest = &rest[1..];
+    }
+    if let Some(extra) = rest.first() {
+        return Err(format!("Unexpected argument: {extra}"));
+    }
+    Ok(args)
+}
+
+// Splits on whitespace, except inside selector brackets, so `@e[type=item, limit=1]` stays one token.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            c if c.is_whitespace() && depth <= 0 => {
+                if let Some(begin) = start.take() {
+                    tokens.push(&text[begin..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(begin) = start {
+        tokens.push(&text[begin..]);
+    }
+    tokens
+}
+
+fn parse_number(text: &str) -> Result<f32, String> {
+    text.parse::<f32>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| format!("Invalid number: {text}"))
+}
+
+// An empty offset, as in a bare `~`, means no offset.
+fn parse_offset(text: &str) -> Result<f32, String> {
+    if text.is_empty() {
+        Ok(0.0)
+    } else {
+        parse_number(text)
+    }
+}
+
+fn parse_coordinates(tokens: [&str; 3]) -> Result<Coordinates, String> {
+    let local = tokens.iter().filter(|token| token.starts_with('^')).count();
+    if local == 3 {
+        let mut offset = Vec3::ZERO;
+        for (axis, token) in tokens.iter().enumerate() {
+            offset[axis] = parse_offset(&token[1..])?;
+        }
+        return Ok(Coordinates::Local(offset));
+    }
+    if local > 0 {
+        return Err("Cannot mix ^ with world coordinates".to_string());
+    }
+
+    let mut axes = [Coordinate::Absolute(0.0); 3];
+    for (axis, token) in tokens.iter().enumerate() {
+        axes[axis] = match token.strip_prefix('~') {
+            Some(offset) => Coordinate::Relative(parse_offset(offset)?),
+            None => Coordinate::Absolute(parse_number(token)?),
+        };
+    }
+    Ok(Coordinates::World(axes))
+}
+
+impl Coordinates {
+    fn resolve(&self, origin: Vec3, yaw: f32, pitch: f32) -> Vec3 {
+        match self {
+            Coordinates::World(axes) => Vec3::from_array(std::array::from_fn(|axis| match axes[axis] {
+                Coordinate::Absolute(value) => value,
+                Coordinate::Relative(offset) => origin[axis] + offset,
+            })),
+            Coordinates::Local(offset) => {
+                let rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0);
+                origin + rotation * Vec3::new(-offset.x, offset.y, -offset.z)
+            }
+        }
+    }
+}
+
+// `5` is exactly 5, `..5` at most 5, `2..` at least 2 and `2..5` between them.
+fn parse_range(text: &str) -> Result<(Option<f32>, Option<f32>), String> {
+    let bound = |text: &str| if text.is_empty() { Ok(None) } else { parse_number(text).map(Some) };
+    let (min, max) = match text.split_once("..") {
+        Some((min, max)) => (bound(min)?, bound(max)?),
+        None => {
+            let value = parse_number(text)?;
+            (Some(value), Some(value))
+        }
+    };
+    match (min, max) {
+        (None, None) => Err(format!("Invalid range: {text}")),
+        (Some(min), Some(max)) if min > max => Err(format!("Range minimum is above its maximum: {text}")),
+        range => Ok(range),
+    }
+}
+
+fn parse_selector(text: &str) -> Result<Selector, String> {
+    let (head, filters) = match text.split_once('[') {
+        Some((head, rest)) => {
+            let filters = rest.strip_suffix(']').ok_or_else(|| format!("Unclosed selector: {text}"))?;
+            (head, Some(filters))
+        }
+        None => (text, None),
+    };
+    let base = match head {
+        "@p" => SelectorBase::Nearest,
+        "@s" => SelectorBase::Executor,
+        "@a" => SelectorBase::Players,
+        "@e" => SelectorBase::Entities,
+        _ => return Err(format!("Unknown selector: {head}")),
+    };
+    let mut selector = Selector {
+        base,
+        entity_type: None,
+        distance: None,
+        limit: None,
+    };
+
+    for filter in filters.into_iter().flat_map(|filters| filters.split(',')) {
+        let filter = filter.trim();
+        if filter.is_empty() {
+            continue;
+        }
+        let (key, value) = filter.split_once('=').ok_or_else(|| format!("Expected key=value in selector: {filter}"))?;
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "type" if selector.entity_type.is_some() => return Err("Duplicate selector filter: type".to_string()),
+            "type" => {
+                let (name, negated) = match value.strip_prefix('!') {
+                    Some(name) => (name, true),
+                    None => (value, false),
+                };
+                if name.is_empty() {
+                    return Err("Missing entity type".to_string());
+                }
+                selector.entity_type = Some((name.to_string(), negated));
+            }
+            "distance" => selector.distance = Some(parse_range(value)?),
+            "limit" => {
+                let limit = value.parse().ok().filter(|&limit| limit > 0).ok_or_else(|| format!("Invalid limit: {value}"))?;
+                selector.limit = Some(limit);
+            }
+            _ => return Err(format!("Unknown selector filter: {key}")),
+        }
+    }
+    Ok(selector)
+}
+
+impl Selector {
+    // Matches against `(id, type name, position)` and returns the hits nearest first.
+    fn select<T: Copy>(&self, origin: Vec3, candidates: &[(T, &'static str, Vec3)]) -> Vec<(T, &'static str, Vec3)> {
+        let mut hits: Vec<(T, &'static str, Vec3)> = candidates
+            .iter()
+            .copied()
+            .filter(|&(_, kind, position)| {
+                let players_only = self.base != SelectorBase::Entities;
+                let type_matches = match &self.entity_type {
+                    Some((name, negated)) => (kind == name) != *negated,
+                    None => true,
+                };
+                let distance = origin.distance(position);
+                let in_range = self.distance.is_none_or(|(min, max)| {
+                    min.is_none_or(|min| distance >= min) && max.is_none_or(|max| distance <= max)
+                });
+                (!players_only || kind == "player") && type_matches && in_range
+            })
+            .collect();
+        hits.sort_by(|a, b| origin.distance_squared(a.2).total_cmp(&origin.distance_squared(b.2)));
+
+        let limit = match self.base {
+            SelectorBase::Nearest | SelectorBase::Executor => Some(1),
+            SelectorBase::Players | SelectorBase::Entities => self.limit,
+        };
+        if let Some(limit) = limit {
+            hits.truncate(limit);
+        }
+        hits
+    }
+}
+
+// `CraftingTable` becomes `crafting_table`.
+fn enum_name(value: &impl std::fmt::Debug) -> String {
+    let mut name = String::new();
+    for (i, c) in format!("{value:?}").chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+fn parse_enum_name<T: IntoEnumIterator + std::fmt::Debug>(name: &str) -> Option<T> {
+    T::iter().find(|value| enum_name(value) == name)
+}
+
+pub(super) fn command_line_display(
+    mut commands: Commands,
+    command_line: Res<CommandLine>,
+    state: Res<State<GameState>>,
+    time: Res<Time>,
+    existing_display: Query<Entity, With<CommandLineUI>>,
+) {
+    for entity in existing_display.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let open = *state.get() == GameState::Console;
+    let now = time.elapsed_seconds();
+    let mut lines: Vec<String> = command_line
+        .messages
+        .iter()
+        .filter(|(_, added)| open || now - added < CHAT_FADE_TIME)
+        .map(|(line, _)| line.clone())
+        .collect();
+    lines.drain(..lines.len().saturating_sub(CHAT_VISIBLE_LINES));
+    if open {
+        if !command_line.completions.is_empty() {
+            lines.push(command_line.completions.join("  "));
+        }
+        lines.push(format!("{}_", command_line.text));
+    }
+    if lines.is_empty() {
+        return;
+    }
+
+    commands.spawn((
+        TextBundle::from_section(
+            lines.join("\n"),
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(90.0),
+            left: Val::Px(10.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.5)),
+        CommandLineUI,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestCommands {
+        world: WorldBlocks,
+        weather: Weather,
+        player: Transform,
+        selection: Selection,
+        history: EditHistory,
+        sharing: WorldSharing,
+    }
+
+    impl TestCommands {
+        fn run(&mut self, text: &str) -> Result<String, String> {
+            let blocks = BlockRegistry::default();
+            let mut context = CommandContext {
+                world_time: &mut WorldTime::default(),
+                weather: &mut self.weather,
+                world: &mut self.world,
+                blocks: &blocks,
+                inventory: &mut Inventory::default(),
+                seed: &mut WorldSeed::default(),
+                game_mode: &mut CurrentGameMode::default(),
+                selection: &mut self.selection,
+                history: &mut self.history,
+                player: &mut self.player,
+                velocity: &mut Vec3::default(),
+                entities: &[],
+                killed: Vec::new(),
+                sharing: self.sharing,
+                written: Vec::new(),
+            };
+            CommandRegistry::default().run(text, &mut context)
+        }
+    }
+
+    #[test]
+    fn import_rejects_positions_outside_the_world() {
+        let mut commands = TestCommands::default();
+        assert!(commands.run("/import fixture 3000000000 64 0").unwrap_err().starts_with("X and Z"));
+        assert!(commands.run("/import fixture 0 -5000 0").unwrap_err().starts_with("Y must"));
+    }
+
+    #[test]
+    fn weather_command_sets_state() {
+        let mut commands = TestCommands::default();
+        assert!(commands.run("/weather thunder 30").is_ok());
+        assert_eq!(commands.weather.kind, WeatherKind::Thunder);
+        assert_eq!(commands.weather.remaining, 600.0);
+        assert!(commands.run("/weather hail").is_err());
+        assert!(commands.run("/weather").is_err());
+        // Durations must be finite and positive, or the weather would never change again.
+        for duration in ["nan", "inf", "-5", "0"] {
+            assert!(commands.run(&format!("/weather rain {duration}")).is_err(), "{duration}");
+        }
+        assert_eq!(commands.weather.kind, WeatherKind::Thunder);
+        assert_eq!(commands.weather.remaining, 600.0);
+    }
+
+    #[test]
+    fn commands_parse_typed_arguments() {
+        let mut commands = TestCommands::default();
+        assert!(commands.run("/tp 1 2 3").is_ok());
+        assert_eq!(commands.player.translation, Vec3::new(1.0, 2.0, 3.0));
+        assert!(commands.run("/tp 1 2").is_err());
+        assert!(commands.run("/tp 1 2 3 4").is_err());
+
+        assert!(commands.run("/setblock 0 0 0 crafting_table").is_ok());
+        assert_eq!(commands.world.get(IVec3::ZERO), Some(BlockKind::CraftingTable));
+        assert!(commands.run("/fill 0 0 0 2 1 2 stone").is_ok());
+        assert_eq!(commands.world.get(IVec3::new(2, 1, 2)), Some(BlockKind::Stone));
+        assert!(commands.run("/fill 0 0 0 1 1 1 air").is_ok());
+        assert_eq!(commands.world.get(IVec3::ONE), None);
+        assert!(commands.run("/setblock 0 0 0 bedrock").is_err());
+        assert!(commands.run("/fill 0 0 0 x 1 1 air").is_err());
+        assert!(commands.run("/fill -3e9 0 0 3e9 0 0 stone").unwrap_err().starts_with("Too many blocks"));
+        assert_eq!(commands.run("/bogus"), Err("Unknown command: bogus".to_string()));
+    }
+
+    #[test]
+    fn tab_completes_commands_and_arguments() {
+        let registry = CommandRegistry::default();
+        assert_eq!(registry.complete("/gam"), ("/gamemode ".to_string(), Vec::new()));
+        assert_eq!(registry.complete("/gamemode c").0, "/gamemode creative ");
+        assert_eq!(registry.complete("/setblock 1 2 3 crafting").0, "/setblock 1 2 3 crafting_table ");
+        let (text, candidates) = registry.complete("/give wooden_");
+        assert_eq!(text, "/give wooden_");
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(registry.complete("hello").0, "hello");
+    }
+
+    #[test]
+    fn parses_world_and_relative_coordinates() {
+        assert_eq!(
+            parse_coordinates(["1", "~", "~-2.5"]),
+            Ok(Coordinates::World([Coordinate::Absolute(1.0), Coordinate::Relative(0.0), Coordinate::Relative(-2.5)]))
+        );
+        let coordinates = parse_coordinates(["~1", "64", "~"]).unwrap();
+        assert_eq!(coordinates.resolve(Vec3::new(10.0, 5.0, -3.0), 1.0, 0.5), Vec3::new(11.0, 64.0, -3.0));
+
+        assert!(parse_coordinates(["~", "^", "~"]).is_err());
+        assert!(parse_coordinates(["~~", "0", "0"]).is_err());
+        assert!(parse_coordinates(["1", "2", "x"]).is_err());
+        assert!(parse_coordinates(["NaN", "0", "0"]).is_err());
+        assert!(parse_coordinates(["inf", "0", "0"]).is_err());
+    }
+
+    #[test]
+    fn resolves_local_coordinates_against_facing() {
+        use std::f32::consts::FRAC_PI_2;
+        let origin = Vec3::new(1.0, 2.0, 3.0);
+        assert_eq!(parse_coordinates(["^", "^", "^"]), Ok(Coordinates::Local(Vec3::ZERO)));
+
+        // Yaw 0 looks down -Z, so forward is -Z and left is -X.
+        let forward = parse_coordinates(["^", "^", "^2"]).unwrap();
+        assert!(forward.resolve(origin, 0.0, 0.0).abs_diff_eq(Vec3::new(1.0, 2.0, 1.0), 1e-5));
+        let left = parse_coordinates(["^1", "^", "^"]).unwrap();
+        assert!(left.resolve(origin, 0.0, 0.0).abs_diff_eq(Vec3::new(0.0, 2.0, 3.0), 1e-5));
+
+        // Turned a quarter left, forward becomes -X.
+        assert!(forward.resolve(origin, FRAC_PI_2, 0.0).abs_diff_eq(Vec3::new(-1.0, 2.0, 3.0), 1e-5));
+        // Looking straight up, forward is +Y and up tilts back to +Z.
+        assert!(forward.resolve(origin, 0.0, FRAC_PI_2).abs_diff_eq(Vec3::new(1.0, 4.0, 3.0), 1e-5));
+        let up = parse_coordinates(["^", "^1", "^"]).unwrap();
+        assert!(up.resolve(origin, 0.0, FRAC_PI_2).abs_diff_eq(Vec3::new(1.0, 2.0, 4.0), 1e-5));
+    }
+
+    #[test]
+    fn parses_selectors() {
+        assert_eq!(
+            parse_selector("@e[type=item, distance=..5,limit=2]"),
+            Ok(Selector {
+                base: SelectorBase::Entities,
+                entity_type: Some(("item".to_string(), false)),
+                distance: Some((None, Some(5.0))),
+                limit: Some(2),
+            })
+        );
+        assert_eq!(parse_selector("@p").map(|selector| selector.base), Ok(SelectorBase::Nearest));
+        assert_eq!(parse_selector("@e[]").map(|selector| selector.entity_type), Ok(None));
+        assert_eq!(
+            parse_selector("@e[type=!player]").map(|selector| selector.entity_type),
+            Ok(Some(("player".to_string(), true)))
+        );
+        assert_eq!(parse_range("2..5"), Ok((Some(2.0), Some(5.0))));
+        assert_eq!(parse_range("3"), Ok((Some(3.0), Some(3.0))));
+        assert_eq!(parse_range("1.5.."), Ok((Some(1.5), None)));
+
+        assert!(parse_selector("@x").is_err());
+        assert!(parse_selector("@e[type=item").is_err());
+        assert!(parse_selector("@e[colour=red]").is_err());
+        assert!(parse_selector("@e[type]").is_err());
+        assert!(parse_selector("@e[type=]").is_err());
+        assert!(parse_selector("@e[type=item,type=player]").is_err());
+        assert!(parse_selector("@e[limit=0]").is_err());
+        assert!(parse_selector("@e[distance=..]").is_err());
+        assert!(parse_selector("@e[distance=5..2]").is_err());
+    }
+
+    #[test]
+    fn selectors_filter_and_sort_by_distance() {
+        let candidates = [
+            (0, "player", Vec3::ZERO),
+            (1, "item", Vec3::new(8.0, 0.0, 0.0)),
+            (2, "item", Vec3::new(2.0, 0.0, 0.0)),
+            (3, "item", Vec3::new(0.0, 0.0, 20.0)),
+        ];
+        let ids = |text: &str| -> Vec<i32> {
+            parse_selector(text).unwrap().select(Vec3::ZERO, &candidates).iter().map(|hit| hit.0).collect()
+        };
+        assert_eq!(ids("@p"), vec![0]);
+        assert_eq!(ids("@a"), vec![0]);
+        assert_eq!(ids("@e"), vec![0, 2, 1, 3]);
+        assert_eq!(ids("@e[type=item,distance=..10]"), vec![2, 1]);
+        assert_eq!(ids("@e[type=!item]"), vec![0]);
+        assert_eq!(ids("@e[distance=5..,limit=1]"), vec![1]);
+        assert_eq!(ids("@p[distance=1..]"), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn tokenize_keeps_selectors_together() {
+        assert_eq!(tokenize("tp  @e[type=item, limit=1] "), vec!["tp", "@e[type=item, limit=1]"]);
+        assert_eq!(tokenize("fill ~ ~ ~ ^1 ^ ^2 stone"), vec!["fill", "~", "~", "~", "^1", "^", "^2", "stone"]);
+        assert!(tokenize("   ").is_empty());
+    }
+
+    #[test]
+    fn world_edits_undo_and_redo() {
+        let mut commands = TestCommands::default();
+        assert!(commands.run("/set stone").is_err());
+        commands.run("/pos1 0 0 0").unwrap();
+        commands.run("/pos2 2 2 2").unwrap();
+        assert_eq!(commands.run("/set stone"), Ok("Set 27 blocks".to_string()));
+        assert_eq!(commands.run("/hollow planks"), Ok("Hollowed the selection, changing 27 blocks".to_string()));
+        assert_eq!(commands.world.get(IVec3::ONE), None);
+        assert_eq!(commands.world.get(IVec3::ZERO), Some(BlockKind::Planks));
+        assert_eq!(commands.run("/replace planks glass"), Ok("Replaced 26 blocks".to_string()));
+
+        commands.run("/undo").unwrap();
+        assert_eq!(commands.world.get(IVec3::ZERO), Some(BlockKind::Planks));
+        commands.run("/undo").unwrap();
+        assert_eq!(commands.world.get(IVec3::ONE), Some(BlockKind::Stone));
+        commands.run("/redo").unwrap();
+        assert_eq!(commands.world.get(IVec3::ONE), None);
+
+        // A new edit drops the redo stack.
+        commands.run("/setblock 1 1 1 dirt").unwrap();
+        assert!(commands.run("/redo").is_err());
+
+        commands.run("/pos1 0 0 0").unwrap();
+        commands.run("/pos2 2 0 0").unwrap();
+        commands.run("/clone 10 0 10 90").unwrap();
+        assert_eq!(commands.world.get(IVec3::new(10, 0, 10)), Some(BlockKind::Planks));
+        assert_eq!(commands.world.get(IVec3::new(10, 0, 12)), Some(BlockKind::Planks));
+        assert_eq!(commands.world.get(IVec3::new(12, 0, 10)), None);
+        commands.run("/undo").unwrap();
+        assert_eq!(commands.world.get(IVec3::new(10, 0, 12)), None);
+        // A destination hanging past i32::MAX is refused rather than wrapping around.
+        assert!(commands.run("/clone 2147483647 0 0").is_err());
+        assert!(commands.run("/clone 0 0 2147483647 90").is_err());
+        assert!(commands.run("/clone 2147483392 0 0").is_ok());
+        commands.run("/undo").unwrap();
+
+        commands.run("/pos1 -2000000000 0 0").unwrap();
+        assert_eq!(commands.run("/pos2 2000000000 0 0"), Ok("Corner 2 set to 2000000000 0 0 (4000000001 blocks)".to_string()));
+        assert!(commands.run("/set stone").unwrap_err().starts_with("Too many blocks"));
+
+        // On a server the world isn't ours to edit.
+        commands.sharing = WorldSharing::Joined;
+        for command in ["/setblock 5 0 5 stone", "/fill 0 0 0 1 1 1 dirt", "/undo", "/redo", "/regen 1"] {
+            assert_eq!(commands.run(command), Err("World edits aren't available on a server".to_string()), "{command}");
+        }
+        assert_eq!(commands.world.get(IVec3::new(5, 0, 5)), None);
+
+        // A hosted world can be edited but not swapped out from under the guests.
+        commands.sharing = WorldSharing::Hosting;
+        assert!(commands.run("/setblock 5 0 5 stone").is_ok());
+        assert_eq!(commands.run("/regen 1"), Err("The world can't be replaced while it's open to LAN".to_string()));
+        assert!(commands.run("/import fixture 0 64 0").unwrap_err().contains("open to LAN"));
+    }
+}
@@ -0,0 +1,685 @@
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+enum RecipeShape {
+    Shaped {
+        width: usize,
+        height: usize,
+        cells: Vec<Option<ItemKind>>,
+    },
+    Shapeless(Vec<ItemKind>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Recipe {
+    name: String,
+    shape: RecipeShape,
+    result: ItemStack,
+}
+
+impl Recipe {
+    fn matches(&self, grid: &[Option<ItemKind>], grid_size: usize) -> bool {
+        match &self.shape {
+            RecipeShape::Shaped { width, height, cells } => {
+                let (grid_width, grid_height, trimmed) = trim_grid(grid, grid_size);
+                if grid_width != *width || grid_height != *height {
+                    return false;
+                }
+                let mirrored = (0..trimmed.len()).map(|i| {
+                    let (x, y) = (i % grid_width, i / grid_width);
+                    trimmed[y * grid_width + (grid_width - 1 - x)]
+                });
+                trimmed == *cells || mirrored.eq(cells.iter().copied())
+            }
+            RecipeShape::Shapeless(ingredients) => {
+                let mut items: Vec<ItemKind> = grid.iter().flatten().copied().collect();
+                let mut wanted = ingredients.clone();
+                items.sort();
+                wanted.sort();
+                items == wanted
+            }
+        }
+    }
+
+    fn ingredients(&self) -> Vec<ItemKind> {
+        match &self.shape {
+            RecipeShape::Shaped { cells, .. } => cells.iter().flatten().copied().collect(),
+            RecipeShape::Shapeless(ingredients) => ingredients.clone(),
+        }
+    }
+
+    fn fits(&self, grid_size: usize) -> bool {
+        match &self.shape {
+            RecipeShape::Shaped { width, height, .. } => *width <= grid_size && *height <= grid_size,
+            RecipeShape::Shapeless(ingredients) => ingredients.len() <= grid_size * grid_size,
+        }
+    }
+}
+
+// Crops empty rows and columns so a shape can sit anywhere in the grid.
+fn trim_grid(grid: &[Option<ItemKind>], size: usize) -> (usize, usize, Vec<Option<ItemKind>>) {
+    let occupied = |x: usize, y: usize| grid[y * size + x].is_some();
+    let columns: Vec<usize> = (0..size).filter(|&x| (0..size).any(|y| occupied(x, y))).collect();
+    let rows: Vec<usize> = (0..size).filter(|&y| (0..size).any(|x| occupied(x, y))).collect();
+    let (Some(&min_x), Some(&max_x), Some(&min_y), Some(&max_y)) =
+        (columns.first(), columns.last(), rows.first(), rows.last())
+    else {
+        return (0, 0, Vec::new());
+    };
+
+    let mut cells = Vec::new();
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            cells.push(grid[y * size + x]);
+        }
+    }
+    (max_x - min_x + 1, max_y - min_y + 1, cells)
+}
+
+#[derive(Resource, Default)]
+pub(super) struct RecipeBook {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeBook {
+    fn find(&self, grid: &[Option<ItemKind>], grid_size: usize) -> Option<&Recipe> {
+        self.recipes.iter().find(|recipe| recipe.matches(grid, grid_size))
+    }
+}
+
+#[derive(Resource)]
+pub(super) struct CraftingGrid {
+    size: usize,
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl CraftingGrid {
+    pub(super) fn new(size: usize) -> Self {
+        Self {
+            size,
+            slots: vec![None; size * size],
+        }
+    }
+
+    fn items(&self) -> Vec<Option<ItemKind>> {
+        self.slots.iter().map(|slot| slot.map(|s| s.item)).collect()
+    }
+}
+
+#[derive(Component, Clone, Copy)]
+pub(super) enum CraftingSlot {
+    Inventory(usize),
+    Grid(usize),
+    Output,
+    Recipe(usize),
+}
+
+#[derive(Component)]
+pub(super) struct CraftingMenu;
+
+pub(super) fn toggle_inventory(
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<GameSettings>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut crafting: ResMut<CraftingGrid>,
+    mut windows: Query<&mut Window>,
+) {
+    if !keyboard.just_pressed(settings.keybinds.inventory) {
+        return;
+    }
+
+    let mut window = windows.single_mut();
+    match state.get() {
+        GameState::Playing => {
+            *crafting = CraftingGrid::new(2);
+            window.cursor.visible = true;
+            window.cursor.grab_mode = CursorGrabMode::None;
+            next_state.set(GameState::Crafting);
+        }
+        GameState::Crafting => {
+            window.cursor.visible = false;
+            window.cursor.grab_mode = CursorGrabMode::Locked;
+            next_state.set(GameState::Playing);
+        }
+        _ => {}
+    }
+}
+
+pub(super) fn close_crafting(
+    mut commands: Commands,
+    mut inventory: ResMut<Inventory>,
+    mut crafting: ResMut<CraftingGrid>,
+    block_assets: Res<BlockAssets>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let returned: Vec<ItemStack> = crafting.slots.iter_mut()
+        .filter_map(Option::take)
+        .chain(inventory.cursor.take())
+        .collect();
+    let transform = player_query.single();
+    for stack in returned {
+        if let Some(leftover) = inventory.add(stack) {
+            spawn_dropped_item(
+                &mut commands,
+                &block_assets,
+                leftover,
+                transform.translation,
+                transform.forward() * ITEM_THROW_SPEED,
+                2.0,
+            );
+        }
+    }
+}
+
+pub(super) fn cleanup_crafting_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<CraftingMenu>>,
+) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn swap_with_cursor(cursor: &mut Option<ItemStack>, slot: &mut Option<ItemStack>) {
+    match (cursor.as_mut(), slot.as_mut()) {
+        (Some(held), Some(stack)) if held.item == stack.item && held.damage == stack.damage => {
+            let moved = held.count.min(held.item.max_stack() - stack.count);
+            stack.count += moved;
+            held.count -= moved;
+            if held.count == 0 {
+                *cursor = None;
+            }
+        }
+        _ => std::mem::swap(cursor, slot),
+    }
+}
+
+fn take_crafting_output(inventory: &mut Inventory, crafting: &mut CraftingGrid, recipes: &RecipeBook) {
+    let Some(recipe) = recipes.find(&crafting.items(), crafting.size) else {
+        return;
+    };
+    let result = recipe.result;
+
+    if let Some(held) = inventory.cursor.as_mut() {
+        if held.item != result.item || held.count + result.count > result.item.max_stack() {
+            return;
+        }
+        held.count += result.count;
+    } else {
+        inventory.cursor = Some(result);
+    }
+
+    for slot in crafting.slots.iter_mut() {
+        if let Some(stack) = slot {
+            stack.count -= 1;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+    }
+}
+
+fn can_craft(recipe: &Recipe, inventory: &Inventory, grid_size: usize) -> bool {
+    if !recipe.fits(grid_size) {
+        return false;
+    }
+    let mut needed: HashMap<ItemKind, u32> = HashMap::new();
+    for item in recipe.ingredients() {
+        *needed.entry(item).or_default() += 1;
+    }
+    needed.iter().all(|(item, count)| inventory.count(*item) >= *count)
+}
+
+// Moves the grid contents back to the inventory and lays the recipe out from inventory items.
+fn fill_from_recipe(inventory: &mut Inventory, crafting: &mut CraftingGrid, recipe: &Recipe) {
+    for index in 0..crafting.slots.len() {
+        if let Some(stack) = crafting.slots[index].take() {
+            if let Some(leftover) = inventory.add(stack) {
+                crafting.slots[index] = Some(leftover);
+                return;
+            }
+        }
+    }
+
+    if !can_craft(recipe, inventory, crafting.size) {
+        return;
+    }
+
+    let size = crafting.size;
+    let placements: Vec<(usize, ItemKind)> = match &recipe.shape {
+        RecipeShape::Shaped { width, cells, .. } => cells.iter().enumerate()
+            .filter_map(|(i, cell)| cell.map(|item| ((i / width) * size + i % width, item)))
+            .collect(),
+        RecipeShape::Shapeless(ingredients) => ingredients.iter().copied().enumerate().collect(),
+    };
+    for (index, item) in placements {
+        inventory.remove(item, 1);
+        crafting.slots[index] = Some(ItemStack::new(item, 1));
+    }
+}
+
+pub(super) fn crafting_interaction(
+    interaction_query: Query<(&Interaction, &CraftingSlot), Changed<Interaction>>,
+    mut inventory: ResMut<Inventory>,
+    mut crafting: ResMut<CraftingGrid>,
+    recipes: Res<RecipeBook>,
+) {
+    let inventory = &mut *inventory;
+    for (interaction, slot) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *slot {
+            CraftingSlot::Inventory(index) => swap_with_cursor(&mut inventory.cursor, &mut inventory.slots[index]),
+            CraftingSlot::Grid(index) => swap_with_cursor(&mut inventory.cursor, &mut crafting.slots[index]),
+            CraftingSlot::Output => take_crafting_output(inventory, &mut crafting, &recipes),
+            CraftingSlot::Recipe(index) => fill_from_recipe(inventory, &mut crafting, &recipes.recipes[index]),
+        }
+    }
+}
+
+fn spawn_slot(parent: &mut ChildBuilder, language: &Language, slot: CraftingSlot, stack: Option<ItemStack>) {
+    parent.spawn((
+        ButtonBundle {
+            style: Style {
+                width: Val::Px(56.0),
+                height: Val::Px(56.0),
+                border: UiRect::all(Val::Px(2.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            border_color: Color::WHITE.into(),
+            background_color: stack.map_or(Color::rgba(0.0, 0.0, 0.0, 0.5), |s| s.item.color()).into(),
+            ..default()
+        },
+        slot,
+    )).with_children(|parent| {
+        if let Some(stack) = stack {
+            parent.spawn(TextBundle::from_section(
+                stack.label(language),
+                TextStyle {
+                    font_size: 12.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        }
+    });
+}
+
+pub(super) fn crafting_menu(
+    mut commands: Commands,
+    existing_menu: Query<Entity, With<CraftingMenu>>,
+    inventory: Res<Inventory>,
+    crafting: Res<CraftingGrid>,
+    recipes: Res<RecipeBook>,
+    language: Res<Language>,
+) {
+    for entity in existing_menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let output = recipes.find(&crafting.items(), crafting.size).map(|recipe| recipe.result);
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(20.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+            CraftingMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                if crafting.size == 3 { "CRAFTING TABLE" } else { "INVENTORY" },
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            // Grid, output and recipe book
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(40.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for row in 0..crafting.size {
+                        parent.spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                column_gap: Val::Px(2.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for column in 0..crafting.size {
+                                let index = row * crafting.size + column;
+                                spawn_slot(parent, &language, CraftingSlot::Grid(index), crafting.slots[index]);
+                            }
+                        });
+                    }
+                });
+
+                parent.spawn(TextBundle::from_section(
+                    "->",
+                    TextStyle {
+                        font_size: 30.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+
+                spawn_slot(parent, &language, CraftingSlot::Output, output);
+
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Recipe Book",
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    ));
+
+                    for (index, recipe) in recipes.recipes.iter().enumerate() {
+                        if !recipe.fits(crafting.size) {
+                            continue;
+                        }
+                        parent.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                background_color: if can_craft(recipe, &inventory, crafting.size) {
+                                    Color::rgb(0.2, 0.4, 0.2)
+                                } else {
+                                    Color::rgb(0.2, 0.2, 0.2)
+                                }.into(),
+                                ..default()
+                            },
+                            CraftingSlot::Recipe(index),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                format!("{} x{}", language.item_name(recipe.result.item), recipe.result.count),
+                                TextStyle {
+                                    font_size: 16.0,
+                                    color: Color::WHITE,
+                                    ..default()
+                                },
+                            ));
+                        });
+                    }
+                });
+            });
+
+            parent.spawn(TextBundle::from_section(
+                match inventory.cursor {
+                    Some(stack) => format!("Holding: {} x{}", language.item_name(stack.item), stack.count),
+                    None => "Click a slot to pick up items".to_string(),
+                },
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            // Main inventory rows, then the hotbar
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                for row_start in [HOTBAR_SIZE, HOTBAR_SIZE * 2, HOTBAR_SIZE * 3, 0] {
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(2.0),
+                            margin: UiRect::top(Val::Px(if row_start == 0 { 10.0 } else { 0.0 })),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for index in row_start..row_start + HOTBAR_SIZE {
+                            spawn_slot(parent, &language, CraftingSlot::Inventory(index), inventory.slots[index]);
+                        }
+                    });
+                }
+            });
+        });
+}
+
+pub(super) fn load_recipes() -> RecipeBook {
+    let mut book = RecipeBook::default();
+    let Ok(entries) = std::fs::read_dir("assets/recipes") else {
+        eprintln!("Failed to read assets/recipes");
+        return book;
+    };
+
+    let mut paths: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "recipe"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let recipe = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| parse_recipe(&name, &content));
+        match recipe {
+            Ok(recipe) => book.recipes.push(recipe),
+            Err(e) => eprintln!("Failed to load recipe {}: {}", path.display(), e),
+        }
+    }
+
+    book
+}
+
+fn parse_item(value: &str) -> Result<ItemKind, String> {
+    value.trim().parse().map_err(|_| format!("unknown item '{}'", value.trim()))
+}
+
+fn parse_recipe(name: &str, content: &str) -> Result<Recipe, String> {
+    let mut recipe_type = None;
+    let mut pattern: Vec<String> = Vec::new();
+    let mut keys: HashMap<char, ItemKind> = HashMap::new();
+    let mut ingredients = Vec::new();
+    let mut result = None;
+    let mut count = 1;
+
+    for line in content.lines() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let (key, raw_value) = line.split_once('=').ok_or_else(|| format!("expected key=value, got '{line}'"))?;
+        let value = raw_value.trim();
+        match key.trim() {
+            "type" => recipe_type = Some(value.to_string()),
+            // Spaces are empty cells, so pattern rows aren't trimmed; `lines` already dropped the line ending.
+            "pattern" => pattern.push(raw_value.to_string()),
+            "key" => {
+                let (symbol, item) = value.split_once(':').ok_or_else(|| format!("expected symbol:item, got '{value}'"))?;
+                let mut chars = symbol.trim().chars();
+                let (Some(symbol), None) = (chars.next(), chars.next()) else {
+                    return Err(format!("key symbol must be one character, got '{symbol}'"));
+                };
+                keys.insert(symbol, parse_item(item)?);
+            }
+            "ingredient" => ingredients.push(parse_item(value)?),
+            "result" => result = Some(parse_item(value)?),
+            "count" => count = value.parse().ok().filter(|count| *count > 0).ok_or_else(|| format!("invalid count '{value}'"))?,
+            other => return Err(format!("unknown key '{other}'")),
+        }
+    }
+
+    let result = ItemStack::new(result.ok_or("missing result")?, count);
+
+    let shape = match recipe_type.as_deref() {
+        Some("shaped") => {
+            if pattern.is_empty() || pattern.len() > 3 || pattern.iter().any(|row| row.chars().count() > 3) {
+                return Err("pattern must be between 1x1 and 3x3".to_string());
+            }
+            let mut grid = vec![None; 9];
+            for (y, row) in pattern.iter().enumerate() {
+                for (x, symbol) in row.chars().enumerate() {
+                    grid[y * 3 + x] = match symbol {
+                        '.' | ' ' => None,
+                        symbol => Some(*keys.get(&symbol).ok_or_else(|| format!("pattern symbol '{symbol}' has no key"))?),
+                    };
+                }
+            }
+            let (width, height, cells) = trim_grid(&grid, 3);
+            if cells.is_empty() {
+                return Err("pattern is empty".to_string());
+            }
+            RecipeShape::Shaped { width, height, cells }
+        }
+        Some("shapeless") => {
+            if ingredients.is_empty() || ingredients.len() > 9 {
+                return Err("shapeless recipes need between 1 and 9 ingredients".to_string());
+            }
+            RecipeShape::Shapeless(ingredients)
+        }
+        _ => return Err("type must be shaped or shapeless".to_string()),
+    };
+
+    Ok(Recipe {
+        name: name.to_string(),
+        shape,
+        result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AXE: &str = "type=shaped\npattern=PP\npattern=PS\npattern=.S\nkey=P:Planks\nkey=S:Stick\nresult=Stone\n";
+
+    fn grid(size: usize, cells: &[(usize, ItemKind)]) -> Vec<Option<ItemKind>> {
+        let mut grid = vec![None; size * size];
+        for (index, item) in cells {
+            grid[*index] = Some(*item);
+        }
+        grid
+    }
+
+    #[test]
+    fn shaped_recipe_matches_anywhere_in_grid() {
+        let recipe = parse_recipe("table", "type=shaped\npattern=PP\npattern=PP\nkey=P:Planks\nresult=CraftingTable").unwrap();
+        let planks = ItemKind::Planks;
+        assert!(recipe.matches(&grid(2, &[(0, planks), (1, planks), (2, planks), (3, planks)]), 2));
+        assert!(recipe.matches(&grid(3, &[(4, planks), (5, planks), (7, planks), (8, planks)]), 3));
+        assert!(!recipe.matches(&grid(3, &[(0, planks), (1, planks), (3, planks)]), 3));
+    }
+
+    #[test]
+    fn shaped_recipe_matches_mirrored() {
+        let recipe = parse_recipe("axe", AXE).unwrap();
+        let (p, s) = (ItemKind::Planks, ItemKind::Stick);
+        let normal = grid(3, &[(0, p), (1, p), (3, p), (4, s), (7, s)]);
+        let mirrored = grid(3, &[(0, p), (1, p), (3, s), (4, p), (6, s)]);
+        let flipped = grid(3, &[(0, s), (3, p), (4, s), (6, p), (7, p)]);
+        assert!(recipe.matches(&normal, 3));
+        assert!(recipe.matches(&mirrored, 3));
+        assert!(!recipe.matches(&flipped, 3));
+    }
+
+    #[test]
+    fn pattern_spaces_are_empty_cells() {
+        let recipe = parse_recipe("diagonal", "type=shaped\npattern=P \r\npattern= P\r\nkey=P:Planks\nresult=Stick").unwrap();
+        let planks = ItemKind::Planks;
+        assert!(recipe.matches(&grid(2, &[(0, planks), (3, planks)]), 2));
+        assert!(!recipe.matches(&grid(2, &[(0, planks), (2, planks)]), 2));
+    }
+
+    #[test]
+    fn large_recipe_does_not_fit_small_grid() {
+        let recipe = parse_recipe("axe", AXE).unwrap();
+        assert!(!recipe.fits(2));
+        assert!(recipe.fits(3));
+    }
+
+    #[test]
+    fn shapeless_recipe_ignores_positions() {
+        let recipe = parse_recipe("planks", "type=shapeless\ningredient=Log\nresult=Planks\ncount=4").unwrap();
+        assert_eq!(recipe.result, ItemStack::new(ItemKind::Planks, 4));
+        assert!(recipe.matches(&grid(2, &[(3, ItemKind::Log)]), 2));
+        assert!(recipe.matches(&grid(3, &[(5, ItemKind::Log)]), 3));
+        assert!(!recipe.matches(&grid(2, &[(0, ItemKind::Log), (1, ItemKind::Log)]), 2));
+    }
+
+    #[test]
+    fn malformed_recipes_are_rejected() {
+        assert!(parse_recipe("x", "type=shaped\npattern=PX\nkey=P:Planks\nresult=Stick").is_err());
+        assert!(parse_recipe("x", "type=shapeless\ningredient=Diamond\nresult=Stick").is_err());
+        assert!(parse_recipe("x", "type=shapeless\ningredient=Log").is_err());
+        assert!(parse_recipe("x", "type=smelting\nresult=Stick").is_err());
+        assert!(parse_recipe("x", "type=shapeless\ningredient=Log\nresult=Planks\ncount=0").is_err());
+    }
+
+    #[test]
+    fn book_finds_matching_recipe() {
+        let book = RecipeBook {
+            recipes: vec![
+                parse_recipe("planks", "type=shapeless\ningredient=Log\nresult=Planks\ncount=4").unwrap(),
+                parse_recipe("sticks", "type=shaped\npattern=P\npattern=P\nkey=P:Planks\nresult=Stick\ncount=4").unwrap(),
+            ],
+        };
+        let sticks = grid(2, &[(1, ItemKind::Planks), (3, ItemKind::Planks)]);
+        assert_eq!(book.find(&sticks, 2).map(|r| r.name.as_str()), Some("sticks"));
+        assert!(book.find(&grid(2, &[]), 2).is_none());
+    }
+
+    #[test]
+    fn glass_can_be_crafted() {
+        let book = load_recipes();
+        let grid = grid(2, &[(0, ItemKind::Coal), (3, ItemKind::Cobblestone)]);
+        assert_eq!(book.find(&grid, 2).map(|recipe| recipe.result), Some(ItemStack::new(ItemKind::Glass, 1)));
+    }
+}
@@ -0,0 +1,437 @@
+use super::*;
+
+#[derive(Component)]
+pub(super) struct Crosshair;
+
+#[derive(Component)]
+pub(super) struct FpsText;
+
+#[derive(Component)]
+pub(super) struct FrameTimeBar(usize);
+
+// F3 toggles the full readout; otherwise only the FPS line is shown.
+#[derive(Resource, Default)]
+pub(super) struct DebugOverlay {
+    visible: bool,
+    // Most recent last, in milliseconds.
+    frame_times: VecDeque<f32>,
+}
+
+#[derive(Component)]
+pub(super) struct KeystrokesDisplay;
+
+const FRAME_TIME_SAMPLES: usize = 120;
+
+// Frame time that fills the graph's full height; 60 FPS sits at the halfway mark.
+const FRAME_TIME_GRAPH_MAX: f32 = 33.3;
+
+const FRAME_TIME_GRAPH_HEIGHT: f32 = 60.0;
+
+pub(super) fn spawn_crosshair(mut commands: Commands, existing: Query<(), With<Crosshair>>) {
+    if !existing.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                ..default()
+            },
+            Crosshair,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(20.0),
+                        height: Val::Px(20.0),
+                        position_type: PositionType::Absolute,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(2.0),
+                            height: Val::Px(20.0),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    });
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(20.0),
+                            height: Val::Px(2.0),
+                            ..default()
+                        },
+                        background_color: Color::WHITE.into(),
+                        ..default()
+                    });
+                });
+        });
+}
+
+pub(super) fn spawn_fps_counter(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "FPS: ",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+                FpsText,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        height: Val::Px(FRAME_TIME_GRAPH_HEIGHT),
+                        align_items: AlignItems::FlexEnd,
+                        ..default()
+                    },
+                    background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+                    ..default()
+                })
+                .with_children(|graph| {
+                    for i in 0..FRAME_TIME_SAMPLES {
+                        graph.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(2.0),
+                                    height: Val::Px(0.0),
+                                    ..default()
+                                },
+                                visibility: Visibility::Hidden,
+                                ..default()
+                            },
+                            FrameTimeBar(i),
+                        ));
+                    }
+                });
+        });
+}
+
+pub(super) fn toggle_debug_overlay(keyboard: Res<Input<KeyCode>>, mut overlay: ResMut<DebugOverlay>) {
+    if keyboard.just_pressed(KeyCode::F3) {
+        overlay.visible = !overlay.visible;
+    }
+}
+
+fn facing_name(yaw: f32) -> &'static str {
+    // Yaw 0 looks down -Z, and it grows turning left towards -X.
+    let quarter = (yaw / std::f32::consts::FRAC_PI_2).round().rem_euclid(4.0) as i32;
+    match quarter {
+        0 => "north (-Z)",
+        1 => "west (-X)",
+        2 => "south (+Z)",
+        _ => "east (+X)",
+    }
+}
+
+// Resident set size in bytes, where the platform makes it cheap to ask.
+fn memory_usage() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn update_fps_text(
+    diagnostics: Res<DiagnosticsStore>,
+    stats: Res<CullingStats>,
+    overlay: Res<DebugOverlay>,
+    world: Res<WorldBlocks>,
+    render_state: Res<ChunkRenderState>,
+    registry: Res<BlockRegistry>,
+    world_seed: Res<WorldSeed>,
+    entities: &Entities,
+    player_query: Query<(&Transform, &Player)>,
+    mut query: Query<&mut Text, With<FpsText>>,
+) {
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let mut lines = vec![format!("FPS: {fps:.0}")];
+
+    if overlay.visible {
+        lines.push(format!(
+            "Minceraft {} (built {})",
+            built_info::BUILD_VERSION,
+            built_info::BUILD_TIMESTAMP
+        ));
+        if let Ok((transform, player)) = player_query.get_single() {
+            let position = transform.translation;
+            let block = position.round().as_ivec3();
+            let (chunk, _) = chunk_position(block);
+            lines.push(format!("XYZ: {:.3} / {:.3} / {:.3}", position.x, position.y, position.z));
+            lines.push(format!("Block: {} {} {}", block.x, block.y, block.z));
+            lines.push(format!("Chunk: {} {} {}", chunk.x, chunk.y, chunk.z));
+            lines.push(format!(
+                "Facing: {} ({:.1} / {:.1})",
+                facing_name(player.yaw),
+                player.yaw.to_degrees(),
+                player.pitch.to_degrees()
+            ));
+            let target = raycast_blocks(position, transform.forward(), PLAYER_REACH, |pos| {
+                world.is_targetable(&registry, pos)
+            });
+            lines.push(match target.and_then(|(hit, _)| world.get(hit).map(|kind| (hit, kind))) {
+                Some((hit, kind)) => format!("Targeted block: {:?} at {} {} {}", kind, hit.x, hit.y, hit.z),
+                None => "Targeted block: none".to_string(),
+            });
+            lines.push(format!(
+                "Biome: {:?}",
+                biome_at(&temperature_noise(world_seed.0), block.x, block.z)
+            ));
+            lines.push(format!(
+                "Light: {} sky, {} block",
+                world.light(block, LightChannel::Sky),
+                world.light(block, LightChannel::Block)
+            ));
+        }
+        lines.push(format!(
+            "Chunks: {} loaded, {} meshed; entities: {}",
+            world.chunks.len(),
+            render_state.entities.len(),
+            entities.len()
+        ));
+        lines.push(format!(
+            "Chunks: {} drawn, {} culled (frustum {}, caves {}, distance {})",
+            stats.drawn,
+            stats.frustum + stats.caves + stats.out_of_range,
+            stats.frustum,
+            stats.caves,
+            stats.out_of_range,
+        ));
+        lines.push(match memory_usage() {
+            Some(bytes) => format!("Memory: {} MB", bytes / (1024 * 1024)),
+            None => "Memory: unavailable".to_string(),
+        });
+    }
+
+    text.sections[0].value = lines.join("\n");
+}
+
+pub(super) fn update_frame_time_graph(
+    time: Res<Time>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut bars: Query<(&FrameTimeBar, &mut Style, &mut BackgroundColor, &mut Visibility)>,
+) {
+    overlay.frame_times.push_back(time.delta_seconds() * 1000.0);
+    while overlay.frame_times.len() > FRAME_TIME_SAMPLES {
+        overlay.frame_times.pop_front();
+    }
+
+    let offset = FRAME_TIME_SAMPLES - overlay.frame_times.len();
+    for (bar, mut style, mut color, mut visibility) in bars.iter_mut() {
+        visibility.set_if_neq(if overlay.visible { Visibility::Inherited } else { Visibility::Hidden });
+        let Some(&millis) = bar.0.checked_sub(offset).and_then(|i| overlay.frame_times.get(i)) else {
+            style.height = Val::Px(0.0);
+            continue;
+        };
+        let fraction = (millis / FRAME_TIME_GRAPH_MAX).min(1.0);
+        style.height = Val::Px(fraction * FRAME_TIME_GRAPH_HEIGHT);
+        color.0 = if fraction < 0.5 {
+            Color::GREEN
+        } else if fraction < 1.0 {
+            Color::YELLOW
+        } else {
+            Color::RED
+        };
+    }
+}
+
+pub(super) fn keystrokes_display(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<GameSettings>,
+    existing_display: Query<Entity, With<KeystrokesDisplay>>,
+) {
+    for entity in existing_display.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !settings.show_keystrokes {
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(20.0),
+                    top: Val::Px(20.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+                ..default()
+            },
+            KeystrokesDisplay,
+        ))
+        .with_children(|parent| {
+            // W key
+            parent.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(40.0),
+                    height: Val::Px(40.0),
+                    border: UiRect::all(Val::Px(2.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::new(Val::Px(42.0), Val::Px(0.0), Val::Px(0.0), Val::Px(0.0)),
+                    ..default()
+                },
+                border_color: Color::WHITE.into(),
+                background_color: if keyboard.pressed(settings.keybinds.forward) {
+                    Color::rgb(0.5, 0.5, 0.5)
+                } else {
+                    Color::rgba(0.0, 0.0, 0.0, 0.5)
+                }.into(),
+                ..default()
+            }).with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    format!("{:?}", settings.keybinds.forward),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+            });
+
+            // ASD row
+            parent.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(2.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                for (key, _) in [
+                    (settings.keybinds.left, "Left"),
+                    (settings.keybinds.backward, "Back"),
+                    (settings.keybinds.right, "Right")
+                ] {
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(40.0),
+                            height: Val::Px(40.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        border_color: Color::WHITE.into(),
+                        background_color: if keyboard.pressed(key) {
+                            Color::rgb(0.5, 0.5, 0.5)
+                        } else {
+                            Color::rgba(0.0, 0.0, 0.0, 0.5)
+                        }.into(),
+                        ..default()
+                    }).with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            format!("{:?}", key),
+                            TextStyle {
+                                font_size: 20.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    });
+                }
+            });
+
+            // Space bar
+            parent.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(124.0),
+                    height: Val::Px(40.0),
+                    border: UiRect::all(Val::Px(2.0)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                border_color: Color::WHITE.into(),
+                background_color: if keyboard.pressed(settings.keybinds.jump) {
+                    Color::rgb(0.5, 0.5, 0.5)
+                } else {
+                    Color::rgba(0.0, 0.0, 0.0, 0.5)
+                }.into(),
+                ..default()
+            }).with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    format!("{:?}", settings.keybinds.jump),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+            });
+        });
+}
+
+pub(super) fn update_window_title(
+    mut windows: Query<&mut Window>,
+    state: Res<State<GameState>>,
+) {
+    let mut window = windows.single_mut();
+    let state_text = match state.get() {
+        GameState::MainMenu => "Main Menu",
+        GameState::Multiplayer => "Multiplayer",
+        GameState::Loading => "Loading",
+        GameState::Playing => "In Game",
+        GameState::Paused => "Paused",
+        GameState::Crafting => "Crafting",
+        GameState::Console => "In Game",
+    };
+    window.title = format!("Minceraft - {}", state_text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn facing_follows_yaw() {
+        use std::f32::consts::FRAC_PI_2;
+        assert_eq!(facing_name(0.0), "north (-Z)");
+        assert_eq!(facing_name(FRAC_PI_2), "west (-X)");
+        assert_eq!(facing_name(-FRAC_PI_2), "east (+X)");
+        assert_eq!(facing_name(5.0 * FRAC_PI_2), "west (-X)");
+        assert_eq!(facing_name(2.0 * FRAC_PI_2 + 0.3), "south (+Z)");
+    }
+}
@@ -0,0 +1,306 @@
+use super::*;
+
+impl ItemStack {
+    pub(super) fn new(item: ItemKind, count: u32) -> Self {
+        Self { item, count, damage: 0 }
+    }
+
+    pub(super) fn label(&self, language: &Language) -> String {
+        let name = language.item_name(self.item);
+        match self.item.tool() {
+            Some(tool) => format!("{}\n{}/{}", name, tool.tier.durability() - self.damage, tool.tier.durability()),
+            None => format!("{}\n{}", name, self.count),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub(super) struct Inventory {
+    pub(super) slots: [Option<ItemStack>; INVENTORY_SIZE],
+    selected: usize,
+    pub(super) cursor: Option<ItemStack>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self {
+            slots: [None; INVENTORY_SIZE],
+            selected: 0,
+            cursor: None,
+        }
+    }
+}
+
+impl Inventory {
+    // Returns whatever did not fit.
+    pub(super) fn add(&mut self, mut stack: ItemStack) -> Option<ItemStack> {
+        let max_stack = stack.item.max_stack();
+        for slot in self.slots.iter_mut().flatten() {
+            if slot.item == stack.item && slot.damage == stack.damage && slot.count < max_stack {
+                let moved = stack.count.min(max_stack - slot.count);
+                slot.count += moved;
+                stack.count -= moved;
+                if stack.count == 0 {
+                    return None;
+                }
+            }
+        }
+        for slot in self.slots.iter_mut() {
+            if slot.is_none() {
+                let moved = stack.count.min(max_stack);
+                *slot = Some(ItemStack { count: moved, ..stack });
+                stack.count -= moved;
+                if stack.count == 0 {
+                    return None;
+                }
+            }
+        }
+        Some(stack)
+    }
+
+    pub(super) fn count(&self, item: ItemKind) -> u32 {
+        self.slots.iter().flatten().filter(|s| s.item == item).map(|s| s.count).sum()
+    }
+
+    pub(super) fn remove(&mut self, item: ItemKind, mut count: u32) -> bool {
+        if self.count(item) < count {
+            return false;
+        }
+        for slot in self.slots.iter_mut() {
+            if let Some(stack) = slot {
+                if stack.item == item {
+                    let taken = stack.count.min(count);
+                    stack.count -= taken;
+                    count -= taken;
+                    if stack.count == 0 {
+                        *slot = None;
+                    }
+                }
+            }
+            if count == 0 {
+                break;
+            }
+        }
+        true
+    }
+
+    pub(super) fn selected_item(&self) -> Option<ItemKind> {
+        self.slots[self.selected].map(|s| s.item)
+    }
+
+    pub(super) fn take_selected(&mut self) -> Option<ItemStack> {
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        let taken = ItemStack { count: 1, ..*stack };
+        stack.count -= 1;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(taken)
+    }
+
+    pub(super) fn damage_selected_tool(&mut self) {
+        let slot = &mut self.slots[self.selected];
+        if let Some(stack) = slot {
+            if let Some(tool) = stack.item.tool() {
+                stack.damage += 1;
+                if stack.damage >= tool.tier.durability() {
+                    *slot = None;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub(super) struct HotbarDisplay;
+
+#[derive(Component)]
+pub(super) struct DroppedItemVisual;
+
+// Seconds a dropped item must have existed before the player can pick it up.
+#[derive(Component)]
+pub(super) struct PickupDelay(f32);
+
+const ITEM_PICKUP_RADIUS: f32 = 1.5;
+
+pub(super) fn hotbar_selection(
+    keyboard: Res<Input<KeyCode>>,
+    mut scroll_evr: EventReader<MouseWheel>,
+    mut inventory: ResMut<Inventory>,
+) {
+    let keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    for (index, key) in keys.iter().enumerate() {
+        if keyboard.just_pressed(*key) {
+            inventory.selected = index;
+        }
+    }
+
+    for ev in scroll_evr.read() {
+        if ev.y > 0.0 {
+            inventory.selected = (inventory.selected + HOTBAR_SIZE - 1) % HOTBAR_SIZE;
+        } else if ev.y < 0.0 {
+            inventory.selected = (inventory.selected + 1) % HOTBAR_SIZE;
+        }
+    }
+}
+
+pub(super) fn hotbar_display(
+    mut commands: Commands,
+    inventory: Res<Inventory>,
+    language: Res<Language>,
+    existing_display: Query<Entity, With<HotbarDisplay>>,
+) {
+    for entity in existing_display.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    bottom: Val::Px(20.0),
+                    justify_content: JustifyContent::Center,
+                    column_gap: Val::Px(2.0),
+                    ..default()
+                },
+                ..default()
+            },
+            HotbarDisplay,
+        ))
+        .with_children(|parent| {
+            for index in 0..HOTBAR_SIZE {
+                let stack = inventory.slots[index];
+                parent.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(56.0),
+                        height: Val::Px(56.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    border_color: if index == inventory.selected { Color::YELLOW } else { Color::WHITE }.into(),
+                    background_color: stack.map_or(Color::rgba(0.0, 0.0, 0.0, 0.5), |s| s.item.color()).into(),
+                    ..default()
+                }).with_children(|parent| {
+                    if let Some(stack) = stack {
+                        parent.spawn(TextBundle::from_section(
+                            stack.label(&language),
+                            TextStyle {
+                                font_size: 12.0,
+                                color: Color::WHITE,
+                                ..default()
+                            },
+                        ));
+                    }
+                });
+            }
+        });
+}
+
+pub(super) fn cleanup_hotbar(
+    mut commands: Commands,
+    hotbar_query: Query<Entity, With<HotbarDisplay>>,
+) {
+    for entity in hotbar_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub(super) fn spawn_dropped_item(
+    commands: &mut Commands,
+    block_assets: &BlockAssets,
+    stack: ItemStack,
+    position: Vec3,
+    velocity: Vec3,
+    pickup_delay: f32,
+) {
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(position)),
+            DroppedItem { stack, age: 0.0 },
+            PickupDelay(pickup_delay),
+            Velocity(velocity),
+            Gravity(GRAVITY),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                PbrBundle {
+                    mesh: block_assets.item_mesh.clone(),
+                    material: block_assets.item_materials[&stack.item].clone(),
+                    ..default()
+                },
+                DroppedItemVisual,
+            ));
+        });
+}
+
+pub(super) fn animate_dropped_items(
+    time: Res<Time>,
+    mut visuals: Query<&mut Transform, With<DroppedItemVisual>>,
+) {
+    let t = time.elapsed_seconds();
+    for mut transform in visuals.iter_mut() {
+        transform.translation.y = (t * 2.0).sin() * 0.1 + 0.1;
+        transform.rotation = Quat::from_rotation_y(t);
+    }
+}
+
+pub(super) fn pickup_dropped_items(
+    mut commands: Commands,
+    player_query: Query<&Transform, With<Player>>,
+    mut items: Query<(Entity, &Transform, &mut DroppedItem, &PickupDelay), Without<Player>>,
+    mut inventory: ResMut<Inventory>,
+) {
+    let player = player_query.single();
+    for (entity, transform, mut item, delay) in items.iter_mut() {
+        if item.age < delay.0
+            || transform.translation.distance(player.translation) > ITEM_PICKUP_RADIUS
+        {
+            continue;
+        }
+        match inventory.add(item.stack) {
+            None => commands.entity(entity).despawn_recursive(),
+            Some(leftover) => item.stack = leftover,
+        }
+    }
+}
+
+pub(super) fn throw_item(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<GameSettings>,
+    player_query: Query<&Transform, With<Player>>,
+    mut inventory: ResMut<Inventory>,
+    block_assets: Res<BlockAssets>,
+) {
+    if !keyboard.just_pressed(settings.keybinds.drop) {
+        return;
+    }
+    let Some(stack) = inventory.take_selected() else {
+        return;
+    };
+
+    let transform = player_query.single();
+    spawn_dropped_item(
+        &mut commands,
+        &block_assets,
+        stack,
+        transform.translation + transform.forward() * 0.5,
+        transform.forward() * ITEM_THROW_SPEED + Vec3::Y * 2.0,
+        2.0,
+    );
+}
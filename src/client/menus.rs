@@ -0,0 +1,738 @@
+use super::*;
+
+#[derive(Component)]
+pub(super) struct PauseMenu;
+
+#[derive(Resource)]
+pub(super) struct WorldGenProgress {
+    pub(super) blocks_completed: usize,
+    pub(super) total_blocks: usize,
+}
+
+#[derive(Component)]
+pub(super) struct MainMenuUI;
+
+#[derive(Component)]
+pub(super) struct LoadingScreenUI;
+
+const MAX_ADDRESS_LENGTH: usize = 64;
+
+#[derive(Component, Debug, Clone, PartialEq)]
+pub(super) enum MenuButton {
+    Play,
+    Multiplayer,
+    Join(String),
+    AddServer,
+    RemoveServer(String),
+    Refresh,
+    Back,
+    TogglePack(String),
+    RaisePack(String),
+}
+
+fn spawn_menu_button(parent: &mut ChildBuilder, label: impl Into<String>, font_size: f32, button: MenuButton) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::all(Val::Px(font_size * 0.5)),
+                    ..default()
+                },
+                background_color: Color::rgb(0.2, 0.2, 0.2).into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+pub(super) fn open_server_browser(mut commands: Commands, settings: Res<GameSettings>) {
+    let mut browser = ServerBrowser::new();
+    for address in &settings.servers {
+        browser.ping(address.clone());
+    }
+    commands.insert_resource(browser);
+}
+
+pub(super) fn close_server_browser(mut commands: Commands) {
+    commands.remove_resource::<ServerBrowser>();
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn multiplayer_menu(
+    mut commands: Commands,
+    mut browser: ResMut<ServerBrowser>,
+    mut settings: ResMut<GameSettings>,
+    player_name: Res<PlayerName>,
+    interaction_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    menu_query: Query<Entity, With<MainMenuUI>>,
+    mut chars: EventReader<ReceivedCharacter>,
+    keyboard: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    time: Res<Time>,
+) {
+    browser.update(time.elapsed_seconds());
+
+    for ev in chars.read() {
+        if !ev.char.is_control() && !ev.char.is_whitespace() && ev.char != ',' && browser.address_input.len() < MAX_ADDRESS_LENGTH {
+            browser.address_input.push(ev.char);
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        browser.address_input.pop();
+    }
+
+    let mut pressed = interaction_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.clone());
+    if keyboard.just_pressed(KeyCode::Return) {
+        pressed = Some(MenuButton::AddServer);
+    } else if keyboard.just_pressed(KeyCode::Escape) {
+        pressed = Some(MenuButton::Back);
+    }
+    match pressed {
+        Some(MenuButton::Join(address)) => {
+            commands.insert_resource(JoinRequest { address, name: player_name.0.clone() });
+            next_state.set(GameState::Loading);
+            return;
+        }
+        Some(MenuButton::AddServer) => {
+            let address = std::mem::take(&mut browser.address_input);
+            if !address.is_empty() && !settings.servers.contains(&address) {
+                settings.servers.push(address.clone());
+                save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+                browser.ping(address);
+            }
+        }
+        Some(MenuButton::RemoveServer(address)) => {
+            settings.servers.retain(|server| *server != address);
+            save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+        }
+        Some(MenuButton::Refresh) => {
+            let lan = browser.lan_games.iter().map(|game| game.address.to_string()).collect::<Vec<_>>();
+            for address in settings.servers.iter().cloned().chain(lan) {
+                browser.ping(address);
+            }
+        }
+        Some(MenuButton::Back) => {
+            next_state.set(GameState::MainMenu);
+            return;
+        }
+        Some(MenuButton::Play | MenuButton::Multiplayer | MenuButton::TogglePack(_) | MenuButton::RaisePack(_)) | None => {}
+    }
+
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let text = |text: String, font_size: f32, color: Color| {
+        TextBundle::from_section(text, TextStyle { font_size, color, ..default() })
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            },
+            MainMenuUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(text("MULTIPLAYER".to_string(), 50.0, Color::GREEN));
+            parent.spawn(text(format!("Playing as {}", player_name.0), 20.0, Color::GRAY));
+
+            parent.spawn(text("Local Network".to_string(), 30.0, Color::WHITE));
+            if browser.lan.is_none() {
+                parent.spawn(text("LAN discovery is unavailable".to_string(), 20.0, Color::GRAY));
+            } else if browser.lan_games.is_empty() {
+                parent.spawn(text("Searching for games...".to_string(), 20.0, Color::GRAY));
+            }
+            for game in &browser.lan_games {
+                let address = game.address.to_string();
+                let label = format!("{} ({address})\n{}", game.motd, browser.status_text(&address));
+                spawn_menu_button(parent, label, 20.0, MenuButton::Join(address));
+            }
+
+            parent.spawn(text("Servers".to_string(), 30.0, Color::WHITE));
+            if settings.servers.is_empty() {
+                parent.spawn(text("No servers added yet".to_string(), 20.0, Color::GRAY));
+            }
+            for address in &settings.servers {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style { column_gap: Val::Px(10.0), ..default() },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        let label = format!("{address}\n{}", browser.status_text(address));
+                        spawn_menu_button(row, label, 20.0, MenuButton::Join(address.clone()));
+                        spawn_menu_button(row, "Remove", 20.0, MenuButton::RemoveServer(address.clone()));
+                    });
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style { column_gap: Val::Px(10.0), align_items: AlignItems::Center, ..default() },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(text(format!("Address: {}_", browser.address_input), 20.0, Color::WHITE));
+                    spawn_menu_button(row, "Add Server", 20.0, MenuButton::AddServer);
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style { column_gap: Val::Px(10.0), ..default() },
+                    ..default()
+                })
+                .with_children(|row| {
+                    spawn_menu_button(row, "Refresh", 20.0, MenuButton::Refresh);
+                    spawn_menu_button(row, "Back", 20.0, MenuButton::Back);
+                });
+        });
+}
+
+pub(super) fn toggle_pause(
+    mut next_state: ResMut<NextState<GameState>>,
+    current_state: Res<State<GameState>>,
+    keyboard: Res<Input<KeyCode>>,
+    mut windows: Query<&mut Window>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        let mut window = windows.single_mut();
+        match current_state.get() {
+            GameState::Playing => {
+                window.cursor.visible = true;
+                window.cursor.grab_mode = CursorGrabMode::None;
+                next_state.set(GameState::Paused);
+            }
+            GameState::Paused | GameState::Crafting | GameState::Console => {
+                window.cursor.visible = false;
+                window.cursor.grab_mode = CursorGrabMode::Locked;
+                next_state.set(GameState::Playing);
+            }
+            GameState::MainMenu | GameState::Multiplayer | GameState::Loading => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn pause_menu(
+    mut commands: Commands,
+    existing_menu: Query<Entity, With<PauseMenu>>,
+    mut settings: ResMut<GameSettings>,
+    resource_packs: Res<ResourcePacks>,
+    keyboard: Res<Input<KeyCode>>,
+    interaction_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    lan: Option<Res<LanAdvertiser>>,
+    client: Option<Res<NetworkClient>>,
+) {
+    for entity in existing_menu.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if keyboard.just_pressed(KeyCode::K) {
+        settings.show_keystrokes = !settings.show_keystrokes;
+    }
+
+    // The reload system picks changes to the enabled list up.
+    let pressed = interaction_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.clone());
+    match pressed {
+        Some(MenuButton::TogglePack(name)) => {
+            toggle_pack(&mut settings.resource_packs, &name);
+            save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+        }
+        Some(MenuButton::RaisePack(name)) => {
+            raise_pack(&mut settings.resource_packs, &name);
+            save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+        }
+        _ => {}
+    }
+
+    if let Some(binding) = settings.currently_binding {
+        if let Some(key) = keyboard.get_just_pressed().next() {
+            match binding {
+                KeyBind::Forward => settings.keybinds.forward = *key,
+                KeyBind::Backward => settings.keybinds.backward = *key,
+                KeyBind::Left => settings.keybinds.left = *key,
+                KeyBind::Right => settings.keybinds.right = *key,
+                KeyBind::Jump => settings.keybinds.jump = *key,
+                KeyBind::Sprint => settings.keybinds.sprint = *key,
+                KeyBind::Inventory => settings.keybinds.inventory = *key,
+                KeyBind::Drop => settings.keybinds.drop = *key,
+            }
+            settings.currently_binding = None;
+            save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+            return;
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::Key1) {
+        settings.currently_binding = Some(KeyBind::Forward);
+    } else if keyboard.just_pressed(KeyCode::Key2) {
+        settings.currently_binding = Some(KeyBind::Backward);
+    } else if keyboard.just_pressed(KeyCode::Key3) {
+        settings.currently_binding = Some(KeyBind::Left);
+    } else if keyboard.just_pressed(KeyCode::Key4) {
+        settings.currently_binding = Some(KeyBind::Right);
+    } else if keyboard.just_pressed(KeyCode::Key5) {
+        settings.currently_binding = Some(KeyBind::Jump);
+    } else if keyboard.just_pressed(KeyCode::Key6) {
+        settings.currently_binding = Some(KeyBind::Sprint);
+    } else if keyboard.just_pressed(KeyCode::Key7) {
+        settings.currently_binding = Some(KeyBind::Inventory);
+    } else if keyboard.just_pressed(KeyCode::Key8) {
+        settings.currently_binding = Some(KeyBind::Drop);
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(20.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "PAUSED",
+                TextStyle {
+                    font_size: 40.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                "Press ESC to resume",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            let lan_text = match (&lan, &client) {
+                (Some(lan), _) => format!("Open to LAN on port {}", lan.advert.port),
+                (None, Some(_)) => "Playing online".to_string(),
+                (None, None) => "Press L to open to LAN".to_string(),
+            };
+            parent.spawn(TextBundle::from_section(
+                lan_text,
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                "Press UP/DOWN to adjust FOV",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Press LEFT/RIGHT to adjust render distance ({} chunks)", settings.render_distance),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Press F to switch fog ({:?})", settings.fog),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Press C to switch clouds ({:?}), [/] to move them (height {})", settings.clouds, settings.cloud_height),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Press K to {} keystrokes ({})", 
+                    if settings.show_keystrokes { "hide" } else { "show" },
+                    if settings.show_keystrokes { "ON" } else { "OFF" }
+                ),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Forward: {:?}", settings.keybinds.forward),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Backward: {:?}", settings.keybinds.backward),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Left: {:?}", settings.keybinds.left),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Right: {:?}", settings.keybinds.right),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Jump: {:?}", settings.keybinds.jump),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Sprint: {:?}", settings.keybinds.sprint),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Inventory: {:?}", settings.keybinds.inventory),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                format!("Drop: {:?}", settings.keybinds.drop),
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                "Press 1-8 to change binds:",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                "Resource packs (click to toggle, top of the list wins):",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                    ..default()
+                },
+            ));
+            if resource_packs.available.is_empty() {
+                parent.spawn(TextBundle::from_section(
+                    format!("No resource packs found in {}/", RESOURCE_PACK_DIR),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ));
+            }
+            // Enabled packs first in priority order, then the rest by name.
+            let disabled = resource_packs.available.iter().filter(|name| !settings.resource_packs.contains(name));
+            for (index, name) in settings.resource_packs.iter().chain(disabled).enumerate() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style { column_gap: Val::Px(10.0), ..default() },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        if index < settings.resource_packs.len() {
+                            spawn_menu_button(row, format!("[{}] {}", index + 1, name), 20.0, MenuButton::TogglePack(name.clone()));
+                            if index > 0 {
+                                spawn_menu_button(row, "Move up", 20.0, MenuButton::RaisePack(name.clone()));
+                            }
+                        } else {
+                            spawn_menu_button(row, format!("[ ] {}", name), 20.0, MenuButton::TogglePack(name.clone()));
+                        }
+                    });
+            }
+        });
+}
+
+pub(super) fn main_menu(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut windows: Query<&mut Window>,
+    interaction_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    menu_query: Query<Entity, With<MainMenuUI>>,
+) {
+    let mut window = windows.single_mut();
+    window.cursor.visible = true;
+    window.cursor.grab_mode = CursorGrabMode::None;
+
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            match button {
+                MenuButton::Multiplayer => next_state.set(GameState::Multiplayer),
+                _ => next_state.set(GameState::Loading),
+            }
+            return;
+        }
+    }
+
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(20.0),
+                    ..default()
+                },
+                ..default()
+            },
+            MainMenuUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "MINCERAFT",
+                TextStyle {
+                    font_size: 80.0,
+                    color: Color::GREEN,
+                    ..default()
+                },
+            ));
+
+            spawn_menu_button(parent, "Play", 30.0, MenuButton::Play);
+            spawn_menu_button(parent, "Multiplayer", 30.0, MenuButton::Multiplayer);
+        });
+}
+
+pub(super) fn cleanup_main_menu(
+    mut commands: Commands,
+    menu_query: Query<Entity, With<MainMenuUI>>,
+) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub(super) fn cleanup_loading_screen(
+    mut commands: Commands,
+    loading_query: Query<Entity, With<LoadingScreenUI>>,
+) {
+    for entity in loading_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub(super) fn cleanup_pause_menu(
+    mut commands: Commands,
+    pause_menu_query: Query<Entity, With<PauseMenu>>,
+) {
+    for entity in pause_menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub(super) fn loading_screen(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut progress: ResMut<WorldGenProgress>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    world_seed: Res<WorldSeed>,
+    loading_query: Query<Entity, With<LoadingScreenUI>>,
+) {
+    for entity in loading_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let perlin = Perlin::new(world_seed.0);
+
+    let size = WORLD_SIZE / 2;
+    let blocks_per_frame = 100;
+
+    if progress.blocks_completed == 0 {
+        world.clear();
+    }
+
+    for _ in 0..blocks_per_frame {
+        if progress.blocks_completed >= progress.total_blocks {
+            initialize_light(&mut world, &registry);
+            next_state.set(GameState::Playing);
+            return;
+        }
+
+        let x = -size + (progress.blocks_completed as i32 % WORLD_SIZE);
+        let z = -size + (progress.blocks_completed as i32 / WORLD_SIZE);
+
+        for (pos, kind) in generate_column(&perlin, x, z) {
+            world.set_raw(pos, Some(kind));
+        }
+
+        progress.blocks_completed += 1;
+    }
+
+    let percentage = (progress.blocks_completed as f32 / progress.total_blocks as f32 * 100.0) as i32;
+    
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        LoadingScreenUI,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            format!("Generating Terrain: {}%", percentage),
+            TextStyle {
+                font_size: 40.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    });
+}
+
+pub(super) fn adjust_render_distance(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<GameSettings>,
+) {
+    // Keys pressed while rebinding belong to the bind.
+    if settings.currently_binding.is_some() {
+        return;
+    }
+    let distance = if keyboard_input.just_pressed(KeyCode::Right) {
+        settings.render_distance + 1
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        settings.render_distance - 1
+    } else if keyboard_input.just_pressed(KeyCode::F) {
+        settings.fog = match settings.fog {
+            FogMode::Linear => FogMode::Exponential,
+            FogMode::Exponential => FogMode::Linear,
+        };
+        save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+        return;
+    } else if keyboard_input.just_pressed(KeyCode::C) {
+        settings.clouds = match settings.clouds {
+            CloudMode::Off => CloudMode::Flat,
+            CloudMode::Flat => CloudMode::Blocky,
+            CloudMode::Blocky => CloudMode::Off,
+        };
+        save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+        return;
+    } else if keyboard_input.any_just_pressed([KeyCode::BracketLeft, KeyCode::BracketRight]) {
+        let step = if keyboard_input.just_pressed(KeyCode::BracketRight) { 8.0 } else { -8.0 };
+        settings.cloud_height = (settings.cloud_height + step).clamp(WORLD_MAX_Y as f32, MAX_CLOUD_HEIGHT);
+        save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+        return;
+    } else {
+        return;
+    };
+    settings.render_distance = distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+    save_settings(&settings).unwrap_or_else(|e| eprintln!("Failed to save settings: {}", e));
+}
+
+pub(super) fn adjust_fov(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<GameSettings>,
+    mut query: Query<&mut Projection, With<Camera3d>>,
+) {
+    let mut changed = false;
+    
+    if keyboard_input.pressed(KeyCode::Up) {
+        settings.fov = (settings.fov + 1.0).min(140.0);
+        changed = true;
+    }
+    if keyboard_input.pressed(KeyCode::Down) {
+        settings.fov = (settings.fov - 1.0).max(30.0);
+        changed = true;
+    }
+
+    if changed {
+        if let Ok(mut projection) = query.get_single_mut() {
+            if let Projection::Perspective(ref mut perspective) = *projection {
+                perspective.fov = settings.fov.to_radians();
+            }
+        }
+    }
+}
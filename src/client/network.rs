@@ -0,0 +1,926 @@
+use super::*;
+
+const MAX_PENDING_INPUTS: usize = 256;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const LAN_MAX_PLAYERS: u32 = 8;
+
+const LAN_MULTICAST_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 2, 60);
+
+const LAN_DISCOVERY_PORT: u16 = 4446;
+
+const LAN_ADVERTISE_INTERVAL: f32 = 1.5;
+
+// LAN games that stop advertising for this long drop off the list.
+const LAN_GAME_TIMEOUT: f32 = 5.0;
+
+const AVATAR_SMOOTHING: f32 = 15.0;
+
+const NAME_TAG_WIDTH: f32 = 160.0;
+
+impl NetworkServer {
+    fn remote_players(&self) -> HashMap<u32, RemotePlayer> {
+        self.players()
+            .map(|player| {
+                let remote = RemotePlayer { name: player.name.clone(), position: player.position, yaw: player.yaw, pitch: player.pitch };
+                (player.player_id, remote)
+            })
+            .collect()
+    }
+}
+
+// What the client's game systems need to act on; the rest is handled inside `NetworkClient::poll`.
+#[derive(Debug, Clone, PartialEq)]
+enum NetworkEvent {
+    LoggedIn { spawn: Vec3, time: f64, seed: u32 },
+    WorldReady,
+    // The player's position and velocity with the server's state and unacknowledged inputs applied.
+    Corrected { position: Vec3, velocity: Vec3 },
+    Message(String),
+}
+
+#[derive(Clone)]
+struct RemotePlayer {
+    name: String,
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+}
+
+#[derive(Resource)]
+pub(super) struct NetworkClient {
+    stream: PacketStream,
+    socket: UdpSocket,
+    token: u64,
+    logged_in: bool,
+    // Set by the server at login; flying anyway would only be undone by its corrections.
+    pub(super) allow_flight: bool,
+    connected_at: Instant,
+    chunks_received: usize,
+    // Inputs applied locally that the server hasn't acknowledged yet, oldest first.
+    pending: VecDeque<MoveInput>,
+    players: HashMap<u32, RemotePlayer>,
+}
+
+impl NetworkClient {
+    fn connect(address: &str, name: &str) -> Result<Self, String> {
+        let address = resolve_server_address(address)?;
+        let stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(|err| format!("Could not connect to {address}: {err}"))?;
+        let local = if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local).map_err(|err| err.to_string())?;
+        socket.connect(address).map_err(|err| err.to_string())?;
+        socket.set_nonblocking(true).map_err(|err| err.to_string())?;
+
+        let mut stream = PacketStream::new(stream).map_err(|err| err.to_string())?;
+        stream.send(&ClientPacket::Handshake { protocol: PROTOCOL_VERSION }.encode());
+        stream.send(&ClientPacket::Login { name: name.to_string() }.encode());
+        stream.flush()?;
+        Ok(Self {
+            stream,
+            socket,
+            token: 0,
+            logged_in: false,
+            allow_flight: false,
+            connected_at: Instant::now(),
+            chunks_received: 0,
+            pending: VecDeque::new(),
+            players: HashMap::new(),
+        })
+    }
+
+    fn send(&mut self, packet: &ClientPacket) {
+        self.stream.send(&packet.encode());
+    }
+
+    // Records an input the player has just applied and sends it, with the others still unacknowledged.
+    fn send_input(&mut self, input: MoveInput) {
+        if !self.logged_in {
+            return;
+        }
+        // Kept as the server will replay it, so reconciling doesn't diverge from the prediction.
+        self.pending.push_back(input.sanitized(self.allow_flight));
+        while self.pending.len() > MAX_PENDING_INPUTS {
+            self.pending.pop_front();
+        }
+        let start = self.pending.len().saturating_sub(MAX_INPUTS_PER_DATAGRAM);
+        let datagram = InputDatagram { token: self.token, inputs: self.pending.range(start..).copied().collect() };
+        let _ = self.socket.send(&datagram.encode());
+    }
+
+    // Applies whatever the server has sent to the local world. An error means the connection is over.
+    fn poll(&mut self, world: &mut WorldBlocks, registry: &BlockRegistry) -> Result<Vec<NetworkEvent>, String> {
+        let mut events = Vec::new();
+        while let Some(bytes) = self.stream.receive()? {
+            match ServerPacket::decode(&bytes)? {
+                ServerPacket::LoginSuccess { token, spawn, time, seed, allow_flight, .. } => {
+                    (self.token, self.logged_in, self.allow_flight) = (token, true, allow_flight);
+                    world.clear();
+                    events.push(NetworkEvent::LoggedIn { spawn, time, seed });
+                }
+                ServerPacket::ChunkData { pos, data } => {
+                    world.chunks.entry(pos).or_default().blocks = decode_chunk(&data)?;
+                    world.dirty.extend(NEIGHBOURS.iter().map(|offset| pos + *offset).chain([pos]));
+                    self.chunks_received += 1;
+                }
+                ServerPacket::WorldReady => events.push(NetworkEvent::WorldReady),
+                ServerPacket::BlockUpdate { pos, kind } => {
+                    world.set_block(registry, pos, kind);
+                }
+                ServerPacket::PlayerJoined { id, name } => {
+                    events.push(NetworkEvent::Message(format!("{name} joined the game")));
+                    self.players.insert(id, RemotePlayer { name, position: Vec3::ZERO, yaw: 0.0, pitch: 0.0 });
+                }
+                ServerPacket::PlayerLeft { id } => {
+                    if let Some(player) = self.players.remove(&id) {
+                        events.push(NetworkEvent::Message(format!("{} left the game", player.name)));
+                    }
+                }
+                ServerPacket::Disconnect { reason } => return Err(reason),
+                ServerPacket::Status { .. } => return Err("Unexpected status reply".to_string()),
+            }
+        }
+        self.stream.flush()?;
+
+        let mut latest: Option<Snapshot> = None;
+        let mut buffer = [0; 65536];
+        while let Ok(len) = self.socket.recv(&mut buffer) {
+            if let Ok(snapshot) = Snapshot::decode(&buffer[..len]) {
+                if latest.as_ref().is_none_or(|latest| !sequence_after(latest.ack, snapshot.ack)) {
+                    latest = Some(snapshot);
+                }
+            }
+        }
+        if let Some(snapshot) = latest {
+            for state in &snapshot.players {
+                if let Some(player) = self.players.get_mut(&state.id) {
+                    (player.position, player.yaw, player.pitch) = (state.position, state.yaw, state.pitch);
+                }
+            }
+            // Rewind to the server's answer and replay what it hasn't seen yet.
+            self.pending.retain(|input| sequence_after(input.sequence, snapshot.ack));
+            let (mut position, mut velocity) = (snapshot.position, snapshot.velocity);
+            for input in &self.pending {
+                position = apply_move_input(world, registry, position, &mut velocity, input);
+            }
+            events.push(NetworkEvent::Corrected { position, velocity });
+        }
+        Ok(events)
+    }
+}
+
+// Accepts `host`, `host:port` or a bare IP; the port defaults to the standard one.
+fn resolve_server_address(address: &str) -> Result<SocketAddr, String> {
+    let address = address.trim();
+    let mut addresses = match address.to_socket_addrs() {
+        Ok(addresses) => addresses,
+        Err(_) => (address.trim_start_matches('[').trim_end_matches(']'), DEFAULT_SERVER_PORT)
+            .to_socket_addrs()
+            .map_err(|err| format!("Could not resolve {address}: {err}"))?,
+    };
+    addresses.next().ok_or_else(|| format!("Could not resolve {address}"))
+}
+
+// A connection being opened on its own thread, for `join_server` to pick up.
+#[derive(Resource)]
+pub(super) struct PendingConnection(Receiver<Result<NetworkClient, String>>);
+
+// Set from `--connect` to join a server instead of generating a world.
+#[derive(Resource, Clone)]
+pub(super) struct JoinRequest {
+    pub(super) address: String,
+    pub(super) name: String,
+}
+
+// Marks another player's body; its name tag is a UI node that follows it on screen.
+#[derive(Component)]
+pub(super) struct RemoteAvatar {
+    id: u32,
+    // The head, which turns with the player's pitch.
+    head: Entity,
+    tag: Entity,
+}
+
+// The name shown to other players, from `--name` or made up at startup.
+#[derive(Resource, Clone)]
+pub(super) struct PlayerName(pub(super) String);
+
+// What a game opened to LAN multicasts: a marker line, then protocol, port and MOTD, one per line.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct LanAdvert {
+    protocol: u32,
+    pub(super) port: u16,
+    motd: String,
+}
+
+impl LanAdvert {
+    fn encode(&self) -> String {
+        format!("MINCERAFT\n{}\n{}\n{}", self.protocol, self.port, self.motd)
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.splitn(4, '\n');
+        if lines.next()? != "MINCERAFT" {
+            return None;
+        }
+        Some(Self { protocol: lines.next()?.parse().ok()?, port: lines.next()?.parse().ok()?, motd: lines.next()?.to_string() })
+    }
+}
+
+#[derive(Resource)]
+pub(super) struct LanAdvertiser {
+    socket: UdpSocket,
+    pub(super) advert: LanAdvert,
+    since_sent: f32,
+}
+
+// Joins the LAN multicast group. Address reuse lets several games on one machine listen at once.
+fn lan_discovery_socket() -> std::io::Result<UdpSocket> {
+    let socket = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::DGRAM, Some(socket2::Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, LAN_DISCOVERY_PORT)).into())?;
+    socket.join_multicast_v4(&LAN_MULTICAST_GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ServerStatus {
+    protocol: u32,
+    motd: String,
+    online: u32,
+    max_players: u32,
+    // Round trip from connecting to the reply.
+    ping: Duration,
+}
+
+// Asks a server for its status over a short-lived blocking connection; run it off the main thread.
+fn query_status(address: &str) -> Result<ServerStatus, String> {
+    let resolved = resolve_server_address(address)?;
+    let started = Instant::now();
+    let mut stream = TcpStream::connect_timeout(&resolved, CONNECT_TIMEOUT).map_err(|err| err.to_string())?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT)).map_err(|err| err.to_string())?;
+    let request = ClientPacket::StatusRequest.encode();
+    stream
+        .write_all(&[&(request.len() as u32).to_be_bytes()[..], &request].concat())
+        .map_err(|err| err.to_string())?;
+
+    let mut len = [0; 4];
+    stream.read_exact(&mut len).map_err(|err| err.to_string())?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_PACKET_SIZE {
+        return Err(format!("Packet too large: {len} bytes"));
+    }
+    let mut reply = vec![0; len];
+    stream.read_exact(&mut reply).map_err(|err| err.to_string())?;
+    match ServerPacket::decode(&reply)? {
+        ServerPacket::Status { protocol, motd, online, max_players } => {
+            Ok(ServerStatus { protocol, motd, online, max_players, ping: started.elapsed() })
+        }
+        ServerPacket::Disconnect { reason } => Err(reason),
+        other => Err(format!("Unexpected reply: {other:?}")),
+    }
+}
+
+pub(super) struct LanGame {
+    pub(super) address: SocketAddr,
+    pub(super) motd: String,
+    last_seen: f32,
+}
+
+type StatusResult = Result<ServerStatus, String>;
+
+// State behind the multiplayer screen. Pings run on the async compute pool and come back over the channel.
+#[derive(Resource)]
+pub(super) struct ServerBrowser {
+    // None when the discovery port couldn't be opened; LAN games just won't show.
+    pub(super) lan: Option<UdpSocket>,
+    pub(super) lan_games: Vec<LanGame>,
+    // Keyed by address as listed; None while a ping is in flight.
+    statuses: HashMap<String, Option<StatusResult>>,
+    pub(super) address_input: String,
+    sender: Sender<(String, StatusResult)>,
+    receiver: Receiver<(String, StatusResult)>,
+}
+
+impl ServerBrowser {
+    pub(super) fn new() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let lan = lan_discovery_socket().map_err(|err| eprintln!("LAN discovery unavailable: {err}")).ok();
+        Self { lan, lan_games: Vec::new(), statuses: HashMap::new(), address_input: String::new(), sender, receiver }
+    }
+
+    pub(super) fn ping(&mut self, address: String) {
+        self.statuses.insert(address.clone(), None);
+        let sender = self.sender.clone();
+        // The ping blocks on the network for seconds at worst, which would hold up a task pool thread.
+        std::thread::spawn(move || {
+            let status = query_status(&address);
+            // The receiver only goes away when the screen closes.
+            let _ = sender.send((address, status));
+        });
+    }
+
+    pub(super) fn update(&mut self, now: f32) {
+        let mut adverts = Vec::new();
+        if let Some(socket) = &self.lan {
+            let mut buffer = [0; 1024];
+            while let Ok((len, from)) = socket.recv_from(&mut buffer) {
+                if let Some(advert) = std::str::from_utf8(&buffer[..len]).ok().and_then(LanAdvert::parse) {
+                    adverts.push((SocketAddr::new(from.ip(), advert.port), advert.motd));
+                }
+            }
+        }
+        for (address, motd) in adverts {
+            match self.lan_games.iter_mut().find(|game| game.address == address) {
+                Some(game) => (game.motd, game.last_seen) = (motd, now),
+                None => {
+                    self.lan_games.push(LanGame { address, motd, last_seen: now });
+                    self.ping(address.to_string());
+                }
+            }
+        }
+        self.lan_games.retain(|game| now - game.last_seen < LAN_GAME_TIMEOUT);
+
+        for (address, status) in self.receiver.try_iter() {
+            if let Some(entry) = self.statuses.get_mut(&address) {
+                *entry = Some(status);
+            }
+        }
+    }
+
+    pub(super) fn status_text(&self, address: &str) -> String {
+        match self.statuses.get(address) {
+            None | Some(None) => "Pinging...".to_string(),
+            Some(Some(Err(err))) => format!("Can't connect: {err}"),
+            Some(Some(Ok(status))) if status.protocol != PROTOCOL_VERSION => format!("{} - incompatible version", status.motd),
+            Some(Some(Ok(status))) => format!(
+                "{} - {}/{} players - {} ms",
+                status.motd,
+                status.online,
+                status.max_players,
+                status.ping.as_millis()
+            ),
+        }
+    }
+}
+
+// Hosts the singleplayer world for the local network, from the pause menu.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn open_to_lan(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<GameSettings>,
+    server: Option<Res<NetworkServer>>,
+    client: Option<Res<NetworkClient>>,
+    player_name: Res<PlayerName>,
+    mut command_line: ResMut<CommandLine>,
+    time: Res<Time>,
+) {
+    if !keyboard.just_pressed(KeyCode::L) || settings.currently_binding.is_some() || server.is_some() || client.is_some() {
+        return;
+    }
+    let motd = format!("{}'s world", player_name.0);
+    let opened = NetworkServer::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)), LAN_MAX_PLAYERS, motd.clone())
+        .and_then(|server| Ok((server, UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?)));
+    match opened {
+        Ok((mut server, socket)) => {
+            // Guests get the same double-jump flight as the host.
+            server.allow_flight = true;
+            let port = server.local_addr().port();
+            command_line.push_message(format!("Local game hosted on port {port}"), time.elapsed_seconds());
+            commands.insert_resource(server);
+            commands.insert_resource(LanAdvertiser {
+                socket,
+                advert: LanAdvert { protocol: PROTOCOL_VERSION, port, motd },
+                since_sent: LAN_ADVERTISE_INTERVAL,
+            });
+        }
+        Err(err) => command_line.push_message(format!("Could not open to LAN: {err}"), time.elapsed_seconds()),
+    }
+}
+
+// Runs the server inside a game opened to LAN, with the local player taking part as the host.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn host_lan_game(
+    mut server: ResMut<NetworkServer>,
+    mut advertiser: ResMut<LanAdvertiser>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    world_time: Res<WorldTime>,
+    world_seed: Res<WorldSeed>,
+    mut edits: EventReader<BlockEdited>,
+    player_query: Query<(&Transform, &Player)>,
+    player_name: Res<PlayerName>,
+    mut command_line: ResMut<CommandLine>,
+    time: Res<Time>,
+) {
+    let (transform, player) = player_query.single();
+    let host = PlayerState { id: HOST_PLAYER_ID, position: transform.translation, yaw: player.yaw, pitch: player.pitch };
+    server.host = Some((player_name.0.clone(), host));
+    for edit in edits.read() {
+        server.broadcast(&ServerPacket::BlockUpdate { pos: edit.pos, kind: edit.kind });
+    }
+    server.update(&mut world, &registry, &world_time, *world_seed);
+    for message in std::mem::take(&mut server.messages) {
+        command_line.push_message(message, time.elapsed_seconds());
+    }
+
+    advertiser.since_sent += time.delta_seconds();
+    if advertiser.since_sent >= LAN_ADVERTISE_INTERVAL {
+        advertiser.since_sent = 0.0;
+        // Fails without a multicast route, which only means there's no one to tell.
+        let _ = advertiser.socket.send_to(
+            advertiser.advert.encode().as_bytes(),
+            SocketAddrV4::new(LAN_MULTICAST_GROUP, LAN_DISCOVERY_PORT),
+        );
+    }
+}
+
+#[derive(Component)]
+pub(super) struct AvatarHead;
+
+#[derive(Component)]
+pub(super) struct NameTag;
+
+// Drops the connection and returns to the main menu with the reason in chat.
+fn leave_server(
+    commands: &mut Commands,
+    next_state: &mut NextState<GameState>,
+    command_line: &mut CommandLine,
+    progress: &mut WorldGenProgress,
+    reason: String,
+    now: f32,
+) {
+    commands.remove_resource::<NetworkClient>();
+    commands.remove_resource::<PendingConnection>();
+    commands.remove_resource::<JoinRequest>();
+    command_line.push_message(format!("Disconnected: {reason}"), now);
+    // The next singleplayer game has to generate its world again.
+    progress.blocks_completed = 0;
+    next_state.set(GameState::MainMenu);
+}
+
+// Stands in for `loading_screen` when joining a server: connects, then waits for the world to download.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn join_server(
+    mut commands: Commands,
+    request: Res<JoinRequest>,
+    pending: Option<Res<PendingConnection>>,
+    mut client: Option<ResMut<NetworkClient>>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    mut world_time: ResMut<WorldTime>,
+    mut world_seed: ResMut<WorldSeed>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut command_line: ResMut<CommandLine>,
+    mut progress: ResMut<WorldGenProgress>,
+    time: Res<Time>,
+    loading_query: Query<Entity, With<LoadingScreenUI>>,
+) {
+    for entity in loading_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let status = match (client.as_deref_mut(), pending) {
+        // Resolving and connecting block, so they happen off the main thread.
+        (None, None) => {
+            let (sender, receiver) = crossbeam_channel::bounded(1);
+            let (address, name) = (request.address.clone(), request.name.clone());
+            std::thread::spawn(move || {
+                let _ = sender.send(NetworkClient::connect(&address, &name));
+            });
+            commands.insert_resource(PendingConnection(receiver));
+            format!("Connecting to {}...", request.address)
+        }
+        (None, Some(pending)) => match pending.0.try_recv() {
+            Ok(Ok(client)) => {
+                commands.remove_resource::<PendingConnection>();
+                commands.insert_resource(client);
+                format!("Connecting to {}...", request.address)
+            }
+            Ok(Err(reason)) => {
+                leave_server(&mut commands, &mut next_state, &mut command_line, &mut progress, reason, time.elapsed_seconds());
+                return;
+            }
+            Err(TryRecvError::Empty) => format!("Connecting to {}...", request.address),
+            Err(TryRecvError::Disconnected) => {
+                let reason = "Connecting failed".to_string();
+                leave_server(&mut commands, &mut next_state, &mut command_line, &mut progress, reason, time.elapsed_seconds());
+                return;
+            }
+        },
+        (Some(client), _) => {
+            if !client.logged_in && client.connected_at.elapsed() > LOGIN_TIMEOUT {
+                let reason = "Timed out waiting for the server".to_string();
+                leave_server(&mut commands, &mut next_state, &mut command_line, &mut progress, reason, time.elapsed_seconds());
+                return;
+            }
+            let events = match client.poll(&mut world, &registry) {
+                Ok(events) => events,
+                Err(reason) => {
+                    leave_server(&mut commands, &mut next_state, &mut command_line, &mut progress, reason, time.elapsed_seconds());
+                    return;
+                }
+            };
+            for event in events {
+                match event {
+                    NetworkEvent::LoggedIn { spawn, time, seed } => {
+                        (world_time.ticks, world_seed.0) = (time, seed);
+                        let (mut transform, mut velocity) = player_query.single_mut();
+                        (transform.translation, velocity.0) = (spawn, Vec3::ZERO);
+                    }
+                    NetworkEvent::WorldReady => {
+                        initialize_light(&mut world, &registry);
+                        next_state.set(GameState::Playing);
+                        return;
+                    }
+                    NetworkEvent::Message(message) => command_line.push_message(message, time.elapsed_seconds()),
+                    NetworkEvent::Corrected { .. } => {}
+                }
+            }
+            if client.logged_in {
+                format!("Downloading Terrain: {} chunks", client.chunks_received)
+            } else {
+                format!("Connecting to {}...", request.address)
+            }
+        }
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        },
+        LoadingScreenUI,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            status,
+            TextStyle {
+                font_size: 40.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        ));
+    });
+}
+
+// Sends this frame's input and block edits, then applies what the server sent back.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn client_network(
+    mut commands: Commands,
+    mut client: ResMut<NetworkClient>,
+    mut local_input: ResMut<LocalInput>,
+    mut edits: EventReader<BlockEdited>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut command_line: ResMut<CommandLine>,
+    mut progress: ResMut<WorldGenProgress>,
+    time: Res<Time>,
+) {
+    if let Some(input) = local_input.current.take() {
+        client.send_input(input);
+    }
+    for edit in edits.read() {
+        client.send(&ClientPacket::SetBlock { pos: edit.pos, kind: edit.kind });
+    }
+
+    let events = match client.poll(&mut world, &registry) {
+        Ok(events) => events,
+        Err(reason) => {
+            leave_server(&mut commands, &mut next_state, &mut command_line, &mut progress, reason, time.elapsed_seconds());
+            return;
+        }
+    };
+    for event in events {
+        match event {
+            NetworkEvent::Corrected { position, velocity } => {
+                let (mut transform, mut player_velocity) = player_query.single_mut();
+                (transform.translation, player_velocity.0) = (position, velocity);
+            }
+            NetworkEvent::Message(message) => command_line.push_message(message, time.elapsed_seconds()),
+            NetworkEvent::LoggedIn { .. } | NetworkEvent::WorldReady => {}
+        }
+    }
+}
+
+pub(super) fn disconnect_on_exit(mut exit_events: EventReader<AppExit>, client: Option<ResMut<NetworkClient>>) {
+    if let (Some(mut client), Some(_)) = (client, exit_events.read().next()) {
+        client.send(&ClientPacket::Disconnect);
+        let _ = client.stream.flush();
+    }
+}
+
+// Keeps a box-shaped body with a floating name for every other player on the server.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(super) fn update_remote_avatars(
+    mut commands: Commands,
+    client: Option<Res<NetworkClient>>,
+    server: Option<Res<NetworkServer>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut avatars: Query<(Entity, &RemoteAvatar, &mut Transform)>,
+    mut heads: Query<&mut Transform, (With<AvatarHead>, Without<RemoteAvatar>)>,
+    mut tags: Query<(&mut Style, &mut Visibility), With<NameTag>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<Player>>,
+    time: Res<Time>,
+) {
+    let hosted = server.map(|server| server.remote_players());
+    let players = client.as_ref().map(|client| &client.players).or(hosted.as_ref());
+    let (camera, camera_transform) = camera_query.single();
+    let blend = 1.0 - (-AVATAR_SMOOTHING * time.delta_seconds()).exp();
+    let mut shown = HashSet::new();
+
+    for (entity, avatar, mut transform) in avatars.iter_mut() {
+        let Some(player) = players.and_then(|players| players.get(&avatar.id)) else {
+            commands.entity(entity).despawn_recursive();
+            commands.entity(avatar.tag).despawn_recursive();
+            continue;
+        };
+        shown.insert(avatar.id);
+        transform.translation = transform.translation.lerp(player.position, blend);
+        transform.rotation = Quat::from_rotation_y(player.yaw);
+        if let Ok(mut head) = heads.get_mut(avatar.head) {
+            head.rotation = Quat::from_rotation_x(player.pitch);
+        }
+
+        let above = transform.translation + Vec3::Y * (PLAYER_HEIGHT * 0.5 + 0.4);
+        if let Ok((mut style, mut visibility)) = tags.get_mut(avatar.tag) {
+            match camera.world_to_viewport(camera_transform, above) {
+                Some(screen) => {
+                    style.left = Val::Px(screen.x - NAME_TAG_WIDTH * 0.5);
+                    style.top = Val::Px(screen.y);
+                    *visibility = Visibility::Visible;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+
+    for (&id, player) in players.into_iter().flatten() {
+        if shown.contains(&id) {
+            continue;
+        }
+        let material = materials.add(StandardMaterial {
+            base_color: Color::hsl((id * 67 % 360) as f32, 0.6, 0.5),
+            ..default()
+        });
+        let head = commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::Cube { size: 0.5 })),
+                    material: material.clone(),
+                    transform: Transform::from_xyz(0.0, 0.75, 0.0),
+                    ..default()
+                },
+                AvatarHead,
+            ))
+            .id();
+        let tag = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(NAME_TAG_WIDTH),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                NameTag,
+            ))
+            .with_children(|parent| {
+                parent.spawn(
+                    TextBundle::from_section(
+                        player.name.clone(),
+                        TextStyle {
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                            ..default()
+                        },
+                    )
+                    .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.4)),
+                );
+            })
+            .id();
+        commands
+            .spawn((
+                PbrBundle {
+                    mesh: meshes.add(Mesh::from(shape::Box::new(PLAYER_WIDTH, 1.5, 0.3))),
+                    material,
+                    transform: Transform::from_translation(player.position),
+                    ..default()
+                },
+                RemoteAvatar { id, head, tag },
+            ))
+            .add_child(head);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::pump;
+
+    #[test]
+    fn players_share_a_world_over_localhost() {
+        struct TestClient {
+            client: NetworkClient,
+            world: WorldBlocks,
+            events: Vec<NetworkEvent>,
+        }
+
+        impl TestClient {
+            fn poll(&mut self, registry: &BlockRegistry) {
+                let events = self.client.poll(&mut self.world, registry).unwrap();
+                self.events.extend(events);
+            }
+        }
+
+        let registry = BlockRegistry::default();
+        let mut world = WorldBlocks::default();
+        for x in -4..=4 {
+            for z in -4..=4 {
+                world.set_raw(IVec3::new(x, 0, z), Some(BlockKind::Stone));
+            }
+        }
+        let mut server = NetworkServer::bind("127.0.0.1:0".parse().unwrap(), 2, "Test".to_string()).unwrap();
+        let address = server.local_addr().to_string();
+        let join = |name: &str| TestClient {
+            client: NetworkClient::connect(&address, name).unwrap(),
+            world: WorldBlocks::default(),
+            events: Vec::new(),
+        };
+        let (mut alice, mut bob) = (join("alice"), join("bob"));
+
+        pump(&mut server, &mut world, &registry, || {
+            alice.poll(&registry);
+            bob.poll(&registry);
+            alice.client.players.len() == 1 && bob.client.players.len() == 1
+        });
+        assert_eq!(alice.events[0], NetworkEvent::LoggedIn { spawn: Vec3::new(0.0, 1.5, 0.0), time: 0.0, seed: 7 });
+        assert!(alice.events.contains(&NetworkEvent::WorldReady));
+        assert!(alice.events.contains(&NetworkEvent::Message("bob joined the game".to_string())));
+        assert_eq!(bob.world.get(IVec3::new(4, 0, -4)), Some(BlockKind::Stone));
+        assert_eq!(bob.world.get(IVec3::new(5, 0, 0)), None);
+        assert_eq!(bob.client.players.values().next().unwrap().name, "alice");
+
+        // Accepted edits reach everyone; rejected ones are undone for the sender.
+        alice.client.send(&ClientPacket::SetBlock { pos: IVec3::new(1, 1, 0), kind: Some(BlockKind::Planks) });
+        bob.world.set_raw(IVec3::ZERO, Some(BlockKind::Glass));
+        bob.client.send(&ClientPacket::SetBlock { pos: IVec3::ZERO, kind: Some(BlockKind::Glass) });
+        pump(&mut server, &mut world, &registry, || {
+            alice.poll(&registry);
+            bob.poll(&registry);
+            bob.world.get(IVec3::new(1, 1, 0)).is_some() && bob.world.get(IVec3::ZERO) == Some(BlockKind::Stone)
+        });
+        assert_eq!(world.get(IVec3::new(1, 1, 0)), Some(BlockKind::Planks));
+        assert_eq!(world.get(IVec3::ZERO), Some(BlockKind::Stone));
+
+        // The server replays movement and its answer matches the client's own prediction.
+        let step = MoveInput { sequence: 1, dt: 0.05, walk: Vec2::new(1.0, 0.0), jump: false, lift: None, flying: false, yaw: 0.3, pitch: 0.0 };
+        let mut predicted_velocity = Vec3::ZERO;
+        let predicted = apply_move_input(&alice.world, &registry, Vec3::new(0.0, 1.5, 0.0), &mut predicted_velocity, &step);
+        alice.client.send_input(step);
+        bob.client.send_input(MoveInput { walk: Vec2::ZERO, ..step });
+        alice.events.clear();
+        pump(&mut server, &mut world, &registry, || {
+            alice.poll(&registry);
+            bob.poll(&registry);
+            alice.client.pending.is_empty() && bob.client.players.values().any(|player| player.position.x > 0.0)
+        });
+        assert!(alice.events.contains(&NetworkEvent::Corrected { position: predicted, velocity: predicted_velocity }));
+        assert_eq!(bob.client.players.values().next().unwrap().yaw, 0.3);
+
+        // A long frame is clamped the same way on both ends.
+        let long = MoveInput { sequence: 2, dt: 0.5, walk: Vec2::new(2.0, 0.0), ..step };
+        let mut long_velocity = predicted_velocity;
+        let long_predicted = apply_move_input(&alice.world, &registry, predicted, &mut long_velocity, &long.sanitized(false));
+        alice.client.send_input(long);
+        assert_eq!(alice.client.pending.back().unwrap().dt, MAX_INPUT_DT);
+        alice.events.clear();
+        pump(&mut server, &mut world, &registry, || {
+            alice.poll(&registry);
+            alice.client.pending.is_empty()
+        });
+        assert!(alice.events.contains(&NetworkEvent::Corrected { position: long_predicted, velocity: long_velocity }));
+
+        // Flooding inputs doesn't buy more movement than the wall clock allows.
+        let alice_x = |server: &NetworkServer| server.players().find(|player| player.name == "alice").unwrap().position.x;
+        let before = alice_x(&server);
+        let started = Instant::now();
+        let sprint = Vec2::new(PLAYER_BASE_SPEED * SPRINT_MULTIPLIER * MAX_INPUT_DT, 0.0);
+        for batch in 0..5 {
+            let inputs = (0..8).map(|index| MoveInput { sequence: 3 + batch * 8 + index, dt: MAX_INPUT_DT, walk: sprint, ..step }).collect();
+            alice.client.socket.send(&InputDatagram { token: alice.client.token, inputs }.encode()).unwrap();
+        }
+        for _ in 0..20 {
+            server.update(&mut world, &registry, &WorldTime::default(), WorldSeed(7));
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let budget = MAX_MOVE_ALLOWANCE + started.elapsed().as_secs_f32();
+        let moved = alice_x(&server) - before;
+        assert!(moved > 0.0 && moved <= sprint.x * budget + 0.01, "moved {moved}");
+
+        let mut carol = NetworkClient::connect(&address, "carol").unwrap();
+        let mut carol_world = WorldBlocks::default();
+        let mut refused = None;
+        pump(&mut server, &mut world, &registry, || {
+            refused = carol.poll(&mut carol_world, &registry).err();
+            refused.is_some()
+        });
+        assert_eq!(refused.as_deref(), Some("Server is full"));
+
+        let mut outdated = PacketStream::new(TcpStream::connect(&address).unwrap()).unwrap();
+        outdated.send(&ClientPacket::Handshake { protocol: PROTOCOL_VERSION + 1 }.encode());
+        outdated.flush().unwrap();
+        let mut reply = None;
+        pump(&mut server, &mut world, &registry, || {
+            reply = outdated.receive().unwrap();
+            reply.is_some()
+        });
+        let Ok(ServerPacket::Disconnect { reason }) = ServerPacket::decode(&reply.unwrap()) else {
+            panic!("expected a disconnect");
+        };
+        assert!(reason.starts_with("Incompatible version"), "{reason}");
+
+        drop(bob);
+        pump(&mut server, &mut world, &registry, || {
+            alice.poll(&registry);
+            alice.client.players.is_empty()
+        });
+        assert!(alice.events.contains(&NetworkEvent::Message("bob left the game".to_string())));
+        assert_eq!(server.players().count(), 1);
+    }
+
+    #[test]
+    fn lan_adverts_round_trip() {
+        let advert = LanAdvert { protocol: PROTOCOL_VERSION, port: 41234, motd: "Steve's world\nwith a newline".to_string() };
+        assert_eq!(LanAdvert::parse(&advert.encode()), Some(advert));
+        assert_eq!(LanAdvert::parse("[MOTD]A Minecraft world[/MOTD][AD]25565[/AD]"), None);
+        assert_eq!(LanAdvert::parse("MINCERAFT\n2\nport\nmotd"), None);
+        assert_eq!(LanAdvert::parse("MINCERAFT\n2\n25565"), None);
+    }
+
+    #[test]
+    fn lan_host_answers_status_and_plays_alongside_guests() {
+        let registry = BlockRegistry::default();
+        let mut world = WorldBlocks::default();
+        world.set_raw(IVec3::ZERO, Some(BlockKind::Stone));
+        let mut server = NetworkServer::bind("127.0.0.1:0".parse().unwrap(), 2, "Steve's world".to_string()).unwrap();
+        let host = PlayerState { id: HOST_PLAYER_ID, position: Vec3::new(2.0, 1.5, 0.0), yaw: 1.0, pitch: 0.0 };
+        server.host = Some(("Steve".to_string(), host));
+        let address = server.local_addr().to_string();
+
+        let pinging = std::thread::spawn({
+            let address = address.clone();
+            move || query_status(&address)
+        });
+        pump(&mut server, &mut world, &registry, || pinging.is_finished());
+        let status = pinging.join().unwrap().unwrap();
+        assert_eq!((status.protocol, status.motd.as_str(), status.online, status.max_players), (PROTOCOL_VERSION, "Steve's world", 1, 2));
+        // The pinger hangs up once answered, which isn't worth a log line.
+        for _ in 0..500 {
+            if server.connections.is_empty() {
+                break;
+            }
+            server.update(&mut world, &registry, &WorldTime::default(), WorldSeed(7));
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(server.connections.is_empty());
+        assert!(server.messages.is_empty(), "{:?}", server.messages);
+
+        let mut impostor = NetworkClient::connect(&address, "steve").unwrap();
+        let mut guest = NetworkClient::connect(&address, "Alex").unwrap();
+        let (mut impostor_world, mut guest_world) = (WorldBlocks::default(), WorldBlocks::default());
+        let mut refused = None;
+        pump(&mut server, &mut world, &registry, || {
+            refused = refused.take().or(impostor.poll(&mut impostor_world, &registry).err());
+            guest.poll(&mut guest_world, &registry).unwrap();
+            refused.is_some() && guest.players.contains_key(&HOST_PLAYER_ID)
+        });
+        assert_eq!(refused.as_deref(), Some("steve is already playing"));
+        assert_eq!(guest.players[&HOST_PLAYER_ID].name, "Steve");
+        assert_eq!(server.remote_players()[&1].name, "Alex");
+
+        // The host's edits go out like anyone's, and the host shows up in snapshots.
+        server.broadcast(&ServerPacket::BlockUpdate { pos: IVec3::Y, kind: Some(BlockKind::Torch) });
+        guest.send_input(MoveInput { sequence: 1, dt: 0.05, walk: Vec2::ZERO, jump: false, lift: None, flying: false, yaw: 0.0, pitch: 0.0 });
+        pump(&mut server, &mut world, &registry, || {
+            guest.poll(&mut guest_world, &registry).unwrap();
+            guest_world.get(IVec3::Y).is_some() && guest.players[&HOST_PLAYER_ID].position == host.position
+        });
+        assert_eq!(guest.players[&HOST_PLAYER_ID].yaw, 1.0);
+    }
+}
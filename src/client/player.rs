@@ -0,0 +1,326 @@
+use super::*;
+
+#[derive(Resource, Default)]
+pub(super) struct BreakProgress {
+    target: Option<IVec3>,
+    elapsed: f32,
+}
+
+#[derive(Component)]
+pub(super) struct BreakOverlay;
+
+const MOUSE_SENSITIVITY: f32 = 0.002;
+
+// Holding the button in creative breaks a block this often, as vanilla's six tick delay does.
+const CREATIVE_BREAK_DELAY: f32 = 0.3;
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn player_control(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Transform, &mut Player)>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut motion_evr: EventReader<MouseMotion>,
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    flight_query: Query<(), With<Flight>>,
+    mut local_input: ResMut<LocalInput>,
+    client: Option<Res<NetworkClient>>,
+) {
+    let (entity, mut transform, mut player) = query.single_mut();
+    // A server decides for itself whether its players may fly.
+    let may_fly = client.is_none_or(|client| client.allow_flight);
+    if !may_fly && !flight_query.is_empty() {
+        commands.entity(entity).remove::<Flight>();
+    }
+
+    if may_fly && keyboard_input.just_pressed(settings.keybinds.jump) {
+        let current_time = time.elapsed_seconds();
+        if let Some(last_time) = player.last_jump_time {
+            if current_time - last_time < 0.3 {
+                if flight_query.is_empty() {
+                    commands.entity(entity).insert(Flight);
+                } else {
+                    commands.entity(entity).remove::<Flight>();
+                }
+            }
+        }
+        player.last_jump_time = Some(current_time);
+    }
+
+    for ev in motion_evr.read() {
+        player.pitch -= ev.delta.y * MOUSE_SENSITIVITY;
+        player.yaw -= ev.delta.x * MOUSE_SENSITIVITY;
+    }
+
+    player.pitch = player.pitch.clamp(-1.5, 1.5);
+    transform.rotation = Quat::from_euler(EulerRot::YXZ, player.yaw, player.pitch, 0.0);
+
+    let sprint_multiplier = if keyboard_input.pressed(settings.keybinds.sprint) { SPRINT_MULTIPLIER } else { 1.0 };
+    let speed = PLAYER_BASE_SPEED * sprint_multiplier;
+    
+    let forward = -transform.forward();
+    let right = transform.right();
+
+    let forward = Vec3::new(forward.x, 0.0, forward.z).normalize();
+    let right = Vec3::new(right.x, 0.0, right.z).normalize();
+
+    let mut movement = match (
+        keyboard_input.pressed(settings.keybinds.forward),
+        keyboard_input.pressed(settings.keybinds.backward),
+        keyboard_input.pressed(settings.keybinds.right), 
+        keyboard_input.pressed(settings.keybinds.left)
+    ) {
+        (true, false, false, false) => -forward,
+        (false, true, false, false) => forward,
+        (false, false, true, false) => right,
+        (false, false, false, true) => -right,
+        (true, false, true, false) => (-forward + right).normalize(),
+        (true, false, false, true) => (-forward - right).normalize(),
+        (false, true, true, false) => (forward + right).normalize(),
+        (false, true, false, true) => (forward - right).normalize(),
+        _ => Vec3::ZERO,
+    };
+
+    let flying = may_fly && flight_query.contains(entity);
+    let mut lift = flying.then(|| {
+        if keyboard_input.pressed(settings.keybinds.jump) {
+            PLAYER_BASE_SPEED * time.delta_seconds()
+        } else if keyboard_input.pressed(KeyCode::ShiftLeft) {
+            -PLAYER_BASE_SPEED * time.delta_seconds()
+        } else {
+            0.0
+        }
+    });
+
+    if movement != Vec3::ZERO {
+        movement = movement.normalize() * speed * time.delta_seconds();
+    }
+
+    if may_fly && keyboard_input.pressed(settings.keybinds.sprint) {
+        lift = Some(10.0);
+    }
+    
+    if may_fly && keyboard_input.pressed(settings.keybinds.sneak) {
+        lift = Some(-10.0);
+    }
+
+    local_input.sequence = local_input.sequence.wrapping_add(1);
+    // Predicted with the same limits a server applies, so long frames don't move further here than there.
+    local_input.current = Some(
+        MoveInput {
+            sequence: local_input.sequence,
+            dt: time.delta_seconds(),
+            walk: Vec2::new(movement.x, movement.z),
+            jump: keyboard_input.just_pressed(settings.keybinds.jump),
+            lift,
+            flying,
+            yaw: player.yaw,
+            pitch: player.pitch,
+        }
+        .sanitized(may_fly),
+    );
+}
+
+pub(super) fn physics_system(
+    local_input: Res<LocalInput>,
+    mut player_query: Query<(&mut Transform, &mut Velocity), With<Player>>,
+    world: Res<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+) {
+    let Some(input) = local_input.current else {
+        return;
+    };
+    let (mut player_transform, mut velocity) = player_query.single_mut();
+    player_transform.translation = apply_move_input(&world, &registry, player_transform.translation, &mut velocity.0, &input);
+}
+
+// Walks the voxel grid along the ray and returns the first solid block with the face normal it was entered through.
+pub(super) fn raycast_blocks(
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+    is_solid: impl Fn(IVec3) -> bool,
+) -> Option<(IVec3, IVec3)> {
+    let direction = direction.normalize_or_zero();
+    if direction == Vec3::ZERO {
+        return None;
+    }
+
+    // Blocks are centred on integer coordinates.
+    let start = origin + Vec3::splat(0.5);
+    let mut cell = start.floor().as_ivec3();
+    let step = direction.signum().as_ivec3();
+    let t_delta = direction.recip().abs();
+    let mut t_max = Vec3::new(
+        axis_boundary(start.x, direction.x),
+        axis_boundary(start.y, direction.y),
+        axis_boundary(start.z, direction.z),
+    );
+    let mut normal = IVec3::ZERO;
+    let mut distance = 0.0;
+
+    while distance <= max_distance {
+        if is_solid(cell) {
+            return Some((cell, normal));
+        }
+
+        if t_max.x < t_max.y && t_max.x < t_max.z {
+            distance = t_max.x;
+            t_max.x += t_delta.x;
+            cell.x += step.x;
+            normal = IVec3::new(-step.x, 0, 0);
+        } else if t_max.y < t_max.z {
+            distance = t_max.y;
+            t_max.y += t_delta.y;
+            cell.y += step.y;
+            normal = IVec3::new(0, -step.y, 0);
+        } else {
+            distance = t_max.z;
+            t_max.z += t_delta.z;
+            cell.z += step.z;
+            normal = IVec3::new(0, 0, -step.z);
+        }
+    }
+
+    None
+}
+
+fn axis_boundary(start: f32, direction: f32) -> f32 {
+    if direction > 0.0 {
+        (start.floor() + 1.0 - start) / direction
+    } else if direction < 0.0 {
+        (start - start.floor()) / -direction
+    } else {
+        f32::INFINITY
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn block_interaction(
+    mouse: Res<Input<MouseButton>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    mut inventory: ResMut<Inventory>,
+    mut crafting: ResMut<CraftingGrid>,
+    game_mode: Res<CurrentGameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut windows: Query<&mut Window>,
+    mut edits: EventWriter<BlockEdited>,
+) {
+    let transform = player_query.single();
+    let Some((hit, normal)) = raycast_blocks(
+        transform.translation,
+        transform.forward(),
+        PLAYER_REACH,
+        |pos| world.is_targetable(&registry, pos),
+    ) else {
+        return;
+    };
+
+    if mouse.just_pressed(MouseButton::Right) {
+        if world.get(hit) == Some(BlockKind::CraftingTable) {
+            *crafting = CraftingGrid::new(3);
+            let mut window = windows.single_mut();
+            window.cursor.visible = true;
+            window.cursor.grab_mode = CursorGrabMode::None;
+            next_state.set(GameState::Crafting);
+            return;
+        }
+
+        let target = hit + normal;
+        let Some(block) = inventory.selected_item().and_then(|item| item.block()) else {
+            return;
+        };
+        if !world.is_replaceable(&registry, target) || !(WORLD_MIN_Y..=WORLD_MAX_Y).contains(&target.y) {
+            return;
+        }
+
+        let diff = (target.as_vec3() - transform.translation).abs();
+        let min_dist = (Vec3::new(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH) + Vec3::ONE) * 0.5;
+        if registry.get(block).solid && diff.cmplt(min_dist).all() {
+            return;
+        }
+
+        if game_mode.0 == GameMode::Survival {
+            inventory.take_selected();
+        }
+        world.set_block(&registry, target, Some(block));
+        edits.send(BlockEdited { pos: target, kind: Some(block) });
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(super) fn mine_blocks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mouse: Res<Input<MouseButton>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    block_assets: Res<BlockAssets>,
+    mut inventory: ResMut<Inventory>,
+    mut progress: ResMut<BreakProgress>,
+    game_mode: Res<CurrentGameMode>,
+    mut overlay_query: Query<
+        (&mut Transform, &mut Visibility, &mut Handle<StandardMaterial>),
+        (With<BreakOverlay>, Without<Player>),
+    >,
+    mut edits: EventWriter<BlockEdited>,
+) {
+    let transform = player_query.single();
+    let target = if mouse.pressed(MouseButton::Left) {
+        raycast_blocks(
+            transform.translation,
+            transform.forward(),
+            PLAYER_REACH,
+            |pos| world.is_targetable(&registry, pos),
+        ).map(|(hit, _)| hit)
+    } else {
+        None
+    };
+
+    if target != progress.target {
+        *progress = BreakProgress { target, elapsed: 0.0 };
+    }
+
+    let (mut overlay_transform, mut visibility, mut material) = overlay_query.single_mut();
+    let Some((pos, kind)) = target.and_then(|pos| world.get(pos).map(|kind| (pos, kind))) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let tool = inventory.selected_item().and_then(|item| item.tool());
+    let break_time = match game_mode.0 {
+        GameMode::Survival => kind.break_time(tool),
+        GameMode::Creative if mouse.just_pressed(MouseButton::Left) => 0.0,
+        GameMode::Creative => CREATIVE_BREAK_DELAY,
+    };
+    progress.elapsed += time.delta_seconds();
+
+    if progress.elapsed >= break_time {
+        world.set_block(&registry, pos, None);
+        edits.send(BlockEdited { pos, kind: None });
+        let drop = kind.mining().drop.filter(|_| game_mode.0 == GameMode::Survival && kind.can_harvest(tool));
+        if let Some(item) = drop {
+            let spread = Vec3::new(random::<f32>() - 0.5, 1.0, random::<f32>() - 0.5) * 2.0;
+            spawn_dropped_item(&mut commands, &block_assets, ItemStack::new(item, 1), pos.as_vec3(), spread, 0.5);
+        }
+        if tool.is_some() && game_mode.0 == GameMode::Survival {
+            inventory.damage_selected_tool();
+        }
+        *progress = BreakProgress::default();
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    // Creative breaks instantly, the delay only paces a held button.
+    if game_mode.0 == GameMode::Creative {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    let stage = (progress.elapsed / break_time * CRACK_STAGES as f32) as usize;
+    overlay_transform.translation = pos.as_vec3();
+    *material = block_assets.crack_materials[stage.min(CRACK_STAGES - 1)].clone();
+    *visibility = Visibility::Visible;
+}
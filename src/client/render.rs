@@ -0,0 +1,1080 @@
+use super::*;
+
+#[derive(Component)]
+pub(super) struct ChunkMesh;
+
+// A coarse stand-in for a far chunk's full mesh.
+#[derive(Component)]
+pub(super) struct LodMesh;
+
+// The level a chunk is drawn at, and the levels of its neighbours in `NEIGHBOURS` order, which decide
+// which of its border faces are hidden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LodLevels {
+    own: usize,
+    neighbours: [usize; 6],
+}
+
+impl LodLevels {
+    fn around(chunk: IVec3, eye_chunk: IVec3) -> Self {
+        Self {
+            own: lod_level(chunk, eye_chunk),
+            neighbours: NEIGHBOURS.map(|offset| lod_level(chunk + offset, eye_chunk)),
+        }
+    }
+}
+
+struct LodResult {
+    chunk: IVec3,
+    levels: LodLevels,
+    generation: u32,
+    layers: HashMap<MeshLayer, LayerGeometry>,
+}
+
+// Tracks LOD meshes per chunk, one entity per layer. They are built on the async compute pool and come
+// back over the channel.
+#[derive(Resource)]
+pub(super) struct LodMeshes {
+    built: HashMap<IVec3, (LodLevels, Vec<Entity>)>,
+    pending: HashSet<IVec3>,
+    // Bumped whenever a chunk changes so results built from old blocks are thrown away.
+    generations: HashMap<IVec3, u32>,
+    sender: Sender<LodResult>,
+    receiver: Receiver<LodResult>,
+}
+
+impl Default for LodMeshes {
+    fn default() -> Self {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        Self {
+            built: HashMap::new(),
+            pending: HashSet::new(),
+            generations: HashMap::new(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl LodMeshes {
+    fn invalidate(&mut self, commands: &mut Commands, chunk: IVec3) {
+        *self.generations.entry(chunk).or_default() += 1;
+        for entity in self.built.remove(&chunk).into_iter().flat_map(|(_, entities)| entities) {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// Chunk meshes drawn and skipped last frame, for the debug readout.
+#[derive(Resource, Default)]
+pub(super) struct CullingStats {
+    pub(super) drawn: usize,
+    pub(super) out_of_range: usize,
+    pub(super) frustum: usize,
+    pub(super) caves: usize,
+}
+
+// The renderer's bookkeeping for each chunk of `WorldBlocks`.
+#[derive(Resource, Default)]
+pub(super) struct ChunkRenderState {
+    pub(super) entities: HashMap<(IVec3, MeshLayer), Entity>,
+    // Which pairs of faces can see each other through each chunk; see `face_connectivity`.
+    connectivity: HashMap<IVec3, u64>,
+}
+
+// Translucent quads with their chunk-local centres, kept so the index buffer can be re-sorted as the camera moves.
+#[derive(Component)]
+pub(super) struct TranslucentFaces {
+    quads: Vec<(Vec3, [u32; 6])>,
+    sorted_from: Option<Vec3>,
+}
+
+const TILE_SIZE: u32 = 16;
+
+const MISSING_TEXTURE: &str = "missing";
+
+const TRANSLUCENT_RESORT_DISTANCE: f32 = 0.5;
+
+const AO_BRIGHTNESS: [f32; 4] = [0.5, 0.7, 0.85, 1.0];
+
+// Chunks further than each of these (in chunks) drop to the next LOD: 2x, 4x, then 8x coarser.
+const LOD_DISTANCES: [i32; 3] = [4, 8, 16];
+
+const LOD_JOBS_PER_FRAME: usize = 8;
+
+const ALL_FACES_CONNECTED: u64 = (1 << 36) - 1;
+
+fn light_brightness(level: u8) -> f32 {
+    0.8f32.powi((MAX_LIGHT - level) as i32)
+}
+
+// Corners of each face, wound counter-clockwise when seen from outside the block.
+pub(super) const FACES: [(IVec3, [Vec3; 4]); 6] = [
+    (IVec3::X, [Vec3::new(0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, -0.5), Vec3::new(0.5, 0.5, 0.5), Vec3::new(0.5, -0.5, 0.5)]),
+    (IVec3::NEG_X, [Vec3::new(-0.5, -0.5, 0.5), Vec3::new(-0.5, 0.5, 0.5), Vec3::new(-0.5, 0.5, -0.5), Vec3::new(-0.5, -0.5, -0.5)]),
+    (IVec3::Y, [Vec3::new(-0.5, 0.5, -0.5), Vec3::new(-0.5, 0.5, 0.5), Vec3::new(0.5, 0.5, 0.5), Vec3::new(0.5, 0.5, -0.5)]),
+    (IVec3::NEG_Y, [Vec3::new(-0.5, -0.5, 0.5), Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, -0.5, -0.5), Vec3::new(0.5, -0.5, 0.5)]),
+    (IVec3::Z, [Vec3::new(0.5, -0.5, 0.5), Vec3::new(0.5, 0.5, 0.5), Vec3::new(-0.5, 0.5, 0.5), Vec3::new(-0.5, -0.5, 0.5)]),
+    (IVec3::NEG_Z, [Vec3::new(-0.5, -0.5, -0.5), Vec3::new(-0.5, 0.5, -0.5), Vec3::new(0.5, 0.5, -0.5), Vec3::new(0.5, -0.5, -0.5)]),
+];
+
+// 0 is fully occluded, 3 is open. Two solid sides hide the corner whatever it holds.
+fn vertex_ao(side1: bool, side2: bool, corner: bool) -> u8 {
+    if side1 && side2 {
+        0
+    } else {
+        3 - (side1 as u8 + side2 as u8 + corner as u8)
+    }
+}
+
+fn face_ao(pos: IVec3, normal: IVec3, corners: &[Vec3; 4], is_occluder: impl Fn(IVec3) -> bool) -> [u8; 4] {
+    corners.map(|corner| {
+        // Step from the face's neighbour towards this corner along each of the face's two axes.
+        let tangent = (corner * 2.0).as_ivec3() - normal;
+        let (side1, side2) = match normal.abs() {
+            IVec3::X => (IVec3::new(0, tangent.y, 0), IVec3::new(0, 0, tangent.z)),
+            IVec3::Y => (IVec3::new(tangent.x, 0, 0), IVec3::new(0, 0, tangent.z)),
+            _ => (IVec3::new(tangent.x, 0, 0), IVec3::new(0, tangent.y, 0)),
+        };
+        let layer = pos + normal;
+        vertex_ao(is_occluder(layer + side1), is_occluder(layer + side2), is_occluder(layer + tangent))
+    })
+}
+
+// Splits the quad along the diagonal that keeps a dark corner from bleeding across the whole face.
+fn quad_indices(ao: [u8; 4]) -> [u32; 6] {
+    if ao[0] + ao[2] < ao[1] + ao[3] {
+        [1, 2, 3, 1, 3, 0]
+    } else {
+        [0, 1, 2, 0, 2, 3]
+    }
+}
+
+// Where each block texture sits in the atlas image, by name.
+#[derive(Resource, Default, Clone)]
+pub(super) struct BlockAtlas {
+    tiles: HashMap<String, u32>,
+    columns: u32,
+}
+
+impl BlockAtlas {
+    // UVs for the four face corners in `FACES` order, pulled in slightly so rounding never lands in the next tile.
+    fn face_uvs(&self, name: &str) -> [[f32; 2]; 4] {
+        let tile = self.tiles.get(name).or_else(|| self.tiles.get(MISSING_TEXTURE)).copied().unwrap_or(0);
+        let scale = 1.0 / self.columns as f32;
+        let inset = scale / (TILE_SIZE as f32 * 64.0);
+        let min = Vec2::new((tile % self.columns) as f32, (tile / self.columns) as f32) * scale + inset;
+        let max = min + scale - inset * 2.0;
+        [[max.x, max.y], [max.x, min.y], [min.x, min.y], [min.x, max.y]]
+    }
+}
+
+fn missing_texture() -> Vec<u8> {
+    (0..TILE_SIZE * TILE_SIZE)
+        .flat_map(|i| {
+            let (x, y) = (i % TILE_SIZE, i / TILE_SIZE);
+            if (x < TILE_SIZE / 2) == (y < TILE_SIZE / 2) { [255, 0, 255, 255] } else { [0, 0, 0, 255] }
+        })
+        .collect()
+}
+
+fn load_block_texture(packs: &ResourcePacks, name: &str) -> Result<Vec<u8>, String> {
+    let path = format!("{BLOCK_TEXTURE_DIR}/{name}.png");
+    let bytes = packs.read(&path).ok_or_else(|| format!("{path}: not found in any resource pack"))?;
+    let image = Image::from_buffer(&bytes, ImageType::Extension("png"), CompressedImageFormats::NONE, true, ImageSampler::Default)
+        .map_err(|e| format!("{path}: {e}"))?;
+    let image = image.convert(TextureFormat::Rgba8UnormSrgb).ok_or_else(|| format!("{path}: unsupported pixel format"))?;
+    if image.width() != TILE_SIZE || image.height() != TILE_SIZE {
+        return Err(format!("{path}: expected {TILE_SIZE}x{TILE_SIZE}, got {}x{}", image.width(), image.height()));
+    }
+    Ok(image.data)
+}
+
+// Halves an RGBA image by averaging 2x2 blocks. Tiles sit on a power-of-two grid, so no block ever straddles two tiles.
+fn downsample(data: &[u8], size: u32) -> Vec<u8> {
+    let half = size / 2;
+    let mut out = Vec::with_capacity((half * half * 4) as usize);
+    for y in 0..half {
+        for x in 0..half {
+            for channel in 0..4 {
+                let sum: u32 = [(0, 0), (1, 0), (0, 1), (1, 1)]
+                    .iter()
+                    .map(|(dx, dy)| data[(((y * 2 + dy) * size + x * 2 + dx) * 4 + channel) as usize] as u32)
+                    .sum();
+                out.push((sum / 4) as u8);
+            }
+        }
+    }
+    out
+}
+
+// Packs every texture a block names into one square image with its own mip chain.
+pub(super) fn build_block_atlas(packs: &ResourcePacks) -> (BlockAtlas, Image) {
+    let mut names: Vec<&str> = BlockKind::iter()
+        .map(|kind| kind.textures())
+        .flat_map(|textures| [textures.top, textures.side, textures.bottom])
+        .collect();
+    names.sort();
+    names.dedup();
+    names.insert(0, MISSING_TEXTURE);
+
+    let columns = (names.len() as f32).sqrt().ceil().max(1.0) as u32;
+    let columns = columns.next_power_of_two();
+    let size = columns * TILE_SIZE;
+    let mut data = vec![0u8; (size * size * 4) as usize];
+    let mut tiles = HashMap::new();
+
+    for (tile, name) in names.iter().enumerate() {
+        let tile = tile as u32;
+        let pixels = if *name == MISSING_TEXTURE {
+            missing_texture()
+        } else {
+            load_block_texture(packs, name).unwrap_or_else(|e| {
+                warn!("Missing block texture {}", e);
+                missing_texture()
+            })
+        };
+        let (left, top) = ((tile % columns) * TILE_SIZE, (tile / columns) * TILE_SIZE);
+        for row in 0..TILE_SIZE {
+            let src = (row * TILE_SIZE * 4) as usize;
+            let dst = (((top + row) * size + left) * 4) as usize;
+            data[dst..dst + (TILE_SIZE * 4) as usize].copy_from_slice(&pixels[src..src + (TILE_SIZE * 4) as usize]);
+        }
+        tiles.insert(name.to_string(), tile);
+    }
+
+    // Stop once a tile is a single pixel; below that neighbouring tiles would blend together.
+    let mip_levels = TILE_SIZE.trailing_zeros() + 1;
+    let mut level = data.clone();
+    let mut level_size = size;
+    for _ in 1..mip_levels {
+        level = downsample(&level, level_size);
+        level_size /= 2;
+        data.extend_from_slice(&level);
+    }
+
+    let extent = Extent3d { width: size, height: size, depth_or_array_layers: 1 };
+    let mut image = Image::new_fill(extent, TextureDimension::D2, &[0, 0, 0, 0], TextureFormat::Rgba8UnormSrgb);
+    image.data = data;
+    image.texture_descriptor.mip_level_count = mip_levels;
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        mag_filter: ImageFilterMode::Nearest,
+        min_filter: ImageFilterMode::Nearest,
+        mipmap_filter: ImageFilterMode::Linear,
+        ..default()
+    });
+    (BlockAtlas { tiles, columns }, image)
+}
+
+// Faces against opaque blocks are hidden, as are faces between two blocks of the same kind so water
+// and glass read as one volume. Faces between different see-through blocks are kept.
+fn face_visible(registry: &BlockRegistry, kind: BlockKind, neighbour: Option<BlockKind>) -> bool {
+    neighbour.is_none_or(|other| !registry.get(other).opaque && other != kind)
+}
+
+#[derive(Default)]
+struct LayerGeometry {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+    quads: Vec<(Vec3, [u32; 6])>,
+}
+
+fn quad_order_indices(quads: &[(Vec3, [u32; 6])]) -> Vec<u32> {
+    quads.iter().flat_map(|(_, indices)| *indices).collect()
+}
+
+// Puts the farthest quads first so blending composites them back-to-front.
+fn sort_back_to_front(quads: &mut [(Vec3, [u32; 6])], eye: Vec3) {
+    quads.sort_by(|(a, _), (b, _)| eye.distance_squared(*b).total_cmp(&eye.distance_squared(*a)));
+}
+
+impl LayerGeometry {
+    fn into_mesh(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.set_indices(Some(Indices::U32(quad_order_indices(&self.quads))));
+        mesh
+    }
+}
+
+fn build_chunk_mesh(
+    world: &WorldBlocks,
+    registry: &BlockRegistry,
+    atlas: &BlockAtlas,
+    chunk_pos: IVec3,
+) -> HashMap<MeshLayer, LayerGeometry> {
+    let mut layers: HashMap<MeshLayer, LayerGeometry> = HashMap::new();
+    let Some(chunk) = world.chunks.get(&chunk_pos) else {
+        return layers;
+    };
+    let origin = chunk_origin(chunk_pos);
+
+    for (index, kind) in chunk.blocks.iter().enumerate() {
+        let Some(kind) = *kind else {
+            continue;
+        };
+        let local = chunk_local(index);
+        let pos = origin + local;
+        let (textures, layer) = (kind.textures(), kind.layer());
+
+        for (normal, corners) in FACES {
+            let neighbour = pos + normal;
+            if !face_visible(registry, kind, world.get(neighbour)) {
+                continue;
+            }
+
+            let level = world.light(neighbour, LightChannel::Sky).max(world.light(neighbour, LightChannel::Block));
+            let brightness = light_brightness(level);
+            let face_uvs = atlas.face_uvs(textures.face(normal));
+            let ao = face_ao(pos, normal, &corners, |pos| {
+                world.get(pos).is_some_and(|kind| registry.get(kind).opaque)
+            });
+
+            let geometry = layers.entry(layer).or_default();
+            let base = geometry.positions.len() as u32;
+            for ((corner, ao), uv) in corners.iter().zip(ao).zip(face_uvs) {
+                let shade = brightness * AO_BRIGHTNESS[ao as usize];
+                geometry.positions.push((local.as_vec3() + *corner).to_array());
+                geometry.normals.push(normal.as_vec3().to_array());
+                geometry.uvs.push(uv);
+                geometry.colors.push([shade, shade, shade, 1.0]);
+            }
+            let center = local.as_vec3() + normal.as_vec3() * 0.5;
+            geometry.quads.push((center, quad_indices(ao).map(|index| base + index)));
+        }
+    }
+
+    layers
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(super) fn remesh_chunks(
+    mut commands: Commands,
+    mut world: ResMut<WorldBlocks>,
+    mut render_state: ResMut<ChunkRenderState>,
+    registry: Res<BlockRegistry>,
+    atlas: Res<BlockAtlas>,
+    block_assets: Res<BlockAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut lods: ResMut<LodMeshes>,
+) {
+    let dirty: Vec<IVec3> = world.dirty.drain().collect();
+    for chunk_pos in dirty {
+        lods.invalidate(&mut commands, chunk_pos);
+        match world.chunks.get(&chunk_pos) {
+            Some(chunk) => render_state.connectivity.insert(chunk_pos, face_connectivity(chunk, &registry)),
+            None => render_state.connectivity.remove(&chunk_pos),
+        };
+        let mut layers = build_chunk_mesh(&world, &registry, &atlas, chunk_pos);
+        for layer in MeshLayer::iter() {
+            let key = (chunk_pos, layer);
+            match (layers.remove(&layer), render_state.entities.get(&key).copied()) {
+                (Some(geometry), entity) => {
+                    let faces = (layer == MeshLayer::Translucent)
+                        .then(|| TranslucentFaces { quads: geometry.quads.clone(), sorted_from: None });
+                    let mesh = meshes.add(geometry.into_mesh());
+                    let mut entity = match entity {
+                        Some(entity) => {
+                            let mut entity = commands.entity(entity);
+                            entity.insert(mesh);
+                            entity
+                        }
+                        None => {
+                            let entity = commands.spawn((
+                                PbrBundle {
+                                    mesh,
+                                    material: block_assets.chunk_materials[&layer].clone(),
+                                    transform: Transform::from_translation(chunk_origin(chunk_pos).as_vec3()),
+                                    ..default()
+                                },
+                                ChunkMesh,
+                            ));
+                            render_state.entities.insert(key, entity.id());
+                            entity
+                        }
+                    };
+                    if let Some(faces) = faces {
+                        entity.insert(faces);
+                    }
+                }
+                (None, Some(entity)) => {
+                    commands.entity(entity).despawn();
+                    render_state.entities.remove(&key);
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+// Re-sorts translucent chunk meshes once the camera has moved far enough to change their order.
+pub(super) fn sort_translucent_faces(
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+    mut chunk_query: Query<(&Transform, &Handle<Mesh>, &mut TranslucentFaces)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    for (transform, mesh, mut faces) in chunk_query.iter_mut() {
+        let eye = camera.translation() - transform.translation;
+        if faces.sorted_from.is_some_and(|from| from.distance(eye) < TRANSLUCENT_RESORT_DISTANCE) {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(mesh) else {
+            continue;
+        };
+        sort_back_to_front(&mut faces.quads, eye);
+        mesh.set_indices(Some(Indices::U32(quad_order_indices(&faces.quads))));
+        faces.sorted_from = Some(eye);
+    }
+}
+
+fn within_render_distance(chunk: IVec3, eye_chunk: IVec3, render_distance: i32) -> bool {
+    (chunk.x - eye_chunk.x).abs() <= render_distance && (chunk.z - eye_chunk.z).abs() <= render_distance
+}
+
+// Hides chunk meshes beyond the render distance, outside the view frustum, or walled off by cave culling.
+// Far chunks swap to their LOD mesh once it has been built.
+#[allow(clippy::type_complexity)]
+pub(super) fn cull_chunks(
+    settings: Res<GameSettings>,
+    render_state: Res<ChunkRenderState>,
+    lods: Res<LodMeshes>,
+    mut stats: ResMut<CullingStats>,
+    camera_query: Query<(&GlobalTransform, &Frustum), With<Camera3d>>,
+    mut chunk_query: Query<(&Transform, &mut Visibility), (With<ChunkMesh>, Without<LodMesh>)>,
+    mut lod_query: Query<(&Transform, &mut Visibility), (With<LodMesh>, Without<ChunkMesh>)>,
+) {
+    let Ok((camera, frustum)) = camera_query.get_single() else {
+        return;
+    };
+    let (eye_chunk, _) = chunk_position(camera.translation().round().as_ivec3());
+    let reachable = visible_chunks(&render_state.connectivity, eye_chunk, settings.render_distance);
+    let bounds = Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(CHUNK_SIZE as f32 - 0.5));
+
+    *stats = CullingStats::default();
+    let mut cull = |transform: &Transform, mut visibility: Mut<Visibility>, is_lod: bool| {
+        let (chunk, _) = chunk_position(transform.translation.as_ivec3());
+        let use_lod = lod_level(chunk, eye_chunk) > 0 && lods.built.contains_key(&chunk);
+        let visible = if use_lod != is_lod {
+            false
+        } else if !within_render_distance(chunk, eye_chunk, settings.render_distance) {
+            stats.out_of_range += 1;
+            false
+        } else if !frustum.intersects_obb(&bounds, &transform.compute_affine(), true, false) {
+            stats.frustum += 1;
+            false
+        } else if !reachable.contains(&chunk) {
+            stats.caves += 1;
+            false
+        } else {
+            stats.drawn += 1;
+            true
+        };
+        visibility.set_if_neq(if visible { Visibility::Inherited } else { Visibility::Hidden });
+    };
+    for (transform, visibility) in chunk_query.iter_mut() {
+        cull(transform, visibility, false);
+    }
+    for (transform, visibility) in lod_query.iter_mut() {
+        cull(transform, visibility, true);
+    }
+}
+
+// 0 is full detail; level n merges 2^n blocks along each axis.
+fn lod_level(chunk: IVec3, eye_chunk: IVec3) -> usize {
+    let distance = (chunk.x - eye_chunk.x).abs().max((chunk.z - eye_chunk.z).abs());
+    LOD_DISTANCES.iter().take_while(|&&limit| distance > limit).count()
+}
+
+// Shrinks a chunk by `scale` along each axis. A cell takes its most common block, or stays empty unless
+// at least half of it is filled.
+fn downsample_blocks(blocks: &[Option<BlockKind>], scale: i32) -> Vec<Option<BlockKind>> {
+    let size = CHUNK_SIZE / scale;
+    let mut cells = Vec::with_capacity((size * size * size) as usize);
+    for y in 0..size {
+        for z in 0..size {
+            for x in 0..size {
+                let mut counts: HashMap<BlockKind, i32> = HashMap::new();
+                for dy in 0..scale {
+                    for dz in 0..scale {
+                        for dx in 0..scale {
+                            let local = IVec3::new(x * scale + dx, y * scale + dy, z * scale + dz);
+                            let (_, index) = chunk_position(local);
+                            if let Some(kind) = blocks[index] {
+                                *counts.entry(kind).or_default() += 1;
+                            }
+                        }
+                    }
+                }
+                let filled: i32 = counts.values().sum();
+                let kind = counts.into_iter().max_by_key(|(kind, count)| (*count, *kind as u8)).map(|(kind, _)| kind);
+                cells.push(kind.filter(|_| filled * 2 >= scale * scale * scale));
+            }
+        }
+    }
+    cells
+}
+
+// A chunk's blocks and light, copied out of the world for a background LOD job.
+struct LodChunk {
+    blocks: Vec<Option<BlockKind>>,
+    sky_light: Vec<u8>,
+    block_light: Vec<u8>,
+}
+
+impl LodChunk {
+    fn copy(chunk: &Chunk) -> Self {
+        Self {
+            blocks: chunk.blocks.clone(),
+            sky_light: chunk.sky_light.clone(),
+            block_light: chunk.block_light.clone(),
+        }
+    }
+}
+
+// Everything a background LOD job needs, copied out of the world so it can run off the main thread.
+struct LodSnapshot {
+    chunk: LodChunk,
+    // In `NEIGHBOURS` order, with the level each one is drawn at; missing chunks count as sunlit air.
+    neighbours: Vec<Option<(usize, LodChunk)>>,
+}
+
+// Meshes a downsampled chunk with one quad per exposed cell face, split into layers like a full chunk mesh.
+// Each face is shaded by the average light of the open blocks in front of it. AO is skipped, since LOD
+// chunks are only seen from far away.
+fn build_lod_mesh(
+    snapshot: &LodSnapshot,
+    registry: &BlockRegistry,
+    atlas: &BlockAtlas,
+    level: usize,
+) -> HashMap<MeshLayer, LayerGeometry> {
+    let scale = 1 << level;
+    let size = CHUNK_SIZE / scale;
+    let cells = downsample_blocks(&snapshot.chunk.blocks, scale);
+    // Neighbours are downsampled at their own level, so border faces are culled against what is drawn there.
+    let neighbour_cells: Vec<Option<(i32, Vec<Option<BlockKind>>)>> = snapshot
+        .neighbours
+        .iter()
+        .map(|neighbour| {
+            neighbour.as_ref().map(|(level, chunk)| (1 << level, downsample_blocks(&chunk.blocks, 1 << level)))
+        })
+        .collect();
+    // The `NEIGHBOURS` index of the chunk holding a block just outside this one, or None inside it.
+    let side = |pos: IVec3| NEIGHBOURS.iter().position(|offset| *offset == pos.div_euclid(IVec3::splat(CHUNK_SIZE)));
+    let index = |cell: IVec3, size: i32| (cell.x + cell.z * size + cell.y * size * size) as usize;
+    let cell_at = |pos: IVec3| -> Option<BlockKind> {
+        let (cell_scale, grid) = match side(pos) {
+            Some(face) => neighbour_cells[face].as_ref().map(|(scale, grid)| (*scale, grid))?,
+            None => (scale, &cells),
+        };
+        grid[index(pos.rem_euclid(IVec3::splat(CHUNK_SIZE)) / cell_scale, CHUNK_SIZE / cell_scale)]
+    };
+    let light_at = |pos: IVec3| -> Option<u8> {
+        let chunk = match side(pos) {
+            Some(face) => match &snapshot.neighbours[face] {
+                Some((_, chunk)) => chunk,
+                None => return Some(MAX_LIGHT),
+            },
+            None => &snapshot.chunk,
+        };
+        let (_, index) = chunk_position(pos);
+        let open = chunk.blocks[index].is_none_or(|kind| !registry.get(kind).opaque);
+        open.then(|| chunk.sky_light[index].max(chunk.block_light[index]))
+    };
+
+    let mut layers: HashMap<MeshLayer, LayerGeometry> = HashMap::new();
+    for y in 0..size {
+        for z in 0..size {
+            for x in 0..size {
+                let cell = IVec3::new(x, y, z);
+                let Some(kind) = cells[index(cell, size)] else {
+                    continue;
+                };
+                let (textures, layer) = (kind.textures(), kind.layer());
+                let center = cell.as_vec3() * scale as f32 + (scale - 1) as f32 * 0.5;
+                for (normal, corners) in FACES {
+                    // The layer of blocks the face looks onto, which may span several cells of a finer neighbour.
+                    let axis = (0..3).find(|&axis| normal[axis] != 0).unwrap();
+                    let front: Vec<IVec3> = (0..scale * scale)
+                        .map(|i| {
+                            let mut offset = IVec3::ZERO;
+                            offset[(axis + 1) % 3] = i % scale;
+                            offset[(axis + 2) % 3] = i / scale;
+                            offset[axis] = if normal[axis] > 0 { scale } else { -1 };
+                            cell * scale + offset
+                        })
+                        .collect();
+                    if !front.iter().any(|pos| face_visible(registry, kind, cell_at(*pos))) {
+                        continue;
+                    }
+                    let lit: Vec<f32> = front.iter().filter_map(|pos| light_at(*pos)).map(light_brightness).collect();
+                    let shade = if lit.is_empty() { light_brightness(0) } else { lit.iter().sum::<f32>() / lit.len() as f32 };
+
+                    let geometry = layers.entry(layer).or_default();
+                    let base = geometry.positions.len() as u32;
+                    for (corner, uv) in corners.iter().zip(atlas.face_uvs(textures.face(normal))) {
+                        geometry.positions.push((center + *corner * scale as f32).to_array());
+                        geometry.normals.push(normal.as_vec3().to_array());
+                        geometry.uvs.push(uv);
+                        geometry.colors.push([shade, shade, shade, 1.0]);
+                    }
+                    let face_center = center + normal.as_vec3() * scale as f32 * 0.5;
+                    geometry.quads.push((face_center, [base, base + 1, base + 2, base, base + 2, base + 3]));
+                }
+            }
+        }
+    }
+    layers
+}
+
+// Queues background builds for far chunks whose LOD mesh is missing or was built for other levels.
+pub(super) fn request_lod_meshes(
+    settings: Res<GameSettings>,
+    world: Res<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    atlas: Res<BlockAtlas>,
+    mut lods: ResMut<LodMeshes>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let (eye_chunk, _) = chunk_position(camera.translation().round().as_ivec3());
+    let mut wanted: Vec<(IVec3, LodLevels)> = world
+        .chunks
+        .keys()
+        .filter(|chunk| within_render_distance(**chunk, eye_chunk, settings.render_distance))
+        .map(|chunk| (*chunk, LodLevels::around(*chunk, eye_chunk)))
+        .filter(|(chunk, levels)| {
+            levels.own > 0
+                && !lods.pending.contains(chunk)
+                && lods.built.get(chunk).is_none_or(|(built, _)| built != levels)
+        })
+        .collect();
+    // Nearest first, so the LOD boundary fills in before the horizon does.
+    wanted.sort_by_key(|(chunk, _)| (*chunk - eye_chunk).abs().max_element());
+
+    let pool = AsyncComputeTaskPool::get();
+    for (chunk, levels) in wanted.into_iter().take(LOD_JOBS_PER_FRAME) {
+        let snapshot = LodSnapshot {
+            chunk: LodChunk::copy(&world.chunks[&chunk]),
+            neighbours: NEIGHBOURS
+                .iter()
+                .zip(levels.neighbours)
+                .map(|(offset, level)| world.chunks.get(&(chunk + *offset)).map(|chunk| (level, LodChunk::copy(chunk))))
+                .collect(),
+        };
+        let generation = lods.generations.get(&chunk).copied().unwrap_or_default();
+        let registry = registry.clone();
+        let atlas = atlas.clone();
+        let sender = lods.sender.clone();
+        lods.pending.insert(chunk);
+        pool.spawn(async move {
+            let layers = build_lod_mesh(&snapshot, &registry, &atlas, levels.own);
+            // The receiver only goes away when the app shuts down.
+            let _ = sender.send(LodResult { chunk, levels, generation, layers });
+        })
+        .detach();
+    }
+}
+
+pub(super) fn receive_lod_meshes(
+    mut commands: Commands,
+    mut lods: ResMut<LodMeshes>,
+    block_assets: Res<BlockAssets>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let results: Vec<LodResult> = lods.receiver.try_iter().collect();
+    for result in results {
+        lods.pending.remove(&result.chunk);
+        if lods.generations.get(&result.chunk).copied().unwrap_or_default() != result.generation {
+            continue;
+        }
+        for entity in lods.built.remove(&result.chunk).into_iter().flat_map(|(_, entities)| entities) {
+            commands.entity(entity).despawn();
+        }
+        let mut entities = Vec::new();
+        for (layer, geometry) in result.layers {
+            let faces = (layer == MeshLayer::Translucent)
+                .then(|| TranslucentFaces { quads: geometry.quads.clone(), sorted_from: None });
+            let mut entity = commands.spawn((
+                PbrBundle {
+                    mesh: meshes.add(geometry.into_mesh()),
+                    material: block_assets.chunk_materials[&layer].clone(),
+                    transform: Transform::from_translation(chunk_origin(result.chunk).as_vec3()),
+                    visibility: Visibility::Hidden,
+                    ..default()
+                },
+                LodMesh,
+            ));
+            if let Some(faces) = faces {
+                entity.insert(faces);
+            }
+            entities.push(entity.id());
+        }
+        // Chunks that come out empty are recorded too, so they aren't rebuilt every frame.
+        lods.built.insert(result.chunk, (result.levels, entities));
+    }
+}
+
+fn face_pair_bit(a: usize, b: usize) -> u64 {
+    1 << (a * 6 + b)
+}
+
+// Flood-fills the see-through cells of a chunk and records, for every pair of faces (in `NEIGHBOURS`
+// order), whether some open region touches both. This is what lets cave culling skip chunks hidden
+// behind solid rock.
+fn face_connectivity(chunk: &Chunk, registry: &BlockRegistry) -> u64 {
+    let open = |index: usize| chunk.blocks[index].is_none_or(|kind| !registry.get(kind).opaque);
+    let mut visited = vec![false; CHUNK_VOLUME];
+    let mut connectivity = 0;
+
+    for start in 0..CHUNK_VOLUME {
+        if visited[start] || !open(start) {
+            continue;
+        }
+        visited[start] = true;
+        let mut faces = 0u8;
+        let mut queue = VecDeque::from([start]);
+        while let Some(index) = queue.pop_front() {
+            let local = chunk_local(index);
+            for (face, offset) in NEIGHBOURS.iter().enumerate() {
+                let next = local + *offset;
+                if next.cmplt(IVec3::ZERO).any() || next.cmpge(IVec3::splat(CHUNK_SIZE)).any() {
+                    faces |= 1 << face;
+                    continue;
+                }
+                let (_, next) = chunk_position(next);
+                if !visited[next] && open(next) {
+                    visited[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        for a in 0..6 {
+            for b in 0..6 {
+                if faces & (1 << a) != 0 && faces & (1 << b) != 0 {
+                    connectivity |= face_pair_bit(a, b);
+                }
+            }
+        }
+    }
+    connectivity
+}
+
+// Walks outwards from the camera's chunk, only leaving a chunk through a face that the face we entered
+// by can see, and never doubling back along an axis. Chunks not reached are buried from view.
+fn visible_chunks(connectivity: &HashMap<IVec3, u64>, start: IVec3, render_distance: i32) -> HashSet<IVec3> {
+    // One empty layer above and below the world so the walk can go over terrain.
+    let min_y = (WORLD_MIN_Y.div_euclid(CHUNK_SIZE) - 1).min(start.y);
+    let max_y = (WORLD_MAX_Y.div_euclid(CHUNK_SIZE) + 1).max(start.y);
+    let opposite = |face: usize| face ^ 1;
+
+    let mut visible = HashSet::from([start]);
+    let mut queue: VecDeque<(IVec3, Option<usize>, u8)> = VecDeque::from([(start, None, 0)]);
+    while let Some((chunk, entered, directions)) = queue.pop_front() {
+        let open = connectivity.get(&chunk).copied().unwrap_or(ALL_FACES_CONNECTED);
+        for (face, offset) in NEIGHBOURS.iter().enumerate() {
+            if directions & (1 << opposite(face)) != 0 {
+                continue;
+            }
+            if entered.is_some_and(|entered| open & face_pair_bit(entered, face) == 0) {
+                continue;
+            }
+            let next = chunk + *offset;
+            if next.y < min_y || next.y > max_y || !within_render_distance(next, start, render_distance) {
+                continue;
+            }
+            if visible.insert(next) {
+                queue.push_back((next, Some(opposite(face)), directions | (1 << face)));
+            }
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_ao_counts_occluders() {
+        assert_eq!(vertex_ao(false, false, false), 3);
+        assert_eq!(vertex_ao(false, false, true), 2);
+        assert_eq!(vertex_ao(true, false, false), 2);
+        assert_eq!(vertex_ao(false, true, true), 1);
+        assert_eq!(vertex_ao(true, true, false), 0);
+        assert_eq!(vertex_ao(true, true, true), 0);
+    }
+
+    fn top_face_ao(occluders: &[IVec3]) -> [u8; 4] {
+        let (normal, corners) = FACES[2];
+        assert_eq!(normal, IVec3::Y);
+        face_ao(IVec3::ZERO, normal, &corners, |pos| occluders.contains(&pos))
+    }
+
+    #[test]
+    fn face_ao_for_corner_configurations() {
+        // Top face corners run (-x,-z), (-x,+z), (+x,+z), (+x,-z).
+        assert_eq!(top_face_ao(&[]), [3, 3, 3, 3]);
+        assert_eq!(top_face_ao(&[IVec3::new(-1, 1, -1)]), [2, 3, 3, 3]);
+        assert_eq!(top_face_ao(&[IVec3::new(-1, 1, 0)]), [2, 2, 3, 3]);
+        assert_eq!(top_face_ao(&[IVec3::new(-1, 1, 0), IVec3::new(0, 1, 1)]), [2, 0, 2, 3]);
+        assert_eq!(top_face_ao(&[IVec3::new(1, 1, -1), IVec3::new(1, 1, 0)]), [3, 3, 2, 1]);
+        // Blocks level with the face itself do not shade it.
+        assert_eq!(top_face_ao(&[IVec3::new(-1, 0, 0), IVec3::new(0, 2, 0)]), [3, 3, 3, 3]);
+    }
+
+    #[test]
+    fn face_ao_on_side_faces() {
+        let (normal, corners) = FACES[0];
+        assert_eq!(normal, IVec3::X);
+        let ao = face_ao(IVec3::ZERO, normal, &corners, |pos| pos == IVec3::new(1, -1, 0));
+        // A block below the neighbour shades the two bottom corners.
+        assert_eq!(ao, [2, 3, 3, 2]);
+    }
+
+    #[test]
+    fn quad_flips_towards_dark_corner() {
+        assert_eq!(quad_indices([3, 3, 3, 3]), [0, 1, 2, 0, 2, 3]);
+        assert_eq!(quad_indices([0, 3, 3, 3]), [1, 2, 3, 1, 3, 0]);
+        assert_eq!(quad_indices([3, 0, 3, 3]), [0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn atlas_uvs_stay_inside_their_tile() {
+        let atlas = BlockAtlas {
+            tiles: HashMap::from([(MISSING_TEXTURE.to_string(), 0), ("stone".to_string(), 5)]),
+            columns: 4,
+        };
+        let uvs = atlas.face_uvs("stone");
+        for [u, v] in uvs {
+            assert!(u > 0.25 && u < 0.5, "u {u}");
+            assert!(v > 0.25 && v < 0.5, "v {v}");
+        }
+        // Bottom corners of a side face sample the bottom of the tile.
+        assert!(uvs[0][1] > uvs[1][1]);
+        assert_eq!(atlas.face_uvs("nonexistent"), atlas.face_uvs(MISSING_TEXTURE));
+    }
+
+    #[test]
+    fn downsample_averages_quads() {
+        let data: Vec<u8> = [0u8, 100, 200, 40].iter().flat_map(|&v| [v, v, v, 255]).collect();
+        assert_eq!(downsample(&data, 2), vec![85, 85, 85, 255]);
+    }
+
+    #[test]
+    fn block_textures_exist() {
+        for textures in BlockKind::iter().map(|kind| kind.textures()) {
+            for name in [textures.top, textures.side, textures.bottom] {
+                assert!(load_block_texture(&ResourcePacks::default(), name).is_ok(), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn faces_between_different_transparent_blocks_are_kept() {
+        let registry = BlockRegistry::default();
+        assert!(face_visible(&registry, BlockKind::Stone, None));
+        assert!(!face_visible(&registry, BlockKind::Stone, Some(BlockKind::Dirt)));
+        assert!(face_visible(&registry, BlockKind::Stone, Some(BlockKind::Water)));
+        assert!(!face_visible(&registry, BlockKind::Water, Some(BlockKind::Water)));
+        assert!(!face_visible(&registry, BlockKind::Glass, Some(BlockKind::Glass)));
+        assert!(face_visible(&registry, BlockKind::Water, Some(BlockKind::Glass)));
+        assert!(face_visible(&registry, BlockKind::Glass, Some(BlockKind::Leaves)));
+        assert!(!face_visible(&registry, BlockKind::Water, Some(BlockKind::Stone)));
+    }
+
+    #[test]
+    fn translucent_quads_sort_farthest_first() {
+        let mut quads = vec![
+            (Vec3::new(1.0, 0.0, 0.0), [0; 6]),
+            (Vec3::new(5.0, 0.0, 0.0), [1; 6]),
+            (Vec3::new(3.0, 0.0, 0.0), [2; 6]),
+        ];
+        sort_back_to_front(&mut quads, Vec3::ZERO);
+        assert_eq!(quad_order_indices(&quads)[..13], [1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 0]);
+        sort_back_to_front(&mut quads, Vec3::new(6.0, 0.0, 0.0));
+        assert_eq!(quads[0].1, [0; 6]);
+    }
+
+    #[test]
+    fn render_distance_is_measured_in_horizontal_chunks() {
+        let eye = IVec3::new(0, 0, 0);
+        assert!(within_render_distance(IVec3::new(2, 0, -2), eye, 2));
+        assert!(within_render_distance(IVec3::new(0, 2, 0), eye, 1));
+        assert!(!within_render_distance(IVec3::new(3, 0, 0), eye, 2));
+        assert!(!within_render_distance(IVec3::new(0, 0, -3), eye, 2));
+    }
+
+    fn stone_chunk() -> Chunk {
+        Chunk {
+            blocks: vec![Some(BlockKind::Stone); CHUNK_VOLUME],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn face_connectivity_follows_open_regions() {
+        let registry = BlockRegistry::default();
+        assert_eq!(face_connectivity(&Chunk::default(), &registry), ALL_FACES_CONNECTED);
+        assert_eq!(face_connectivity(&stone_chunk(), &registry), 0);
+
+        // A solid wall across x = 8 splits the chunk into two halves.
+        let mut chunk = Chunk::default();
+        for index in 0..CHUNK_VOLUME {
+            if chunk_local(index).x == 8 {
+                chunk.blocks[index] = Some(BlockKind::Stone);
+            }
+        }
+        let connectivity = face_connectivity(&chunk, &registry);
+        let (pos_x, neg_x, pos_y) = (0, 1, 2);
+        assert_eq!(connectivity & face_pair_bit(pos_x, neg_x), 0);
+        assert_ne!(connectivity & face_pair_bit(pos_x, pos_y), 0);
+        assert_ne!(connectivity & face_pair_bit(neg_x, pos_y), 0);
+
+        // Leaves don't block the view.
+        let leaves = Chunk {
+            blocks: vec![Some(BlockKind::Leaves); CHUNK_VOLUME],
+            ..default()
+        };
+        assert_eq!(face_connectivity(&leaves, &registry), ALL_FACES_CONNECTED);
+    }
+
+    #[test]
+    fn cave_culling_stops_at_solid_chunks() {
+        let registry = BlockRegistry::default();
+        // Bury the camera's chunk in a shell of solid chunks.
+        let shell: HashMap<IVec3, u64> =
+            NEIGHBOURS.iter().map(|offset| (*offset, face_connectivity(&stone_chunk(), &registry))).collect();
+        let visible = visible_chunks(&shell, IVec3::ZERO, 4);
+        assert!(visible.contains(&IVec3::ZERO));
+        assert!(visible.contains(&IVec3::X));
+        assert!(!visible.contains(&IVec3::new(2, 0, 0)));
+        assert!(!visible.contains(&IVec3::new(1, 1, 0)));
+
+        let open = visible_chunks(&HashMap::new(), IVec3::ZERO, 2);
+        assert!(open.contains(&IVec3::new(2, 0, -2)));
+        assert!(!open.contains(&IVec3::new(3, 0, 0)));
+    }
+
+    #[test]
+    fn lod_level_grows_with_distance() {
+        assert_eq!(lod_level(IVec3::new(4, 0, 0), IVec3::ZERO), 0);
+        assert_eq!(lod_level(IVec3::new(5, 0, -2), IVec3::ZERO), 1);
+        assert_eq!(lod_level(IVec3::new(0, 0, 9), IVec3::ZERO), 2);
+        assert_eq!(lod_level(IVec3::new(20, 0, 20), IVec3::new(2, 0, 2)), 3);
+    }
+
+    #[test]
+    fn downsample_keeps_majority_blocks() {
+        let mut blocks = vec![None; CHUNK_VOLUME];
+        // Fill the bottom half of the first 2x2x2 cell with stone and one block with dirt above it.
+        for local in [IVec3::new(0, 0, 0), IVec3::new(1, 0, 0), IVec3::new(0, 0, 1), IVec3::new(1, 0, 1)] {
+            blocks[chunk_position(local).1] = Some(BlockKind::Stone);
+        }
+        blocks[chunk_position(IVec3::new(0, 1, 0)).1] = Some(BlockKind::Dirt);
+        // A lone block in the next cell is not enough to keep it.
+        blocks[chunk_position(IVec3::new(2, 0, 0)).1] = Some(BlockKind::Stone);
+
+        let cells = downsample_blocks(&blocks, 2);
+        assert_eq!(cells.len(), 512);
+        assert_eq!(cells[0], Some(BlockKind::Stone));
+        assert_eq!(cells[1], None);
+    }
+
+    fn lod_chunk(kind: Option<BlockKind>) -> LodChunk {
+        LodChunk {
+            blocks: vec![kind; CHUNK_VOLUME],
+            sky_light: vec![MAX_LIGHT; CHUNK_VOLUME],
+            block_light: vec![0; CHUNK_VOLUME],
+        }
+    }
+
+    fn lod_vertices(layers: &HashMap<MeshLayer, LayerGeometry>, layer: MeshLayer) -> usize {
+        layers.get(&layer).map_or(0, |geometry| geometry.positions.len())
+    }
+
+    #[test]
+    fn lod_mesh_of_a_solid_chunk_is_its_shell() {
+        let registry = BlockRegistry::default();
+        let atlas = BlockAtlas {
+            tiles: HashMap::from([(MISSING_TEXTURE.to_string(), 0)]),
+            columns: 1,
+        };
+        let solid = LodSnapshot {
+            chunk: lod_chunk(Some(BlockKind::Stone)),
+            neighbours: (0..6).map(|_| None).collect(),
+        };
+        for level in 1..=3 {
+            let cells = (CHUNK_SIZE >> level) as usize;
+            let layers = build_lod_mesh(&solid, &registry, &atlas, level);
+            assert_eq!(lod_vertices(&layers, MeshLayer::Opaque), 6 * cells * cells * 4);
+            // Faces onto missing chunks are lit like open sky.
+            assert!(layers[&MeshLayer::Opaque].colors.iter().all(|color| color[0] == 1.0));
+        }
+
+        // A solid neighbour hides the face it shares.
+        let mut neighbours: Vec<Option<(usize, LodChunk)>> = (0..6).map(|_| None).collect();
+        neighbours[0] = Some((3, lod_chunk(Some(BlockKind::Stone))));
+        let walled = LodSnapshot { neighbours, ..solid };
+        let layers = build_lod_mesh(&walled, &registry, &atlas, 3);
+        assert_eq!(lod_vertices(&layers, MeshLayer::Opaque), 5 * 4 * 4);
+    }
+
+    #[test]
+    fn lod_mesh_keeps_layers_light_and_neighbour_levels() {
+        let registry = BlockRegistry::default();
+        let atlas = BlockAtlas {
+            tiles: HashMap::from([(MISSING_TEXTURE.to_string(), 0)]),
+            columns: 1,
+        };
+        let water = LodSnapshot {
+            chunk: lod_chunk(Some(BlockKind::Water)),
+            neighbours: (0..6).map(|_| None).collect(),
+        };
+        let layers = build_lod_mesh(&water, &registry, &atlas, 2);
+        assert_eq!(lod_vertices(&layers, MeshLayer::Opaque), 0);
+        assert_eq!(lod_vertices(&layers, MeshLayer::Translucent), 6 * 4 * 4 * 4);
+
+        // The +X neighbour is drawn at full detail with one air block against every cell, so no border
+        // face is hidden, even though downsampling it at this chunk's level would fill it in.
+        let mut neighbour = lod_chunk(Some(BlockKind::Stone));
+        for y in (0..CHUNK_SIZE).step_by(4) {
+            for z in (0..CHUNK_SIZE).step_by(4) {
+                let (_, index) = chunk_position(IVec3::new(0, y, z));
+                neighbour.blocks[index] = None;
+                neighbour.sky_light[index] = 5;
+            }
+        }
+        let mut neighbours: Vec<Option<(usize, LodChunk)>> = (0..6).map(|_| None).collect();
+        neighbours[0] = Some((0, neighbour));
+        let snapshot = LodSnapshot { chunk: lod_chunk(Some(BlockKind::Stone)), neighbours };
+        let layers = build_lod_mesh(&snapshot, &registry, &atlas, 2);
+        let geometry = &layers[&MeshLayer::Opaque];
+        assert_eq!(geometry.positions.len(), 6 * 4 * 4 * 4);
+        // Those faces are shaded by the light of the air blocks in front of them.
+        let border: Vec<f32> = geometry
+            .normals
+            .iter()
+            .zip(&geometry.colors)
+            .filter(|(normal, _)| **normal == [1.0, 0.0, 0.0])
+            .map(|(_, color)| color[0])
+            .collect();
+        assert_eq!(border.len(), 4 * 4 * 4);
+        assert!(border.iter().all(|shade| *shade == light_brightness(5)));
+
+        // At the same level as this chunk, the neighbour counts as solid and hides the border.
+        let mut snapshot = snapshot;
+        snapshot.neighbours[0].as_mut().unwrap().0 = 2;
+        let layers = build_lod_mesh(&snapshot, &registry, &atlas, 2);
+        assert_eq!(lod_vertices(&layers, MeshLayer::Opaque), 5 * 4 * 4 * 4);
+    }
+}
@@ -0,0 +1,390 @@
+use super::*;
+
+const ASSETS_DIR: &str = "assets";
+
+const PACK_RELOAD_INTERVAL: f32 = 1.0;
+
+const LANGUAGE: &str = "en_us";
+
+// Looks for packs added or removed on disk each time the pause screen opens.
+pub(super) fn rescan_resource_packs(mut resource_packs: ResMut<ResourcePacks>) {
+    resource_packs.available = list_resource_packs();
+}
+
+enum PackFiles {
+    Directory(PathBuf),
+    Zip(HashMap<String, Vec<u8>>),
+}
+
+// A resource pack mirrors the layout of the assets directory. Only textures/ and lang/ are read; the
+// game has no block models or sounds for a pack to replace.
+struct ResourcePack {
+    name: String,
+    files: PackFiles,
+}
+
+impl ResourcePack {
+    fn open(path: &Path) -> Result<Self, String> {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let files = if path.is_dir() {
+            PackFiles::Directory(path.to_path_buf())
+        } else {
+            let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            PackFiles::Zip(read_zip(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?)
+        };
+        Ok(Self { name, files })
+    }
+
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        match &self.files {
+            PackFiles::Directory(root) => std::fs::read(root.join(path)).ok(),
+            PackFiles::Zip(entries) => entries.get(path).cloned(),
+        }
+    }
+}
+
+fn zip_u16(bytes: &[u8], at: usize) -> Result<usize, String> {
+    bytes
+        .get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| "truncated zip".to_string())
+}
+
+fn zip_u32(bytes: &[u8], at: usize) -> Result<usize, String> {
+    bytes
+        .get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| "truncated zip".to_string())
+}
+
+// Reads every file of a zip archive through its central directory. Only stored and deflated entries are supported.
+fn read_zip(bytes: &[u8]) -> Result<HashMap<String, Vec<u8>>, String> {
+    const END_OF_DIRECTORY: [u8; 4] = [0x50, 0x4b, 0x05, 0x06];
+    const DIRECTORY_ENTRY: [u8; 4] = [0x50, 0x4b, 0x01, 0x02];
+    const LOCAL_HEADER: [u8; 4] = [0x50, 0x4b, 0x03, 0x04];
+
+    // The end record is the last 22 bytes unless the archive carries a comment.
+    let end = (0..=bytes.len().saturating_sub(22))
+        .rev()
+        .find(|&at| bytes[at..].starts_with(&END_OF_DIRECTORY))
+        .ok_or("not a zip archive")?;
+    let count = zip_u16(bytes, end + 10)?;
+    let mut entry = zip_u32(bytes, end + 16)?;
+
+    let mut files = HashMap::new();
+    for _ in 0..count {
+        if !bytes.get(entry..).is_some_and(|b| b.starts_with(&DIRECTORY_ENTRY)) {
+            return Err("corrupt central directory".to_string());
+        }
+        let method = zip_u16(bytes, entry + 10)?;
+        let compressed_size = zip_u32(bytes, entry + 20)?;
+        let name_length = zip_u16(bytes, entry + 28)?;
+        let extra_length = zip_u16(bytes, entry + 30)?;
+        let comment_length = zip_u16(bytes, entry + 32)?;
+        let local = zip_u32(bytes, entry + 42)?;
+        let name = bytes.get(entry + 46..entry + 46 + name_length).ok_or("truncated zip")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        entry += 46 + name_length + extra_length + comment_length;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        if !bytes.get(local..).is_some_and(|b| b.starts_with(&LOCAL_HEADER)) {
+            return Err(format!("corrupt local header for {}", name));
+        }
+        let start = local + 30 + zip_u16(bytes, local + 26)? + zip_u16(bytes, local + 28)?;
+        let data = bytes.get(start..start + compressed_size).ok_or("truncated zip")?;
+        let contents = match method {
+            0 => data.to_vec(),
+            8 => {
+                let mut contents = Vec::new();
+                flate2::read::DeflateDecoder::new(data)
+                    .read_to_end(&mut contents)
+                    .map_err(|e| format!("{}: {}", name, e))?;
+                contents
+            }
+            _ => return Err(format!("{}: unsupported compression method {}", name, method)),
+        };
+        files.insert(name, contents);
+    }
+    Ok(files)
+}
+
+// Directories and .zip files under the resource pack folder, by name.
+fn list_resource_packs() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(RESOURCE_PACK_DIR) else {
+        return Vec::new();
+    };
+    let mut packs: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir() || path.extension().is_some_and(|ext| ext == "zip"))
+        .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().into_owned()))
+        .collect();
+    packs.sort();
+    packs
+}
+
+// Enabling puts a pack on top of the stack; disabling takes it out.
+pub(super) fn toggle_pack(enabled: &mut Vec<String>, name: &str) {
+    match enabled.iter().position(|pack| pack == name) {
+        Some(index) => {
+            enabled.remove(index);
+        }
+        None => enabled.insert(0, name.to_string()),
+    }
+}
+
+// Swaps an enabled pack with the one above it.
+pub(super) fn raise_pack(enabled: &mut [String], name: &str) {
+    if let Some(index) = enabled.iter().position(|pack| pack == name).filter(|&index| index > 0) {
+        enabled.swap(index, index - 1);
+    }
+}
+
+// Hashes the size and modification time of everything a reload would read, so edits on disk can be spotted cheaply.
+fn pack_fingerprint(enabled: &[String]) -> u64 {
+    fn visit(path: &Path, hasher: &mut std::collections::hash_map::DefaultHasher) {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return;
+        };
+        path.hash(hasher);
+        metadata.len().hash(hasher);
+        metadata.modified().ok().hash(hasher);
+        if metadata.is_dir() {
+            if let Ok(entries) = std::fs::read_dir(path) {
+                let mut children: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+                children.sort();
+                for child in children {
+                    visit(&child, hasher);
+                }
+            }
+        }
+    }
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    let roots = std::iter::once(PathBuf::from(ASSETS_DIR)).chain(enabled.iter().map(|name| Path::new(RESOURCE_PACK_DIR).join(name)));
+    for root in roots {
+        if root.is_dir() {
+            for dir in [BLOCK_TEXTURE_DIR, "lang"] {
+                visit(&root.join(dir), &mut hasher);
+            }
+        } else {
+            // A zip pack is watched as a whole.
+            visit(&root, &mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+#[derive(Resource)]
+pub(super) struct ResourcePacks {
+    // Highest priority first; the assets directory sits underneath all of them.
+    active: Vec<ResourcePack>,
+    enabled: Vec<String>,
+    pub(super) available: Vec<String>,
+    fingerprint: u64,
+    reload_timer: Timer,
+}
+
+impl Default for ResourcePacks {
+    fn default() -> Self {
+        Self {
+            active: Vec::new(),
+            enabled: Vec::new(),
+            available: Vec::new(),
+            fingerprint: 0,
+            reload_timer: Timer::from_seconds(PACK_RELOAD_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+impl ResourcePacks {
+    pub(super) fn load(enabled: &[String]) -> Self {
+        let active = enabled
+            .iter()
+            .filter_map(|name| {
+                ResourcePack::open(&Path::new(RESOURCE_PACK_DIR).join(name))
+                    .map_err(|e| warn!("Failed to load resource pack {}", e))
+                    .ok()
+            })
+            .collect();
+        Self {
+            active,
+            enabled: enabled.to_vec(),
+            available: list_resource_packs(),
+            fingerprint: pack_fingerprint(enabled),
+            ..default()
+        }
+    }
+
+    pub(super) fn read(&self, path: &str) -> Option<Vec<u8>> {
+        self.active
+            .iter()
+            .find_map(|pack| pack.read(path))
+            .or_else(|| std::fs::read(Path::new(ASSETS_DIR).join(path)).ok())
+    }
+
+    // Every layer's copy of `path`, lowest priority first, for files that merge rather than replace.
+    fn read_all(&self, path: &str) -> Vec<Vec<u8>> {
+        std::fs::read(Path::new(ASSETS_DIR).join(path))
+            .ok()
+            .into_iter()
+            .chain(self.active.iter().rev().filter_map(|pack| pack.read(path)))
+            .collect()
+    }
+}
+
+#[derive(Resource, Default)]
+pub(super) struct Language {
+    strings: HashMap<String, String>,
+}
+
+impl Language {
+    // Language files are `key=value` lines; higher priority packs override single keys.
+    pub(super) fn load(packs: &ResourcePacks) -> Self {
+        let mut strings = HashMap::new();
+        for file in packs.read_all(&format!("lang/{}.lang", LANGUAGE)) {
+            for line in String::from_utf8_lossy(&file).lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some((key, value)) = line.split_once('=') {
+                    strings.insert(key.trim().to_string(), value.trim().to_string());
+                }
+            }
+        }
+        Self { strings }
+    }
+
+    pub(super) fn item_name(&self, item: ItemKind) -> String {
+        self.strings.get(&format!("item.{:?}", item)).cloned().unwrap_or_else(|| format!("{:?}", item))
+    }
+}
+
+// Reloads packs when the enabled list changes or any of their files change on disk, then rebuilds
+// everything derived from them.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn reload_resource_packs(
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    block_assets: Res<BlockAssets>,
+    mut resource_packs: ResMut<ResourcePacks>,
+    mut language: ResMut<Language>,
+    mut atlas: ResMut<BlockAtlas>,
+    mut world: ResMut<WorldBlocks>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let enabled_changed = resource_packs.enabled != settings.resource_packs;
+    let timer_finished = resource_packs.reload_timer.tick(time.delta()).just_finished();
+    let files_changed = timer_finished && pack_fingerprint(&settings.resource_packs) != resource_packs.fingerprint;
+    if !enabled_changed && !files_changed {
+        return;
+    }
+
+    *resource_packs = ResourcePacks::load(&settings.resource_packs);
+    *language = Language::load(&resource_packs);
+
+    let (new_atlas, image) = build_block_atlas(&resource_packs);
+    *atlas = new_atlas;
+    let image = images.add(image);
+    for material in block_assets.chunk_materials.values() {
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color_texture = Some(image.clone());
+        }
+    }
+    let chunks: Vec<IVec3> = world.chunks.keys().copied().collect();
+    world.dirty.extend(chunks);
+    let names: Vec<&str> = resource_packs.active.iter().map(|pack| pack.name.as_str()).collect();
+    info!("Reloaded resource packs: {:?}", names);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for (name, contents) in files {
+            let offset = archive.len() as u32;
+            let mut header = Vec::new();
+            header.extend_from_slice(&[0x50, 0x4b, 0x03, 0x04, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            header.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            header.extend_from_slice(&[0, 0]);
+            archive.extend_from_slice(&header);
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(contents);
+
+            directory.extend_from_slice(&[0x50, 0x4b, 0x01, 0x02, 20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            directory.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(&[0x50, 0x4b, 0x05, 0x06, 0, 0, 0, 0]);
+        archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&directory_offset.to_le_bytes());
+        archive.extend_from_slice(&[0, 0]);
+        archive
+    }
+
+    fn zip_pack(name: &str, files: &[(&str, &[u8])]) -> ResourcePack {
+        ResourcePack {
+            name: name.to_string(),
+            files: PackFiles::Zip(read_zip(&stored_zip(files)).unwrap()),
+        }
+    }
+
+    #[test]
+    fn read_zip_extracts_stored_files() {
+        let files = read_zip(&stored_zip(&[("lang/en_us.lang", b"item.Stone=Rock"), ("textures/", b"")])).unwrap();
+        assert_eq!(files.get("lang/en_us.lang").map(Vec::as_slice), Some(&b"item.Stone=Rock"[..]));
+        assert!(!files.contains_key("textures/"));
+        assert!(read_zip(b"not a zip").is_err());
+        assert!(read_zip(&stored_zip(&[("a", b"abc")])[..20]).is_err());
+    }
+
+    #[test]
+    fn higher_priority_packs_win() {
+        let packs = ResourcePacks {
+            active: vec![
+                zip_pack("top", &[("lang/en_us.lang", b"item.Stone=Top Stone")]),
+                zip_pack("bottom", &[("lang/en_us.lang", b"item.Stone=Bottom Stone\nitem.Dirt=Mud"), ("textures/blocks/dirt.png", b"png")]),
+            ],
+            ..default()
+        };
+        assert_eq!(packs.active[0].name, "top");
+        assert_eq!(packs.read("lang/en_us.lang").unwrap(), b"item.Stone=Top Stone");
+        assert_eq!(packs.read("textures/blocks/dirt.png").unwrap(), b"png");
+        // Falls through to the bundled assets.
+        assert!(packs.read("textures/blocks/stone.png").is_some());
+
+        let language = Language::load(&packs);
+        assert_eq!(language.item_name(ItemKind::Stone), "Top Stone");
+        assert_eq!(language.item_name(ItemKind::Dirt), "Mud");
+        assert_eq!(language.item_name(ItemKind::Coal), "Coal");
+    }
+
+    #[test]
+    fn pack_list_toggles_and_reorders() {
+        let mut enabled = vec!["a".to_string(), "b".to_string()];
+        toggle_pack(&mut enabled, "c");
+        assert_eq!(enabled, ["c", "a", "b"]);
+        raise_pack(&mut enabled, "b");
+        raise_pack(&mut enabled, "c");
+        assert_eq!(enabled, ["c", "b", "a"]);
+        toggle_pack(&mut enabled, "b");
+        assert_eq!(enabled, ["c", "a"]);
+    }
+}
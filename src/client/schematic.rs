@@ -0,0 +1,280 @@
+use super::*;
+
+// A box of blocks in Sponge schematic order: x fastest, then z, then y.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Schematic {
+    pub(super) size: IVec3,
+    blocks: Vec<Option<BlockKind>>,
+}
+
+// Minecraft 1.16.5, the last release every Sponge v2 reader understands.
+const SCHEMATIC_DATA_VERSION: i32 = 2586;
+
+// Namespaced Minecraft ids for our blocks. The first id listed for a block is the one we write;
+// the rest only map other Minecraft blocks onto ours when reading.
+const BLOCK_IDS: &[(&str, Option<BlockKind>)] = &[
+    ("minecraft:air", None),
+    ("minecraft:grass_block", Some(BlockKind::Grass)),
+    ("minecraft:dirt", Some(BlockKind::Dirt)),
+    ("minecraft:stone", Some(BlockKind::Stone)),
+    ("minecraft:cobblestone", Some(BlockKind::Cobblestone)),
+    ("minecraft:coal_ore", Some(BlockKind::CoalOre)),
+    ("minecraft:oak_log", Some(BlockKind::Log)),
+    ("minecraft:oak_leaves", Some(BlockKind::Leaves)),
+    ("minecraft:oak_planks", Some(BlockKind::Planks)),
+    ("minecraft:crafting_table", Some(BlockKind::CraftingTable)),
+    ("minecraft:torch", Some(BlockKind::Torch)),
+    ("minecraft:lava", Some(BlockKind::Lava)),
+    ("minecraft:glass", Some(BlockKind::Glass)),
+    ("minecraft:water", Some(BlockKind::Water)),
+    ("minecraft:cave_air", None),
+    ("minecraft:void_air", None),
+    ("minecraft:coarse_dirt", Some(BlockKind::Dirt)),
+    ("minecraft:wall_torch", Some(BlockKind::Torch)),
+    ("minecraft:granite", Some(BlockKind::Stone)),
+    ("minecraft:diorite", Some(BlockKind::Stone)),
+    ("minecraft:andesite", Some(BlockKind::Stone)),
+    ("minecraft:deepslate", Some(BlockKind::Stone)),
+    ("minecraft:bedrock", Some(BlockKind::Stone)),
+    ("minecraft:deepslate_coal_ore", Some(BlockKind::CoalOre)),
+    ("minecraft:birch_log", Some(BlockKind::Log)),
+    ("minecraft:spruce_log", Some(BlockKind::Log)),
+    ("minecraft:birch_leaves", Some(BlockKind::Leaves)),
+    ("minecraft:spruce_leaves", Some(BlockKind::Leaves)),
+    ("minecraft:birch_planks", Some(BlockKind::Planks)),
+    ("minecraft:spruce_planks", Some(BlockKind::Planks)),
+];
+
+pub(super) fn block_id(kind: Option<BlockKind>) -> &'static str {
+    BLOCK_IDS.iter().find(|(_, block)| *block == kind).map_or("minecraft:air", |(id, _)| id)
+}
+
+// `None` when the id is unknown; `Some(None)` for air. Block state properties such as `[axis=y]` are ignored.
+pub(super) fn block_from_id(id: &str) -> Option<Option<BlockKind>> {
+    let id = id.split('[').next().unwrap_or(id);
+    let id = if id.contains(':') { id.to_string() } else { format!("minecraft:{id}") };
+    BLOCK_IDS.iter().find(|(name, _)| *name == id).map(|(_, kind)| *kind)
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u32, String> {
+    let (value, len) = peek_varint(bytes.get(*pos..).unwrap_or_default())?.ok_or("Block data ends mid-value")?;
+    *pos += len;
+    Ok(value)
+}
+
+pub(super) fn copy_schematic(world: &WorldBlocks, min: IVec3, max: IVec3) -> Schematic {
+    Schematic {
+        size: max - min + IVec3::ONE,
+        blocks: region_positions(min, max).map(|pos| world.get(pos)).collect(),
+    }
+}
+
+impl Schematic {
+    pub(super) fn positions(&self) -> impl Iterator<Item = (IVec3, Option<BlockKind>)> + '_ {
+        region_positions(IVec3::ZERO, self.size - IVec3::ONE).zip(self.blocks.iter().copied())
+    }
+
+    pub(super) fn to_nbt(&self) -> nbt::Tag {
+        use nbt::Tag;
+        let mut palette: Vec<Option<BlockKind>> = Vec::new();
+        let mut data = Vec::new();
+        for kind in &self.blocks {
+            let index = palette.iter().position(|entry| entry == kind).unwrap_or_else(|| {
+                palette.push(*kind);
+                palette.len() - 1
+            });
+            write_varint(&mut data, index as u32);
+        }
+
+        let palette_tag = palette
+            .iter()
+            .enumerate()
+            .map(|(index, kind)| (block_id(*kind).to_string(), Tag::Int(index as i32)))
+            .collect();
+        Tag::Compound(
+            [
+                ("Version", Tag::Int(2)),
+                ("DataVersion", Tag::Int(SCHEMATIC_DATA_VERSION)),
+                ("Width", Tag::Short(self.size.x as u16 as i16)),
+                ("Height", Tag::Short(self.size.y as u16 as i16)),
+                ("Length", Tag::Short(self.size.z as u16 as i16)),
+                ("Offset", Tag::IntArray(vec![0, 0, 0])),
+                ("PaletteMax", Tag::Int(palette.len() as i32)),
+                ("Palette", Tag::Compound(palette_tag)),
+                ("BlockData", Tag::ByteArray(data.into_iter().map(|byte| byte as i8).collect())),
+                ("BlockEntities", Tag::List(Vec::new())),
+            ]
+            .into_iter()
+            .map(|(name, tag)| (name.to_string(), tag))
+            .collect(),
+        )
+    }
+
+    // Reads versions 1 to 3. Also returns how many blocks had ids we don't know, which load as air.
+    pub(super) fn from_nbt(root: &nbt::Tag) -> Result<(Schematic, usize), String> {
+        // Version 3 nests everything in a `Schematic` compound and the blocks in `Blocks`.
+        let root = root.get("Schematic").unwrap_or(root);
+        let blocks = root.get("Blocks").unwrap_or(root);
+        let dimension = |name: &str| {
+            root.get(name)
+                .and_then(nbt::Tag::as_i64)
+                .map(|value| value as u16 as i32)
+                .ok_or_else(|| format!("Schematic is missing {name}"))
+        };
+        let size = IVec3::new(dimension("Width")?, dimension("Height")?, dimension("Length")?);
+
+        let palette = blocks.get("Palette").and_then(nbt::Tag::as_compound).ok_or("Schematic is missing its palette")?;
+        let mut mapping = HashMap::new();
+        for (id, index) in palette {
+            let index = index.as_i64().ok_or_else(|| format!("Palette entry {id} is not a number"))?;
+            mapping.insert(index as u32, block_from_id(id));
+        }
+
+        let data = match blocks.get("BlockData").or_else(|| blocks.get("Data")) {
+            Some(nbt::Tag::ByteArray(data)) => data.iter().map(|&byte| byte as u8).collect::<Vec<u8>>(),
+            _ => return Err("Schematic is missing its block data".to_string()),
+        };
+        // Checked before allocating, since the dimensions come straight from the file.
+        let volume = (size.x as u64).checked_mul(size.y as u64).and_then(|area| area.checked_mul(size.z as u64)).unwrap_or(u64::MAX);
+        if volume == 0 {
+            return Err("Schematic is empty".to_string());
+        }
+        if volume > FILL_LIMIT as u64 {
+            return Err(format!("Schematic is too big: {volume} blocks (limit {FILL_LIMIT})"));
+        }
+        let volume = volume as usize;
+        let mut kinds = Vec::with_capacity(volume);
+        let mut unknown = 0;
+        let mut pos = 0;
+        for _ in 0..volume {
+            let index = read_varint(&data, &mut pos)?;
+            match mapping.get(&index) {
+                Some(Some(kind)) => kinds.push(*kind),
+                Some(None) => {
+                    unknown += 1;
+                    kinds.push(None);
+                }
+                None => return Err(format!("Block data refers to palette entry {index}, which doesn't exist")),
+            }
+        }
+        if pos != data.len() {
+            return Err(format!("Block data has more entries than the schematic's {volume} blocks"));
+        }
+        Ok((Schematic { size, blocks: kinds }, unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_ids_round_trip() {
+        for kind in BlockKind::iter() {
+            assert_eq!(block_from_id(block_id(Some(kind))), Some(Some(kind)));
+        }
+        assert_eq!(block_from_id("minecraft:oak_log[axis=x]"), Some(Some(BlockKind::Log)));
+        assert_eq!(block_from_id("stone"), Some(Some(BlockKind::Stone)));
+        assert_eq!(block_from_id("minecraft:cave_air"), Some(None));
+        assert_eq!(block_from_id("minecraft:beacon"), None);
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 300, 16383, 16384, u32::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value);
+            let mut pos = 0;
+            assert_eq!(read_varint(&bytes, &mut pos), Ok(value));
+            assert_eq!(pos, bytes.len());
+        }
+        assert_eq!(read_varint(&[0x80, 0x01], &mut 0), Ok(128));
+        assert!(read_varint(&[0x80], &mut 0).is_err());
+        assert!(read_varint(&[0xff; 6], &mut 0).is_err());
+    }
+
+    #[test]
+    fn schematics_round_trip_through_gzip() {
+        let mut world = WorldBlocks::default();
+        world.set_raw(IVec3::new(0, 0, 0), Some(BlockKind::Stone));
+        world.set_raw(IVec3::new(2, 1, 0), Some(BlockKind::Log));
+        world.set_raw(IVec3::new(1, 0, 1), Some(BlockKind::Glass));
+        let schematic = copy_schematic(&world, IVec3::ZERO, IVec3::new(2, 1, 1));
+        assert_eq!(schematic.blocks.len(), 12);
+        // x fastest, then z, then y.
+        assert_eq!(schematic.blocks[1 + 3], Some(BlockKind::Glass));
+        assert_eq!(schematic.blocks[2 + 6], Some(BlockKind::Log));
+
+        let bytes = nbt::write_gzip("Schematic", &schematic.to_nbt()).unwrap();
+        let (name, root) = nbt::read_gzip(&bytes).unwrap();
+        assert_eq!(name, "Schematic");
+        assert_eq!(root.get("Version"), Some(&nbt::Tag::Int(2)));
+        assert_eq!(Schematic::from_nbt(&root), Ok((schematic.clone(), 0)));
+
+        // Pasting near the edge of i32 is refused rather than wrapping around.
+        assert_eq!(region_at(IVec3::new(-1, 0, 5), schematic.size), Ok((IVec3::new(-1, 0, 5), IVec3::new(1, 1, 6))));
+        assert!(region_at(IVec3::new(i32::MAX - 1, 0, 0), schematic.size).is_err());
+        assert!(region_at(IVec3::new(0, 0, i32::MAX), schematic.size).is_err());
+    }
+
+    #[test]
+    fn schematic_import_maps_unknown_blocks_to_air() {
+        use nbt::Tag;
+        let compound = |entries: Vec<(&str, Tag)>| {
+            Tag::Compound(entries.into_iter().map(|(name, tag)| (name.to_string(), tag)).collect())
+        };
+        // Version 3 layout with a block we don't have.
+        let root = compound(vec![(
+            "Schematic",
+            compound(vec![
+                ("Version", Tag::Int(3)),
+                ("Width", Tag::Short(2)),
+                ("Height", Tag::Short(1)),
+                ("Length", Tag::Short(1)),
+                (
+                    "Blocks",
+                    compound(vec![
+                        ("Palette", compound(vec![("minecraft:stone", Tag::Int(0)), ("minecraft:beacon", Tag::Int(1))])),
+                        ("Data", Tag::ByteArray(vec![0, 1])),
+                    ]),
+                ),
+            ]),
+        )]);
+        let (schematic, unknown) = Schematic::from_nbt(&root).unwrap();
+        assert_eq!(schematic.blocks, vec![Some(BlockKind::Stone), None]);
+        assert_eq!(unknown, 1);
+
+        let truncated = compound(vec![
+            ("Width", Tag::Short(3)),
+            ("Height", Tag::Short(1)),
+            ("Length", Tag::Short(1)),
+            ("Palette", compound(vec![("minecraft:stone", Tag::Int(0))])),
+            ("BlockData", Tag::ByteArray(vec![0, 0])),
+        ]);
+        assert!(Schematic::from_nbt(&truncated).is_err());
+        let bad_index = compound(vec![
+            ("Width", Tag::Short(1)),
+            ("Height", Tag::Short(1)),
+            ("Length", Tag::Short(1)),
+            ("Palette", compound(vec![("minecraft:stone", Tag::Int(0))])),
+            ("BlockData", Tag::ByteArray(vec![5])),
+        ]);
+        assert!(Schematic::from_nbt(&bad_index).is_err());
+        let oversized = compound(vec![
+            ("Width", Tag::Short(-1)),
+            ("Height", Tag::Short(-1)),
+            ("Length", Tag::Short(1)),
+            ("Palette", compound(vec![("minecraft:stone", Tag::Int(0))])),
+            ("BlockData", Tag::ByteArray(vec![0])),
+        ]);
+        assert!(Schematic::from_nbt(&oversized).unwrap_err().contains("too big"));
+        let overlong = compound(vec![
+            ("Width", Tag::Short(1)),
+            ("Height", Tag::Short(1)),
+            ("Length", Tag::Short(1)),
+            ("Palette", compound(vec![("minecraft:stone", Tag::Int(0))])),
+            ("BlockData", Tag::ByteArray(vec![0, 0])),
+        ]);
+        assert!(Schematic::from_nbt(&overlong).is_err());
+    }
+}
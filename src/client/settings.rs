@@ -0,0 +1,160 @@
+use super::*;
+
+pub(super) fn save_settings(settings: &GameSettings) -> std::io::Result<()> {
+    let mut content = String::new();
+    content.push_str(&format!("forward={:?}\n", settings.keybinds.forward));
+    content.push_str(&format!("backwards={:?}\n", settings.keybinds.backward));
+    content.push_str(&format!("strafe_left={:?}\n", settings.keybinds.left));
+    content.push_str(&format!("strafe_right={:?}\n", settings.keybinds.right));
+    content.push_str(&format!("jump={:?}\n", settings.keybinds.jump));
+    content.push_str(&format!("sprint={:?}\n", settings.keybinds.sprint));
+    content.push_str(&format!("inventory={:?}\n", settings.keybinds.inventory));
+    content.push_str(&format!("drop={:?}\n", settings.keybinds.drop));
+    content.push_str(&format!("item_lifetime={}\n", settings.simulation.item_lifetime));
+    content.push_str(&format!("day_length={}\n", settings.simulation.day_length));
+    content.push_str(&format!("resource_packs={}\n", settings.resource_packs.join(",")));
+    content.push_str(&format!("render_distance={}\n", settings.render_distance));
+    content.push_str(&format!("fog={}\n", match settings.fog {
+        FogMode::Linear => "linear",
+        FogMode::Exponential => "exponential",
+    }));
+    content.push_str(&format!("clouds={}\n", match settings.clouds {
+        CloudMode::Off => "off",
+        CloudMode::Flat => "flat",
+        CloudMode::Blocky => "blocky",
+    }));
+    content.push_str(&format!("cloud_height={}\n", settings.cloud_height));
+    content.push_str(&format!("servers={}\n", settings.servers.join(",")));
+
+    std::fs::create_dir_all("assets")?;
+    std::fs::write("assets/options.txt", content)
+}
+
+// Handles the options that are not keybinds, returning false for unknown keys.
+fn apply_option(settings: &mut GameSettings, key: &str, value: &str) -> bool {
+    match key {
+        "item_lifetime" => {
+            if let Ok(lifetime) = value.parse() {
+                settings.simulation.item_lifetime = lifetime;
+            }
+        }
+        "day_length" => {
+            if let Ok(day_length) = value.parse::<f32>() {
+                settings.simulation.day_length = day_length.max(1.0);
+            }
+        }
+        "render_distance" => {
+            if let Ok(distance) = value.parse::<i32>() {
+                settings.render_distance = distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+            }
+        }
+        "fog" => match value {
+            "linear" => settings.fog = FogMode::Linear,
+            "exponential" => settings.fog = FogMode::Exponential,
+            _ => {}
+        },
+        "clouds" => match value {
+            "off" => settings.clouds = CloudMode::Off,
+            "flat" => settings.clouds = CloudMode::Flat,
+            "blocky" => settings.clouds = CloudMode::Blocky,
+            _ => {}
+        },
+        "cloud_height" => {
+            if let Some(height) = value.parse::<f32>().ok().filter(|height| height.is_finite()) {
+                settings.cloud_height = height.clamp(WORLD_MAX_Y as f32, MAX_CLOUD_HEIGHT);
+            }
+        }
+        "servers" => {
+            settings.servers = value
+                .split(',')
+                .map(str::trim)
+                .filter(|address| !address.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        "resource_packs" => {
+            settings.resource_packs = value
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        _ => return false,
+    }
+    true
+}
+
+pub(super) fn load_settings() -> Option<GameSettings> {
+    let content = std::fs::read_to_string("assets/options.txt").ok()?;
+    let mut settings = GameSettings::default();
+
+    for line in content.lines() {
+        let mut parts = line.split('=');
+        let key = parts.next()?;
+        let value = parts.next()?;
+        if apply_option(&mut settings, key, value.trim()) {
+            continue;
+        }
+        let keycode = match value.trim() {
+            "KeyCode::A" => bevy::prelude::KeyCode::A,
+            "KeyCode::B" => bevy::prelude::KeyCode::B,
+            "KeyCode::C" => bevy::prelude::KeyCode::C,
+            "KeyCode::D" => bevy::prelude::KeyCode::D,
+            "KeyCode::E" => bevy::prelude::KeyCode::E,
+            "KeyCode::F" => bevy::prelude::KeyCode::F,
+            "KeyCode::G" => bevy::prelude::KeyCode::G,
+            "KeyCode::H" => bevy::prelude::KeyCode::H,
+            "KeyCode::I" => bevy::prelude::KeyCode::I,
+            "KeyCode::J" => bevy::prelude::KeyCode::J,
+            "KeyCode::K" => bevy::prelude::KeyCode::K,
+            "KeyCode::L" => bevy::prelude::KeyCode::L,
+            "KeyCode::M" => bevy::prelude::KeyCode::M,
+            "KeyCode::N" => bevy::prelude::KeyCode::N,
+            "KeyCode::O" => bevy::prelude::KeyCode::O,
+            "KeyCode::P" => bevy::prelude::KeyCode::P,
+            "KeyCode::Q" => bevy::prelude::KeyCode::Q,
+            "KeyCode::R" => bevy::prelude::KeyCode::R,
+            "KeyCode::S" => bevy::prelude::KeyCode::S,
+            "KeyCode::T" => bevy::prelude::KeyCode::T,
+            "KeyCode::U" => bevy::prelude::KeyCode::U,
+            "KeyCode::V" => bevy::prelude::KeyCode::V,
+            "KeyCode::W" => bevy::prelude::KeyCode::W,
+            "KeyCode::X" => bevy::prelude::KeyCode::X,
+            "KeyCode::Y" => bevy::prelude::KeyCode::Y,
+            "KeyCode::Z" => bevy::prelude::KeyCode::Z,
+            "KeyCode::Space" => bevy::prelude::KeyCode::Space,
+            "KeyCode::LeftShift" => bevy::prelude::KeyCode::ShiftLeft,
+            _ => continue,
+        };
+        match key {
+            "forward" => settings.keybinds.forward = keycode,
+            "backwards" => settings.keybinds.backward = keycode,
+            "strafe_left" => settings.keybinds.left = keycode,
+            "strafe_right" => settings.keybinds.right = keycode,
+            "jump" => settings.keybinds.jump = keycode,
+            "sprint" => settings.keybinds.sprint = keycode,
+            "inventory" => settings.keybinds.inventory = keycode,
+            "drop" => settings.keybinds.drop = keycode,
+            _ => {}
+        }
+    }
+
+    Some(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaded_cloud_height_is_clamped() {
+        let mut settings = GameSettings::default();
+        assert!(apply_option(&mut settings, "cloud_height", "100000"));
+        assert_eq!(settings.cloud_height, MAX_CLOUD_HEIGHT);
+        apply_option(&mut settings, "cloud_height", "-5");
+        assert_eq!(settings.cloud_height, WORLD_MAX_Y as f32);
+        apply_option(&mut settings, "cloud_height", "NaN");
+        assert_eq!(settings.cloud_height, WORLD_MAX_Y as f32);
+    }
+}
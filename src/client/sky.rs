@@ -0,0 +1,428 @@
+use super::*;
+
+impl WorldTime {
+    // Fraction of the day, where 0.0 is sunrise and 0.25 is noon.
+    fn time_of_day(&self) -> f32 {
+        (self.ticks.rem_euclid(TICKS_PER_DAY) / TICKS_PER_DAY) as f32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Biome {
+    Plains,
+    SnowyPlains,
+}
+
+#[derive(Component)]
+pub(super) struct Precipitation {
+    velocity: Vec3,
+    // Height of the first block below the drop, where it lands.
+    floor: f32,
+}
+
+#[derive(Resource)]
+pub(super) struct PrecipitationAssets {
+    pub(super) rain_mesh: Handle<Mesh>,
+    pub(super) rain_material: Handle<StandardMaterial>,
+    pub(super) snow_mesh: Handle<Mesh>,
+    pub(super) snow_material: Handle<StandardMaterial>,
+}
+
+// The cloud layer's mesh is laid out in drifting cloud space and rebuilt when the camera crosses a cell.
+#[derive(Component)]
+pub(super) struct Clouds {
+    pub(super) built_for: Option<(IVec2, CloudMode, u32)>,
+}
+
+#[derive(Component)]
+pub(super) struct Sun;
+
+#[derive(Component)]
+pub(super) struct Moon;
+
+// How brightly the last lightning strike still lights the sky, fading to 0.
+#[derive(Resource, Default)]
+pub(super) struct LightningFlash(f32);
+
+const WEATHER_FADE_SPEED: f32 = 0.2;
+
+const PRECIPITATION_COUNT: usize = 400;
+
+const PRECIPITATION_RADIUS: f32 = 16.0;
+
+const PRECIPITATION_SPAWN_HEIGHT: f32 = 20.0;
+
+const RAIN_SPEED: f32 = 14.0;
+
+const SNOW_SPEED: f32 = 2.0;
+
+const LIGHTNING_CHANCE: f32 = 0.003;
+
+const SNOW_TEMPERATURE: f64 = -0.15;
+
+const CLOUD_CELL_SIZE: f32 = 12.0;
+
+const CLOUD_THICKNESS: f32 = 4.0;
+
+const CLOUD_RADIUS: i32 = 24;
+
+const CLOUD_SPEED: f32 = 1.5;
+
+const CLOUD_NOISE_SCALE: f64 = 0.15;
+
+const CLOUD_THRESHOLD: f64 = 0.2;
+
+const UNDERWATER_FOG_COLOR: Color = Color::rgb(0.05, 0.15, 0.35);
+
+const UNDERWATER_FOG_DENSITY: f32 = 0.15;
+
+pub(super) fn sky_color(time_of_day: f32) -> Color {
+    let day = Color::rgb(0.5, 0.7, 1.0);
+    let sunset = Color::rgb(0.9, 0.5, 0.3);
+    let night = Color::rgb(0.01, 0.01, 0.05);
+    let height = (time_of_day * std::f32::consts::TAU).sin();
+
+    let lerp = |a: Color, b: Color, t: f32| {
+        let t = t.clamp(0.0, 1.0);
+        Color::rgb(
+            a.r() + (b.r() - a.r()) * t,
+            a.g() + (b.g() - a.g()) * t,
+            a.b() + (b.b() - a.b()) * t,
+        )
+    };
+    if height >= 0.0 {
+        lerp(sunset, day, height / 0.3)
+    } else {
+        lerp(sunset, night, -height / 0.2)
+    }
+}
+
+fn ambient_brightness(time_of_day: f32) -> f32 {
+    0.05 + 0.25 * daylight(time_of_day)
+}
+
+// 0 at night, 1 once the sun is properly up.
+fn daylight(time_of_day: f32) -> f32 {
+    let height = (time_of_day * std::f32::consts::TAU).sin();
+    ((height + 0.2) / 0.5).clamp(0.0, 1.0)
+}
+
+// Fog reaches the sky colour at the render distance; night pulls it in closer.
+fn fog_falloff(mode: FogMode, render_distance: i32, daylight: f32) -> FogFalloff {
+    let end = (render_distance * CHUNK_SIZE) as f32;
+    let end = end * (0.6 + 0.4 * daylight);
+    match mode {
+        FogMode::Linear => FogFalloff::Linear { start: end * 0.6, end },
+        // e^-3 leaves about 5% of the original colour at `end`.
+        FogMode::Exponential => FogFalloff::Exponential { density: 3.0 / end },
+    }
+}
+
+pub(super) fn update_fog(
+    settings: Res<GameSettings>,
+    world_time: Res<WorldTime>,
+    clear_color: Res<ClearColor>,
+    world: Res<WorldBlocks>,
+    mut camera_query: Query<(&GlobalTransform, &mut FogSettings), With<Camera3d>>,
+) {
+    let Ok((transform, mut fog)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let daylight = daylight(world_time.time_of_day());
+    let eye = transform.translation().round().as_ivec3();
+    if world.get(eye) == Some(BlockKind::Water) {
+        let brightness = 0.2 + 0.8 * daylight;
+        fog.color = Color::rgb(
+            UNDERWATER_FOG_COLOR.r() * brightness,
+            UNDERWATER_FOG_COLOR.g() * brightness,
+            UNDERWATER_FOG_COLOR.b() * brightness,
+        );
+        fog.falloff = FogFalloff::Exponential { density: UNDERWATER_FOG_DENSITY };
+    } else {
+        fog.color = clear_color.0;
+        fog.falloff = fog_falloff(settings.fog, settings.render_distance, daylight);
+    }
+}
+
+fn is_cloud(noise: &Perlin, cell: IVec2) -> bool {
+    noise.get([cell.x as f64 * CLOUD_NOISE_SCALE, cell.y as f64 * CLOUD_NOISE_SCALE]) > CLOUD_THRESHOLD
+}
+
+fn cloud_noise(seed: u32) -> Perlin {
+    // Offset so the clouds don't trace the terrain's height map.
+    Perlin::new(seed.wrapping_add(0x5eed_c10d))
+}
+
+// Cloud cells within `CLOUD_RADIUS` of `center`, as flat quads or boxes with shared sides removed.
+fn build_cloud_mesh(noise: &Perlin, center: IVec2, mode: CloudMode) -> Option<Mesh> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    let size = Vec3::new(CLOUD_CELL_SIZE, CLOUD_THICKNESS, CLOUD_CELL_SIZE);
+
+    for dz in -CLOUD_RADIUS..=CLOUD_RADIUS {
+        for dx in -CLOUD_RADIUS..=CLOUD_RADIUS {
+            let cell = center + IVec2::new(dx, dz);
+            if !is_cloud(noise, cell) {
+                continue;
+            }
+            let origin = Vec3::new(cell.x as f32 + 0.5, 0.5, cell.y as f32 + 0.5) * size;
+            for (normal, corners) in FACES {
+                let visible = match mode {
+                    CloudMode::Off => false,
+                    CloudMode::Flat => normal == IVec3::NEG_Y,
+                    CloudMode::Blocky => normal.y != 0 || !is_cloud(noise, cell + IVec2::new(normal.x, normal.z)),
+                };
+                if !visible {
+                    continue;
+                }
+                let base = positions.len() as u32;
+                for corner in corners {
+                    let position = origin + corner * size;
+                    // Flat clouds are a single sheet at the bottom of the layer.
+                    let position = if mode == CloudMode::Flat { Vec3::new(position.x, 0.0, position.z) } else { position };
+                    positions.push(position.to_array());
+                    normals.push(normal.as_vec3().to_array());
+                }
+                indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+            }
+        }
+    }
+
+    if indices.is_empty() {
+        return None;
+    }
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    Some(mesh)
+}
+
+// Drift follows world time, so a given seed and time always show the same sky.
+#[allow(clippy::type_complexity)]
+pub(super) fn update_clouds(
+    settings: Res<GameSettings>,
+    world_seed: Res<WorldSeed>,
+    world_time: Res<WorldTime>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+    mut clouds_query: Query<(&mut Clouds, &mut Transform, &mut Visibility, &mut Handle<Mesh>, &Handle<StandardMaterial>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let (Ok(camera), Ok((mut clouds, mut transform, mut visibility, mut mesh, material))) =
+        (camera_query.get_single(), clouds_query.get_single_mut())
+    else {
+        return;
+    };
+    if settings.clouds == CloudMode::Off {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    }
+    visibility.set_if_neq(Visibility::Inherited);
+
+    let drift = (world_time.ticks / TICKS_PER_SECOND) as f32 * CLOUD_SPEED;
+    transform.translation = Vec3::new(drift, settings.cloud_height, 0.0);
+
+    let local = camera.translation() - transform.translation;
+    let center = IVec2::new((local.x / CLOUD_CELL_SIZE).floor() as i32, (local.z / CLOUD_CELL_SIZE).floor() as i32);
+    let key = (center, settings.clouds, world_seed.0);
+    if clouds.built_for != Some(key) {
+        clouds.built_for = Some(key);
+        *mesh = build_cloud_mesh(&cloud_noise(world_seed.0), center, settings.clouds)
+            .map(|cloud_mesh| meshes.add(cloud_mesh))
+            .unwrap_or_default();
+    }
+
+    if let Some(material) = materials.get_mut(material) {
+        let brightness = 0.2 + 0.8 * daylight(world_time.time_of_day());
+        material.base_color = Color::rgba(
+            CLOUD_COLOR.r() * brightness,
+            CLOUD_COLOR.g() * brightness,
+            CLOUD_COLOR.b() * brightness,
+            CLOUD_COLOR.a(),
+        );
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(super) fn update_sky(
+    world_time: Res<WorldTime>,
+    weather: Res<Weather>,
+    flash: Res<LightningFlash>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun_query: Query<(&mut Transform, &mut DirectionalLight), (With<Sun>, Without<Moon>)>,
+    mut moon_query: Query<(&mut Transform, &mut DirectionalLight), (With<Moon>, Without<Sun>)>,
+) {
+    let time_of_day = world_time.time_of_day();
+    let angle = time_of_day * std::f32::consts::TAU;
+    // Tilted slightly so the light never points straight down the up axis.
+    let sun_direction = Vec3::new(angle.cos(), angle.sin(), 0.2).normalize();
+
+    let overcast = weather_darkness(&weather);
+    if let Ok((mut transform, mut light)) = sun_query.get_single_mut() {
+        *transform = Transform::from_translation(sun_direction).looking_at(Vec3::ZERO, Vec3::Y);
+        light.illuminance = 50000.0 * (sun_direction.y * 4.0).clamp(0.0, 1.0) * (1.0 - overcast);
+    }
+    if let Ok((mut transform, mut light)) = moon_query.get_single_mut() {
+        *transform = Transform::from_translation(-sun_direction).looking_at(Vec3::ZERO, Vec3::Y);
+        light.illuminance = 500.0 * (-sun_direction.y * 4.0).clamp(0.0, 1.0);
+    }
+
+    let sky = sky_color(time_of_day);
+    let grey = (sky.r() + sky.g() + sky.b()) / 3.0 * (1.0 - overcast);
+    let mix = |channel: f32| channel + (grey - channel) * weather.intensity + flash.0;
+    clear_color.0 = Color::rgb(mix(sky.r()), mix(sky.g()), mix(sky.b()));
+    ambient.brightness = ambient_brightness(time_of_day) * (1.0 - overcast) + flash.0;
+}
+
+// How much rain or a storm dims the sky, from 0 to just under 1.
+fn weather_darkness(weather: &Weather) -> f32 {
+    let storm = if weather.kind == WeatherKind::Thunder { 0.75 } else { 0.5 };
+    weather.intensity * storm
+}
+
+pub(super) fn fade_weather(time: Res<Time>, mut weather: ResMut<Weather>, mut flash: ResMut<LightningFlash>) {
+    let target = if weather.kind == WeatherKind::Clear { 0.0 } else { 1.0 };
+    let step = WEATHER_FADE_SPEED * time.delta_seconds();
+    weather.intensity += (target - weather.intensity).clamp(-step, step);
+    flash.0 = (flash.0 - time.delta_seconds() * 2.0).max(0.0);
+    if weather.kind == WeatherKind::Thunder && weather.intensity > 0.9 && random::<f32>() < LIGHTNING_CHANCE {
+        flash.0 = 0.6;
+    }
+}
+
+pub(super) fn temperature_noise(seed: u32) -> Perlin {
+    Perlin::new(seed.wrapping_add(0x7e3b_0a11))
+}
+
+pub(super) fn biome_at(temperature: &Perlin, x: i32, z: i32) -> Biome {
+    if temperature.get([x as f64 * 0.01, z as f64 * 0.01]) < SNOW_TEMPERATURE {
+        Biome::SnowyPlains
+    } else {
+        Biome::Plains
+    }
+}
+
+// Height of the highest block in a column, or the bottom of the world if it is empty. Rain and snow stop here.
+fn precipitation_floor(world: &WorldBlocks, x: i32, z: i32) -> i32 {
+    (WORLD_MIN_Y..=WORLD_MAX_Y)
+        .rev()
+        .find(|&y| world.get(IVec3::new(x, y, z)).is_some())
+        .unwrap_or(WORLD_MIN_Y - 1)
+}
+
+// Keeps a pool of falling drops around the camera. Each lands on the topmost block of its column,
+// so nothing falls under a roof.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn update_precipitation(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    world: Res<WorldBlocks>,
+    world_seed: Res<WorldSeed>,
+    assets: Res<PrecipitationAssets>,
+    camera_query: Query<&GlobalTransform, With<Camera3d>>,
+    mut drops: Query<(Entity, &mut Transform, &Precipitation)>,
+) {
+    let Ok(camera) = camera_query.get_single() else {
+        return;
+    };
+    let eye = camera.translation();
+    let mut alive = 0;
+    for (entity, mut transform, drop) in drops.iter_mut() {
+        transform.translation += drop.velocity * time.delta_seconds();
+        let offset = transform.translation - eye;
+        if transform.translation.y < drop.floor || offset.x.abs() > PRECIPITATION_RADIUS || offset.z.abs() > PRECIPITATION_RADIUS {
+            commands.entity(entity).despawn();
+        } else {
+            alive += 1;
+        }
+    }
+
+    let wanted = (PRECIPITATION_COUNT as f32 * weather.intensity) as usize;
+    let temperature = temperature_noise(world_seed.0);
+    // Spread spawning out so a fresh shower doesn't arrive as one sheet.
+    for _ in 0..wanted.saturating_sub(alive).min(PRECIPITATION_COUNT / 20) {
+        let offset = Vec3::new(random::<f32>() - 0.5, 0.0, random::<f32>() - 0.5) * PRECIPITATION_RADIUS * 2.0;
+        let spawn = eye + offset + Vec3::Y * (PRECIPITATION_SPAWN_HEIGHT * random::<f32>());
+        let column = spawn.round().as_ivec3();
+        let floor = precipitation_floor(&world, column.x, column.z) as f32 + 0.5;
+        if spawn.y <= floor {
+            continue;
+        }
+        let (mesh, material, velocity) = match biome_at(&temperature, column.x, column.z) {
+            Biome::SnowyPlains => (
+                assets.snow_mesh.clone(),
+                assets.snow_material.clone(),
+                Vec3::new(random::<f32>() - 0.5, -SNOW_SPEED, random::<f32>() - 0.5),
+            ),
+            Biome::Plains => (assets.rain_mesh.clone(), assets.rain_material.clone(), Vec3::NEG_Y * RAIN_SPEED),
+        };
+        commands.spawn((
+            PbrBundle {
+                mesh,
+                material,
+                transform: Transform::from_translation(spawn),
+                ..default()
+            },
+            NotShadowCaster,
+            Precipitation { velocity, floor },
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fog_closes_in_at_night() {
+        let FogFalloff::Linear { end: day, .. } = fog_falloff(FogMode::Linear, 8, 1.0) else {
+            panic!("expected linear fog");
+        };
+        let FogFalloff::Linear { end: night, .. } = fog_falloff(FogMode::Linear, 8, 0.0) else {
+            panic!("expected linear fog");
+        };
+        assert_eq!(day, 128.0);
+        assert!(night < day);
+    }
+
+    #[test]
+    fn clouds_are_deterministic_per_seed() {
+        let cells = |seed: u32| -> Vec<bool> {
+            let noise = cloud_noise(seed);
+            (-20..20).flat_map(|x| (-20..20).map(move |z| IVec2::new(x, z))).map(|cell| is_cloud(&noise, cell)).collect()
+        };
+        let first = cells(WORLD_SEED);
+        assert_eq!(first, cells(WORLD_SEED));
+        assert_ne!(first, cells(WORLD_SEED + 1));
+        assert!(first.iter().any(|cloud| *cloud));
+        assert!(first.iter().any(|cloud| !*cloud));
+    }
+
+    #[test]
+    fn flat_clouds_use_one_quad_per_cell() {
+        let noise = cloud_noise(WORLD_SEED);
+        let cells = (-CLOUD_RADIUS..=CLOUD_RADIUS)
+            .flat_map(|x| (-CLOUD_RADIUS..=CLOUD_RADIUS).map(move |z| IVec2::new(x, z)))
+            .filter(|cell| is_cloud(&noise, *cell))
+            .count();
+        let flat = build_cloud_mesh(&noise, IVec2::ZERO, CloudMode::Flat).unwrap();
+        assert_eq!(flat.count_vertices(), cells * 4);
+        let blocky = build_cloud_mesh(&noise, IVec2::ZERO, CloudMode::Blocky).unwrap();
+        assert!(blocky.count_vertices() > cells * 8);
+        assert!(build_cloud_mesh(&noise, IVec2::ZERO, CloudMode::Off).is_none());
+    }
+
+    #[test]
+    fn precipitation_stops_at_roofs() {
+        let mut world = WorldBlocks::default();
+        assert_eq!(precipitation_floor(&world, 0, 0), WORLD_MIN_Y - 1);
+        world.set_raw(IVec3::new(0, 2, 0), Some(BlockKind::Stone));
+        world.set_raw(IVec3::new(0, 10, 0), Some(BlockKind::Planks));
+        assert_eq!(precipitation_floor(&world, 0, 0), 10);
+        assert_eq!(precipitation_floor(&world, 1, 0), WORLD_MIN_Y - 1);
+    }
+}
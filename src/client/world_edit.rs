@@ -0,0 +1,186 @@
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct BlockChange {
+    pub(super) pos: IVec3,
+    pub(super) before: Option<BlockKind>,
+    pub(super) after: Option<BlockKind>,
+}
+
+// Two corners picked with /pos1 and /pos2; world edits act on the box between them.
+#[derive(Resource, Default)]
+pub(super) struct Selection {
+    corners: [Option<IVec3>; 2],
+}
+
+impl Selection {
+    pub(super) fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        let [Some(a), Some(b)] = self.corners else {
+            return None;
+        };
+        Some((a.min(b), a.max(b)))
+    }
+}
+
+#[derive(Resource, Default)]
+pub(super) struct EditHistory {
+    pub(super) undo: Vec<Vec<BlockChange>>,
+    pub(super) redo: Vec<Vec<BlockChange>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub(super) enum Mirror {
+    None,
+    X,
+    Z,
+}
+
+// Edits bigger than this relight the whole world once instead of block by block.
+const BULK_RELIGHT_THRESHOLD: usize = 4096;
+
+pub(super) fn set_corner(args: &[Arg], context: &mut CommandContext, corner: usize) -> Result<String, String> {
+    let pos = match args {
+        [Arg::Position(pos)] => context.resolve(pos),
+        _ => context.player.translation,
+    }
+    .round()
+    .as_ivec3();
+    context.selection.corners[corner] = Some(pos);
+    let size = context
+        .selection
+        .bounds()
+        .map(|(min, max)| {
+            let size = region_size(min, max);
+            format!(" ({} blocks)", size.x.saturating_mul(size.y).saturating_mul(size.z))
+        })
+        .unwrap_or_default();
+    Ok(format!("Corner {} set to {} {} {}{size}", corner + 1, pos.x, pos.y, pos.z))
+}
+
+// Clips a box to the world's height and refuses ones too big to edit in one go.
+pub(super) fn clamp_region(min: IVec3, max: IVec3) -> Result<(IVec3, IVec3), String> {
+    let min = min.max(IVec3::new(i32::MIN, WORLD_MIN_Y, i32::MIN));
+    let max = max.min(IVec3::new(i32::MAX, WORLD_MAX_Y, i32::MAX));
+    let size = region_size(min, max).max(I64Vec3::ZERO);
+    let volume = size.x.saturating_mul(size.y).saturating_mul(size.z);
+    if volume == 0 {
+        return Err("The region is outside the world".to_string());
+    }
+    if volume > FILL_LIMIT as i64 {
+        return Err(format!("Too many blocks: {volume} (limit {FILL_LIMIT})"));
+    }
+    Ok((min, max))
+}
+
+// The box of `size` whose lowest corner is `origin`, refused when its far corner overflows i32.
+pub(super) fn region_at(origin: IVec3, size: IVec3) -> Result<(IVec3, IVec3), String> {
+    let max = origin.as_i64vec3() + size.as_i64vec3() - I64Vec3::ONE;
+    if max.cmpgt(I64Vec3::splat(i32::MAX as i64)).any() {
+        return Err("The region is outside the world".to_string());
+    }
+    clamp_region(origin, max.as_ivec3())
+}
+
+// In i64, since corners far apart overflow i32.
+fn region_size(min: IVec3, max: IVec3) -> I64Vec3 {
+    max.as_i64vec3() - min.as_i64vec3() + I64Vec3::ONE
+}
+
+pub(super) fn region_positions(min: IVec3, max: IVec3) -> impl Iterator<Item = IVec3> {
+    (min.y..=max.y).flat_map(move |y| (min.z..=max.z).flat_map(move |z| (min.x..=max.x).map(move |x| IVec3::new(x, y, z))))
+}
+
+// Where `offset` inside a box of `size` ends up after mirroring and then turning clockwise seen from above.
+fn transform_offset(offset: IVec3, size: IVec3, quarter_turns: u32, mirror: Mirror) -> IVec3 {
+    let mut offset = match mirror {
+        Mirror::None => offset,
+        Mirror::X => IVec3::new(size.x - 1 - offset.x, offset.y, offset.z),
+        Mirror::Z => IVec3::new(offset.x, offset.y, size.z - 1 - offset.z),
+    };
+    let mut size = size;
+    for _ in 0..quarter_turns % 4 {
+        offset = IVec3::new(size.z - 1 - offset.z, offset.y, offset.x);
+        size = IVec3::new(size.z, size.y, size.x);
+    }
+    offset
+}
+
+pub(super) fn clone_region(
+    world: &WorldBlocks,
+    min: IVec3,
+    max: IVec3,
+    destination: IVec3,
+    quarter_turns: u32,
+    mirror: Mirror,
+) -> Result<Vec<(IVec3, Option<BlockKind>)>, String> {
+    let size = max - min + IVec3::ONE;
+    let rotated = if quarter_turns % 2 == 1 { IVec3::new(size.z, size.y, size.x) } else { size };
+    region_at(destination, rotated)?;
+    // Read everything first so overlapping copies see the original blocks.
+    Ok(region_positions(min, max)
+        .map(|pos| (destination + transform_offset(pos - min, size, quarter_turns, mirror), world.get(pos)))
+        .collect())
+}
+
+// Writes blocks in bulk. Chunks are only flagged dirty, so each is remeshed once however many blocks changed.
+pub(super) fn apply_changes(world: &mut WorldBlocks, registry: &BlockRegistry, changes: impl Iterator<Item = (IVec3, Option<BlockKind>)>) {
+    let mut written = Vec::new();
+    for (pos, kind) in changes {
+        world.set_raw(pos, kind);
+        written.push((pos, kind));
+    }
+    if written.len() > BULK_RELIGHT_THRESHOLD {
+        initialize_light(world, registry);
+        return;
+    }
+    for (pos, kind) in written {
+        let emission = kind.map_or(0, |kind| registry.get(kind).emission);
+        update_light(&mut WorldLight { world, registry }, pos, emission);
+    }
+}
+
+pub(super) fn draw_selection(mut gizmos: Gizmos, selection: Res<Selection>, state: Res<State<GameState>>) {
+    if matches!(state.get(), GameState::MainMenu | GameState::Multiplayer | GameState::Loading) {
+        return;
+    }
+    let (min, max) = match selection.corners {
+        [Some(a), Some(b)] => (a.min(b), a.max(b)),
+        [Some(corner), None] | [None, Some(corner)] => (corner, corner),
+        [None, None] => return,
+    };
+    // Blocks are centred on integer coordinates; grow the box slightly so it doesn't z-fight.
+    let size = region_size(min, max).as_vec3() + Vec3::splat(0.02);
+    let center = (min.as_vec3() + max.as_vec3()) * 0.5;
+    gizmos.cuboid(Transform::from_translation(center).with_scale(size), Color::YELLOW);
+    for corner in selection.corners.iter().flatten() {
+        gizmos.cuboid(Transform::from_translation(corner.as_vec3()).with_scale(Vec3::splat(1.04)), Color::ORANGE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_offset_rotates_and_mirrors() {
+        let size = IVec3::new(3, 1, 2);
+        // A quarter turn maps x along the old -z edge.
+        assert_eq!(transform_offset(IVec3::ZERO, size, 1, Mirror::None), IVec3::new(1, 0, 0));
+        assert_eq!(transform_offset(IVec3::new(2, 0, 0), size, 1, Mirror::None), IVec3::new(1, 0, 2));
+        assert_eq!(transform_offset(IVec3::new(2, 0, 1), size, 2, Mirror::None), IVec3::ZERO);
+        assert_eq!(transform_offset(IVec3::new(1, 0, 1), size, 4, Mirror::None), IVec3::new(1, 0, 1));
+        assert_eq!(transform_offset(IVec3::ZERO, size, 0, Mirror::X), IVec3::new(2, 0, 0));
+        assert_eq!(transform_offset(IVec3::ZERO, size, 0, Mirror::Z), IVec3::new(0, 0, 1));
+
+        // Every cell lands on a distinct cell inside the rotated box.
+        for turns in 0..4 {
+            let rotated = if turns % 2 == 1 { IVec3::new(size.z, size.y, size.x) } else { size };
+            let cells: HashSet<IVec3> = region_positions(IVec3::ZERO, size - IVec3::ONE)
+                .map(|offset| transform_offset(offset, size, turns, Mirror::X))
+                .collect();
+            assert_eq!(cells.len(), 6);
+            assert!(cells.iter().all(|cell| cell.cmpge(IVec3::ZERO).all() && cell.cmplt(rotated).all()));
+        }
+    }
+}
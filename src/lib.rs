@@ -0,0 +1,2574 @@
+use bevy::{
+    prelude::*,
+    app::{AppExit, ScheduleRunnerPlugin},
+};
+use noise::{NoiseFn, Perlin, Seedable};
+use strum::IntoEnumIterator;
+use strum_macros::{EnumIter, EnumString};
+use rand::random;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// The window, renderer, menus and single-player game. The headless server is built without it.
+#[cfg(feature = "client")]
+mod client;
+#[cfg(feature = "client")]
+pub use client::run_client;
+
+// Minecraft's NBT format, for interop with vanilla files.
+pub mod nbt;
+
+mod built_info {
+    include!(concat!(env!("OUT_DIR"), "/built_info.rs"));
+}
+
+#[derive(Component)]
+struct Velocity(Vec3);
+
+#[derive(Component)]
+struct Gravity(f32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, EnumIter)]
+enum BlockKind {
+    Grass,
+    Dirt,
+    Stone,
+    Cobblestone,
+    CoalOre,
+    Log,
+    Leaves,
+    Planks,
+    CraftingTable,
+    Torch,
+    Lava,
+    Glass,
+    Water,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolType {
+    Pickaxe,
+    Axe,
+    Shovel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ToolTier {
+    Wood,
+    Stone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tool {
+    tool_type: ToolType,
+    tier: ToolTier,
+}
+
+// What the simulation needs to know about a block. How it looks and breaks is up to the client.
+#[derive(Debug, Clone, Copy)]
+struct BlockProperties {
+    opaque: bool,
+    solid: bool,
+    emission: u8,
+    // Fluids can't be targeted and are replaced by placed blocks.
+    fluid: bool,
+}
+
+#[derive(Resource, Clone)]
+struct BlockRegistry {
+    blocks: HashMap<BlockKind, BlockProperties>,
+}
+
+impl Default for BlockRegistry {
+    fn default() -> Self {
+        let block = BlockProperties { opaque: true, solid: true, emission: 0, fluid: false };
+        let blocks = BlockKind::iter()
+            .map(|kind| {
+                let properties = match kind {
+                    BlockKind::Leaves | BlockKind::Glass => BlockProperties { opaque: false, ..block },
+                    BlockKind::Torch => BlockProperties { opaque: false, solid: false, emission: 14, ..block },
+                    BlockKind::Lava => BlockProperties { opaque: false, solid: false, emission: 15, fluid: true },
+                    BlockKind::Water => BlockProperties { opaque: false, solid: false, fluid: true, ..block },
+                    _ => block,
+                };
+                (kind, properties)
+            })
+            .collect();
+        Self { blocks }
+    }
+}
+
+impl BlockRegistry {
+    fn get(&self, kind: BlockKind) -> &BlockProperties {
+        &self.blocks[&kind]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, EnumIter)]
+enum ItemKind {
+    Grass,
+    Dirt,
+    Stone,
+    Cobblestone,
+    Log,
+    Leaves,
+    Planks,
+    CraftingTable,
+    Torch,
+    Glass,
+    Coal,
+    Stick,
+    WoodenPickaxe,
+    WoodenAxe,
+    WoodenShovel,
+    StonePickaxe,
+    StoneAxe,
+    StoneShovel,
+}
+
+impl ItemKind {
+    fn tool(&self) -> Option<Tool> {
+        let (tool_type, tier) = match self {
+            ItemKind::WoodenPickaxe => (ToolType::Pickaxe, ToolTier::Wood),
+            ItemKind::WoodenAxe => (ToolType::Axe, ToolTier::Wood),
+            ItemKind::WoodenShovel => (ToolType::Shovel, ToolTier::Wood),
+            ItemKind::StonePickaxe => (ToolType::Pickaxe, ToolTier::Stone),
+            ItemKind::StoneAxe => (ToolType::Axe, ToolTier::Stone),
+            ItemKind::StoneShovel => (ToolType::Shovel, ToolTier::Stone),
+            _ => return None,
+        };
+        Some(Tool { tool_type, tier })
+    }
+
+    fn max_stack(&self) -> u32 {
+        if self.tool().is_some() { 1 } else { MAX_STACK_SIZE }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ItemStack {
+    item: ItemKind,
+    count: u32,
+    // Uses taken off a tool's durability.
+    damage: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LightChannel {
+    Sky,
+    Block,
+}
+
+struct Chunk {
+    blocks: Vec<Option<BlockKind>>,
+    sky_light: Vec<u8>,
+    block_light: Vec<u8>,
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self {
+            blocks: vec![None; CHUNK_VOLUME],
+            sky_light: vec![MAX_LIGHT; CHUNK_VOLUME],
+            block_light: vec![0; CHUNK_VOLUME],
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+struct WorldBlocks {
+    chunks: HashMap<IVec3, Chunk>,
+    dirty: HashSet<IVec3>,
+}
+
+impl WorldBlocks {
+    fn get(&self, pos: IVec3) -> Option<BlockKind> {
+        let (chunk, index) = chunk_position(pos);
+        self.chunks.get(&chunk).and_then(|chunk| chunk.blocks[index])
+    }
+
+    fn is_solid(&self, registry: &BlockRegistry, pos: IVec3) -> bool {
+        self.get(pos).is_some_and(|kind| registry.get(kind).solid)
+    }
+
+    // Blocks the crosshair can hit; fluids are looked through.
+    fn is_targetable(&self, registry: &BlockRegistry, pos: IVec3) -> bool {
+        self.get(pos).is_some_and(|kind| !registry.get(kind).fluid)
+    }
+
+    fn is_replaceable(&self, registry: &BlockRegistry, pos: IVec3) -> bool {
+        self.get(pos).is_none_or(|kind| registry.get(kind).fluid)
+    }
+
+    fn light(&self, pos: IVec3, channel: LightChannel) -> u8 {
+        let (chunk, index) = chunk_position(pos);
+        match (self.chunks.get(&chunk), channel) {
+            (Some(chunk), LightChannel::Sky) => chunk.sky_light[index],
+            (Some(chunk), LightChannel::Block) => chunk.block_light[index],
+            (None, LightChannel::Sky) => MAX_LIGHT,
+            (None, LightChannel::Block) => 0,
+        }
+    }
+
+    // Flags the chunk holding `pos` for remeshing, along with neighbours whose faces border it.
+    fn mark_dirty(&mut self, pos: IVec3) {
+        let (chunk, _) = chunk_position(pos);
+        let local = pos.rem_euclid(IVec3::splat(CHUNK_SIZE));
+        self.dirty.insert(chunk);
+        for axis in 0..3 {
+            let mut neighbour = chunk;
+            if local[axis] == 0 {
+                neighbour[axis] -= 1;
+            } else if local[axis] == CHUNK_SIZE - 1 {
+                neighbour[axis] += 1;
+            } else {
+                continue;
+            }
+            self.dirty.insert(neighbour);
+        }
+    }
+
+    // Writes a block without updating light, for bulk generation followed by `initialize_light`.
+    fn set_raw(&mut self, pos: IVec3, kind: Option<BlockKind>) -> Option<BlockKind> {
+        if pos.y < WORLD_MIN_Y || pos.y > WORLD_MAX_Y {
+            return None;
+        }
+        let (chunk, index) = chunk_position(pos);
+        if !self.chunks.contains_key(&chunk) {
+            for y in WORLD_MIN_Y.div_euclid(CHUNK_SIZE)..=WORLD_MAX_Y.div_euclid(CHUNK_SIZE) {
+                self.chunks.entry(IVec3::new(chunk.x, y, chunk.z)).or_default();
+            }
+        }
+        let old = std::mem::replace(&mut self.chunks.get_mut(&chunk)?.blocks[index], kind);
+        self.mark_dirty(pos);
+        old
+    }
+
+    fn set_block(&mut self, registry: &BlockRegistry, pos: IVec3, kind: Option<BlockKind>) -> Option<BlockKind> {
+        let old = self.set_raw(pos, kind);
+        let emission = kind.map_or(0, |kind| registry.get(kind).emission);
+        update_light(&mut WorldLight { world: self, registry }, pos, emission);
+        old
+    }
+
+    fn clear(&mut self) {
+        let chunks: Vec<IVec3> = self.chunks.keys().copied().collect();
+        self.dirty.extend(chunks);
+        self.chunks.clear();
+    }
+}
+
+fn chunk_position(pos: IVec3) -> (IVec3, usize) {
+    let chunk = pos.div_euclid(IVec3::splat(CHUNK_SIZE));
+    let local = pos.rem_euclid(IVec3::splat(CHUNK_SIZE));
+    (chunk, (local.x + local.z * CHUNK_SIZE + local.y * CHUNK_SIZE * CHUNK_SIZE) as usize)
+}
+
+// Light propagation works against this so it can run on plain test grids as well as the world.
+trait LightGrid {
+    fn contains(&self, pos: IVec3) -> bool;
+    fn is_opaque(&self, pos: IVec3) -> bool;
+    fn light(&self, pos: IVec3, channel: LightChannel) -> u8;
+    fn set_light(&mut self, pos: IVec3, channel: LightChannel, level: u8);
+}
+
+struct WorldLight<'a> {
+    world: &'a mut WorldBlocks,
+    registry: &'a BlockRegistry,
+}
+
+impl LightGrid for WorldLight<'_> {
+    fn contains(&self, pos: IVec3) -> bool {
+        self.world.chunks.contains_key(&chunk_position(pos).0)
+    }
+
+    fn is_opaque(&self, pos: IVec3) -> bool {
+        self.world.get(pos).is_some_and(|kind| self.registry.get(kind).opaque)
+    }
+
+    fn light(&self, pos: IVec3, channel: LightChannel) -> u8 {
+        self.world.light(pos, channel)
+    }
+
+    fn set_light(&mut self, pos: IVec3, channel: LightChannel, level: u8) {
+        let (chunk, index) = chunk_position(pos);
+        if let Some(chunk) = self.world.chunks.get_mut(&chunk) {
+            match channel {
+                LightChannel::Sky => chunk.sky_light[index] = level,
+                LightChannel::Block => chunk.block_light[index] = level,
+            }
+            self.world.mark_dirty(pos);
+        }
+    }
+}
+
+#[derive(Component)]
+struct DroppedItem {
+    stack: ItemStack,
+    age: f32,
+}
+
+#[derive(Resource, Default)]
+struct WorldTime {
+    ticks: f64,
+}
+
+// The world generator's seed; anything seeded from it looks the same every time the world loads.
+#[derive(Resource, Clone, Copy)]
+struct WorldSeed(u32);
+
+impl Default for WorldSeed {
+    fn default() -> Self {
+        Self(WORLD_SEED)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString)]
+#[strum(serialize_all = "lowercase")]
+enum WeatherKind {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+#[derive(Resource)]
+struct Weather {
+    kind: WeatherKind,
+    // Ticks until the next transition.
+    remaining: f64,
+    // Eases between 0 (clear) and 1 (raining) so the sky doesn't snap.
+    intensity: f32,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self {
+            kind: WeatherKind::Clear,
+            remaining: weather_duration(WeatherKind::Clear, 0.5),
+            intensity: 0.0,
+        }
+    }
+}
+
+// Everything saved in level.txt.
+#[derive(Default)]
+struct LevelData {
+    time: WorldTime,
+    seed: WorldSeed,
+    weather: Weather,
+}
+
+// How fast the world ticks and how long dropped items last. The client reads these from its options,
+// the server from server.properties.
+#[derive(Resource, Clone, Copy)]
+struct SimulationSettings {
+    item_lifetime: f32,
+    day_length: f32,
+}
+
+impl Default for SimulationSettings {
+    fn default() -> Self {
+        Self { item_lifetime: ITEM_LIFETIME, day_length: DAY_LENGTH }
+    }
+}
+
+const WORLD_SIZE: i32 = 128;
+const SPRINT_MULTIPLIER: f32 = 5.0;
+const PLAYER_HEIGHT: f32 = 2.0;
+const PLAYER_WIDTH: f32 = 0.5;
+const PLAYER_JUMP_FORCE: f32 = 10.0;
+const PLAYER_BASE_SPEED: f32 = 200.0;
+const GRAVITY: f32 = 20.0;
+const PLAYER_REACH: f32 = 5.0;
+const MAX_STACK_SIZE: u32 = 64;
+const TREE_CHANCE: u32 = 60;
+const TREE_HEIGHT: i32 = 4;
+const SEA_LEVEL: i32 = -1;
+const WORLD_SEED: u32 = 42;
+const ITEM_SIZE: f32 = 0.25;
+const ITEM_LIFETIME: f32 = 300.0;
+const ITEM_MERGE_RADIUS: f32 = 0.5;
+const TICKS_PER_SECOND: f64 = 20.0;
+const TICKS_PER_DAY: f64 = 24000.0;
+const DAY_LENGTH: f32 = 1200.0;
+const SAVES_DIR: &str = "saves";
+const LEVEL_FILE: &str = "level.txt";
+// Also where the client keeps its single-player world.
+const DEFAULT_LEVEL_NAME: &str = "world";
+const SERVER_BIN_NAME: &str = "minceraft-server";
+const SERVER_PROPERTIES_PATH: &str = "server.properties";
+const DEFAULT_SERVER_PORT: u16 = 25565;
+// Bumped whenever the packets below change; clients and servers must agree exactly.
+const PROTOCOL_VERSION: u32 = 3;
+// Below 16 MiB, so our length prefix always starts with a zero byte; a vanilla client's never does.
+const MAX_PACKET_SIZE: usize = 1 << 20;
+// The server list ping's packets are tiny; anything bigger isn't one.
+const JAVA_MAX_PACKET_SIZE: usize = 4096;
+// A client further behind than this is dropped rather than buffered forever.
+const MAX_OUTGOING_BYTES: usize = 64 << 20;
+const MAX_NAME_LENGTH: usize = 16;
+const MAX_INPUTS_PER_DATAGRAM: usize = 8;
+// Longest frame the server will simulate for one input, in seconds.
+const MAX_INPUT_DT: f32 = 0.1;
+// Seconds of movement a client may bank ahead of the wall clock, to ride out network jitter.
+const MAX_MOVE_ALLOWANCE: f32 = 0.5;
+// How long a connection may take to log in or finish a status ping before it's dropped.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(15);
+// The local player's id on a server hosted from the pause menu; remote players count up from 1.
+const HOST_PLAYER_ID: u32 = 0;
+const JAVA_PLAYER_SAMPLE: usize = 12;
+const CHUNK_SIZE: i32 = 16;
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+const WORLD_MIN_Y: i32 = -16;
+const WORLD_MAX_Y: i32 = 47;
+const MAX_LIGHT: u8 = 15;
+const COAL_CHANCE: u32 = 40;
+const LAVA_CHANCE: u32 = 150;
+const NEIGHBOURS: [IVec3; 6] = [IVec3::X, IVec3::NEG_X, IVec3::Y, IVec3::NEG_Y, IVec3::Z, IVec3::NEG_Z];
+
+// The world and everything that ticks in it, without rendering or input, so it also runs headless under
+// `MinimalPlugins`. Hosts decide when it runs by configuring `SimulationSet` in `Update` and `FixedUpdate`.
+struct SimulationPlugin;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+struct SimulationSet;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationSettings>()
+            .init_resource::<WorldBlocks>()
+            .init_resource::<BlockRegistry>()
+            .init_resource::<WorldTime>()
+            .init_resource::<WorldSeed>()
+            .init_resource::<Weather>()
+            .insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND))
+            .add_systems(FixedUpdate, (advance_time, advance_weather).in_set(SimulationSet))
+            .add_systems(Update, (dropped_item_physics, merge_dropped_items, despawn_old_items).in_set(SimulationSet));
+    }
+}
+
+// Settings for `minceraft-server`, read from server.properties.
+#[derive(Resource, Debug, Clone, PartialEq)]
+struct ServerProperties {
+    level_name: String,
+    // Only used when the level is first created; empty picks a random seed.
+    level_seed: Option<u32>,
+    motd: String,
+    server_port: u16,
+    max_players: u32,
+    day_length: f32,
+    item_lifetime: f32,
+    // Seconds between saves; 0 saves only on shutdown.
+    autosave_interval: f32,
+    allow_flight: bool,
+}
+
+impl Default for ServerProperties {
+    fn default() -> Self {
+        Self {
+            level_name: DEFAULT_LEVEL_NAME.to_string(),
+            level_seed: None,
+            motd: "A Minceraft Server".to_string(),
+            server_port: DEFAULT_SERVER_PORT,
+            max_players: 20,
+            day_length: DAY_LENGTH,
+            item_lifetime: ITEM_LIFETIME,
+            autosave_interval: 300.0,
+            allow_flight: false,
+        }
+    }
+}
+
+impl ServerProperties {
+    // Unknown keys are ignored so newer files still load; malformed values are errors.
+    fn parse(text: &str) -> Result<Self, String> {
+        fn value<T: std::str::FromStr>(line: usize, key: &str, value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| format!("Line {line}: invalid {key}: {value}"))
+        }
+
+        let mut properties = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, raw)) = line.split_once('=') else {
+                return Err(format!("Line {line_number}: expected key=value"));
+            };
+            let (key, raw) = (key.trim(), raw.trim());
+            match key {
+                "level-name" => {
+                    if raw.is_empty() || !raw.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                        return Err(format!("Line {line_number}: invalid level-name: {raw}"));
+                    }
+                    properties.level_name = raw.to_string();
+                }
+                "level-seed" => properties.level_seed = if raw.is_empty() { None } else { Some(value(line_number, key, raw)?) },
+                "motd" => properties.motd = raw.to_string(),
+                "server-port" => properties.server_port = value(line_number, key, raw)?,
+                "max-players" => properties.max_players = value(line_number, key, raw)?,
+                "day-length" => properties.day_length = value::<f32>(line_number, key, raw)?.max(1.0),
+                "item-lifetime" => properties.item_lifetime = value(line_number, key, raw)?,
+                "autosave-interval" => properties.autosave_interval = value::<f32>(line_number, key, raw)?.max(0.0),
+                "allow-flight" => properties.allow_flight = value(line_number, key, raw)?,
+                _ => {}
+            }
+        }
+        Ok(properties)
+    }
+
+    fn to_text(&self) -> String {
+        let mut content = String::from("# Minceraft server properties\n");
+        content.push_str(&format!("level-name={}\n", self.level_name));
+        content.push_str(&format!("level-seed={}\n", self.level_seed.map_or(String::new(), |seed| seed.to_string())));
+        content.push_str(&format!("motd={}\n", self.motd));
+        content.push_str(&format!("server-port={}\n", self.server_port));
+        content.push_str(&format!("max-players={}\n", self.max_players));
+        content.push_str(&format!("day-length={}\n", self.day_length));
+        content.push_str(&format!("item-lifetime={}\n", self.item_lifetime));
+        content.push_str(&format!("autosave-interval={}\n", self.autosave_interval));
+        content.push_str(&format!("allow-flight={}\n", self.allow_flight));
+        content
+    }
+
+    fn level_path(&self) -> PathBuf {
+        level_path(&self.level_name)
+    }
+}
+
+fn level_path(level_name: &str) -> PathBuf {
+    Path::new(SAVES_DIR).join(level_name).join(LEVEL_FILE)
+}
+
+// Reads server.properties, writing the defaults there first if it doesn't exist yet.
+fn load_server_properties(path: &Path) -> Result<ServerProperties, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => ServerProperties::parse(&text).map_err(|err| format!("{}: {err}", path.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            let properties = ServerProperties::default();
+            std::fs::write(path, properties.to_text()).map_err(|err| format!("Could not write {}: {err}", path.display()))?;
+            Ok(properties)
+        }
+        Err(err) => Err(format!("Could not read {}: {err}", path.display())),
+    }
+}
+
+#[derive(Resource, Default)]
+struct ServerClock {
+    ticks: u64,
+    // Set by `--ticks`, for soak runs that should stop on their own.
+    stop_after: Option<u64>,
+}
+
+// The headless server: the simulation alone, ticking under `MinimalPlugins`.
+pub fn run_server() {
+    let mut stop_after = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next().map(|value| value.parse())) {
+            ("--ticks", Some(Ok(ticks))) => stop_after = Some(ticks),
+            _ => {
+                eprintln!("Usage: {SERVER_BIN_NAME} [--ticks <count>]");
+                std::process::exit(2);
+            }
+        }
+    }
+    println!("Starting Minceraft {} (built {})", built_info::BUILD_VERSION, built_info::BUILD_TIMESTAMP);
+    let properties = load_server_properties(Path::new(SERVER_PROPERTIES_PATH)).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+    let address = SocketAddr::from(([0, 0, 0, 0], properties.server_port));
+    let mut network = NetworkServer::bind(address, properties.max_players, properties.motd.clone()).unwrap_or_else(|err| {
+        eprintln!("Could not listen on port {}: {err}", properties.server_port);
+        std::process::exit(1);
+    });
+    network.allow_flight = properties.allow_flight;
+
+    App::new()
+        .add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / TICKS_PER_SECOND))))
+        .insert_resource(SimulationSettings { day_length: properties.day_length, item_lifetime: properties.item_lifetime })
+        .add_plugins(SimulationPlugin)
+        .insert_resource(properties)
+        .insert_resource(ServerClock { ticks: 0, stop_after })
+        .insert_resource(network)
+        .add_systems(Startup, start_server)
+        .add_systems(Update, serve_network)
+        .add_systems(FixedUpdate, (server_tick, send_snapshots).chain().after(SimulationSet))
+        .add_systems(Last, (save_server_on_exit, close_server_on_exit))
+        .run();
+}
+
+fn start_server(
+    properties: Res<ServerProperties>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    mut world_time: ResMut<WorldTime>,
+    mut world_seed: ResMut<WorldSeed>,
+    mut weather: ResMut<Weather>,
+    network: Res<NetworkServer>,
+) {
+    let path = properties.level_path();
+    let level = load_level(&path).unwrap_or_else(|| LevelData {
+        seed: WorldSeed(properties.level_seed.unwrap_or_else(random)),
+        ..default()
+    });
+    regenerate_world(&mut world, &registry, level.seed.0);
+    (*world_time, *world_seed, *weather) = (level.time, level.seed, level.weather);
+    save_level(&path, &world_time, &world_seed, &weather).unwrap_or_else(|e| eprintln!("Failed to save world: {}", e));
+    println!(
+        "Hosting '{}' (seed {}) on port {} with up to {} players",
+        properties.level_name, world_seed.0, network.local_addr().port(), properties.max_players
+    );
+}
+
+fn server_tick(
+    mut clock: ResMut<ServerClock>,
+    properties: Res<ServerProperties>,
+    world_time: Res<WorldTime>,
+    world_seed: Res<WorldSeed>,
+    weather: Res<Weather>,
+    mut exit: EventWriter<AppExit>,
+) {
+    clock.ticks += 1;
+    let autosave_ticks = (properties.autosave_interval as f64 * TICKS_PER_SECOND) as u64;
+    if autosave_ticks > 0 && clock.ticks.is_multiple_of(autosave_ticks) {
+        save_level(&properties.level_path(), &world_time, &world_seed, &weather)
+            .unwrap_or_else(|e| eprintln!("Failed to save world: {}", e));
+    }
+    if clock.stop_after.is_some_and(|limit| clock.ticks >= limit) {
+        println!("Stopping after {} ticks", clock.ticks);
+        exit.send(AppExit);
+    }
+}
+
+fn save_server_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    properties: Res<ServerProperties>,
+    world_time: Res<WorldTime>,
+    world_seed: Res<WorldSeed>,
+    weather: Res<Weather>,
+) {
+    if exit_events.read().next().is_some() {
+        save_level(&properties.level_path(), &world_time, &world_seed, &weather)
+            .unwrap_or_else(|e| eprintln!("Failed to save world: {}", e));
+    }
+}
+
+// One frame of player input. The client applies it straight away and sends it to the server, which replays
+// it on its own copy of the player; snapshots then tell the client which inputs the server has caught up to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MoveInput {
+    sequence: u32,
+    dt: f32,
+    // Horizontal velocity (x, z) for the frame.
+    walk: Vec2,
+    jump: bool,
+    // Vertical velocity set outright, while flying or holding sprint/sneak.
+    lift: Option<f32>,
+    flying: bool,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl MoveInput {
+    // The server only trusts inputs as far as the client could have produced them.
+    fn sanitized(self, allow_flight: bool) -> Self {
+        fn finite(value: f32) -> f32 {
+            if value.is_finite() { value } else { 0.0 }
+        }
+
+        let dt = finite(self.dt).clamp(0.0, MAX_INPUT_DT);
+        let walk = Vec2::new(finite(self.walk.x), finite(self.walk.y));
+        let max_lift = PLAYER_JUMP_FORCE.max(PLAYER_BASE_SPEED * dt);
+        Self {
+            dt,
+            walk: walk.clamp_length_max(PLAYER_BASE_SPEED * SPRINT_MULTIPLIER * dt),
+            lift: self.lift.filter(|_| allow_flight).map(|lift| finite(lift).clamp(-max_lift, max_lift)),
+            flying: self.flying && allow_flight,
+            yaw: finite(self.yaw),
+            pitch: finite(self.pitch).clamp(-1.5, 1.5),
+            ..self
+        }
+    }
+}
+
+// Sequence numbers wrap, so "newer" means less than half the range ahead.
+fn sequence_after(sequence: u32, other: u32) -> bool {
+    (sequence.wrapping_sub(other) as i32) > 0
+}
+
+// Big-endian, with strings and byte arrays prefixed by their length.
+#[derive(Default)]
+struct PacketWriter(Vec<u8>);
+
+impl PacketWriter {
+    fn u8(&mut self, value: u8) -> &mut Self {
+        self.0.push(value);
+        self
+    }
+
+    #[cfg(feature = "client")]
+    fn bool(&mut self, value: bool) -> &mut Self {
+        self.u8(value as u8)
+    }
+
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn i32(&mut self, value: i32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn u64(&mut self, value: u64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn f32(&mut self, value: f32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn f64(&mut self, value: f64) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn string(&mut self, value: &str) -> &mut Self {
+        let bytes = &value.as_bytes()[..value.len().min(u16::MAX as usize)];
+        self.u16(bytes.len() as u16);
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn bytes(&mut self, value: &[u8]) -> &mut Self {
+        self.u32(value.len() as u32);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    fn ivec3(&mut self, value: IVec3) -> &mut Self {
+        self.i32(value.x).i32(value.y).i32(value.z)
+    }
+
+    fn vec3(&mut self, value: Vec3) -> &mut Self {
+        self.f32(value.x).f32(value.y).f32(value.z)
+    }
+
+    fn block(&mut self, kind: Option<BlockKind>) -> &mut Self {
+        // 0 is air; blocks count up from 1 in declaration order.
+        self.u8(kind.map_or(0, |kind| kind as u8 + 1))
+    }
+
+    #[cfg(feature = "client")]
+    fn input(&mut self, input: &MoveInput) -> &mut Self {
+        self.u32(input.sequence).f32(input.dt).f32(input.walk.x).f32(input.walk.y).bool(input.jump);
+        self.bool(input.lift.is_some()).f32(input.lift.unwrap_or(0.0));
+        self.bool(input.flying).f32(input.yaw).f32(input.pitch)
+    }
+}
+
+struct PacketReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> PacketReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() < len {
+            return Err("Packet ended early".to_string());
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, String> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(format!("Invalid boolean {other}")),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_be_bytes(self.array()?))
+    }
+
+    #[cfg(feature = "client")]
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_be_bytes(self.array()?))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "Invalid UTF-8 in string".to_string())
+    }
+
+    #[cfg(feature = "client")]
+    fn bytes(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn ivec3(&mut self) -> Result<IVec3, String> {
+        Ok(IVec3::new(self.i32()?, self.i32()?, self.i32()?))
+    }
+
+    #[cfg(feature = "client")]
+    fn vec3(&mut self) -> Result<Vec3, String> {
+        Ok(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn block(&mut self) -> Result<Option<BlockKind>, String> {
+        match self.u8()? {
+            0 => Ok(None),
+            id => BlockKind::iter().nth(id as usize - 1).map(Some).ok_or_else(|| format!("Unknown block id {id}")),
+        }
+    }
+
+    fn input(&mut self) -> Result<MoveInput, String> {
+        let (sequence, dt, walk, jump) = (self.u32()?, self.f32()?, Vec2::new(self.f32()?, self.f32()?), self.bool()?);
+        let (has_lift, lift) = (self.bool()?, self.f32()?);
+        Ok(MoveInput {
+            sequence,
+            dt,
+            walk,
+            jump,
+            lift: has_lift.then_some(lift),
+            flying: self.bool()?,
+            yaw: self.f32()?,
+            pitch: self.f32()?,
+        })
+    }
+
+    fn finish<T>(self, value: T) -> Result<T, String> {
+        if self.bytes.is_empty() {
+            Ok(value)
+        } else {
+            Err(format!("{} unexpected bytes at end of packet", self.bytes.len()))
+        }
+    }
+}
+
+// Sent over TCP by clients.
+#[derive(Debug, Clone, PartialEq)]
+enum ClientPacket {
+    Handshake { protocol: u32 },
+    Login { name: String },
+    SetBlock { pos: IVec3, kind: Option<BlockKind> },
+    Disconnect,
+    // Sent instead of a handshake to ask for `ServerPacket::Status`; works across protocol versions.
+    StatusRequest,
+}
+
+impl ClientPacket {
+    #[cfg(feature = "client")]
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::default();
+        match self {
+            Self::Handshake { protocol } => writer.u8(0).u32(*protocol),
+            Self::Login { name } => writer.u8(1).string(name),
+            Self::SetBlock { pos, kind } => writer.u8(2).ivec3(*pos).block(*kind),
+            Self::Disconnect => writer.u8(3),
+            Self::StatusRequest => writer.u8(4),
+        };
+        writer.0
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = PacketReader::new(bytes);
+        let packet = match reader.u8()? {
+            0 => Self::Handshake { protocol: reader.u32()? },
+            1 => Self::Login { name: reader.string()? },
+            2 => Self::SetBlock { pos: reader.ivec3()?, kind: reader.block()? },
+            3 => Self::Disconnect,
+            4 => Self::StatusRequest,
+            id => return Err(format!("Unknown client packet {id}")),
+        };
+        reader.finish(packet)
+    }
+}
+
+// Sent over TCP by the server.
+#[derive(Debug, Clone, PartialEq)]
+enum ServerPacket {
+    LoginSuccess { player_id: u32, token: u64, spawn: Vec3, time: f64, seed: u32, allow_flight: bool },
+    // One zlib-compressed chunk of block ids, sent while logging in.
+    ChunkData { pos: IVec3, data: Vec<u8> },
+    // Every chunk has been sent.
+    WorldReady,
+    BlockUpdate { pos: IVec3, kind: Option<BlockKind> },
+    PlayerJoined { id: u32, name: String },
+    PlayerLeft { id: u32 },
+    Disconnect { reason: String },
+    Status { protocol: u32, motd: String, online: u32, max_players: u32 },
+}
+
+impl ServerPacket {
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::default();
+        match self {
+            Self::LoginSuccess { player_id, token, spawn, time, seed, allow_flight } => {
+                writer.u8(0).u32(*player_id).u64(*token).vec3(*spawn).f64(*time).u32(*seed).u8(*allow_flight as u8)
+            }
+            Self::ChunkData { pos, data } => writer.u8(1).ivec3(*pos).bytes(data),
+            Self::WorldReady => writer.u8(2),
+            Self::BlockUpdate { pos, kind } => writer.u8(3).ivec3(*pos).block(*kind),
+            Self::PlayerJoined { id, name } => writer.u8(4).u32(*id).string(name),
+            Self::PlayerLeft { id } => writer.u8(5).u32(*id),
+            Self::Disconnect { reason } => writer.u8(6).string(reason),
+            Self::Status { protocol, motd, online, max_players } => writer.u8(7).u32(*protocol).string(motd).u32(*online).u32(*max_players),
+        };
+        writer.0
+    }
+
+    #[cfg(feature = "client")]
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = PacketReader::new(bytes);
+        let packet = match reader.u8()? {
+            0 => Self::LoginSuccess {
+                player_id: reader.u32()?,
+                token: reader.u64()?,
+                spawn: reader.vec3()?,
+                time: reader.f64()?,
+                seed: reader.u32()?,
+                allow_flight: reader.u8()? != 0,
+            },
+            1 => Self::ChunkData { pos: reader.ivec3()?, data: reader.bytes()? },
+            2 => Self::WorldReady,
+            3 => Self::BlockUpdate { pos: reader.ivec3()?, kind: reader.block()? },
+            4 => Self::PlayerJoined { id: reader.u32()?, name: reader.string()? },
+            5 => Self::PlayerLeft { id: reader.u32()? },
+            6 => Self::Disconnect { reason: reader.string()? },
+            7 => Self::Status { protocol: reader.u32()?, motd: reader.string()?, online: reader.u32()?, max_players: reader.u32()? },
+            id => return Err(format!("Unknown server packet {id}")),
+        };
+        reader.finish(packet)
+    }
+}
+
+// Sent over UDP by clients: the most recent inputs, so a lost datagram is covered by the next one.
+#[derive(Debug, Clone, PartialEq)]
+struct InputDatagram {
+    token: u64,
+    inputs: Vec<MoveInput>,
+}
+
+impl InputDatagram {
+    #[cfg(feature = "client")]
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::default();
+        writer.u64(self.token).u8(self.inputs.len() as u8);
+        for input in &self.inputs {
+            writer.input(input);
+        }
+        writer.0
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = PacketReader::new(bytes);
+        let token = reader.u64()?;
+        let count = reader.u8()? as usize;
+        if count > MAX_INPUTS_PER_DATAGRAM {
+            return Err(format!("Too many inputs: {count}"));
+        }
+        let inputs = (0..count).map(|_| reader.input()).collect::<Result<_, _>>()?;
+        reader.finish(Self { token, inputs })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PlayerState {
+    id: u32,
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+}
+
+// Sent over UDP by the server every tick: where it has the player after the acknowledged input, and
+// where everyone else is.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    ack: u32,
+    position: Vec3,
+    velocity: Vec3,
+    players: Vec<PlayerState>,
+}
+
+impl Snapshot {
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = PacketWriter::default();
+        writer.u32(self.ack).vec3(self.position).vec3(self.velocity).u16(self.players.len() as u16);
+        for player in &self.players {
+            writer.u32(player.id).vec3(player.position).f32(player.yaw).f32(player.pitch);
+        }
+        writer.0
+    }
+
+    #[cfg(feature = "client")]
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = PacketReader::new(bytes);
+        let (ack, position, velocity) = (reader.u32()?, reader.vec3()?, reader.vec3()?);
+        let count = reader.u16()? as usize;
+        let players = (0..count)
+            .map(|_| Ok(PlayerState { id: reader.u32()?, position: reader.vec3()?, yaw: reader.f32()?, pitch: reader.f32()? }))
+            .collect::<Result<_, String>>()?;
+        reader.finish(Self { ack, position, velocity, players })
+    }
+}
+
+fn encode_chunk(chunk: &Chunk) -> Vec<u8> {
+    let mut writer = PacketWriter::default();
+    for kind in &chunk.blocks {
+        writer.block(*kind);
+    }
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, &writer.0).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(feature = "client")]
+fn decode_chunk(data: &[u8]) -> Result<Vec<Option<BlockKind>>, String> {
+    let mut ids = Vec::new();
+    flate2::read::ZlibDecoder::new(data)
+        .take(CHUNK_VOLUME as u64 + 1)
+        .read_to_end(&mut ids)
+        .map_err(|err| format!("Bad chunk data: {err}"))?;
+    if ids.len() != CHUNK_VOLUME {
+        return Err(format!("Chunk has {} blocks, expected {CHUNK_VOLUME}", ids.len()));
+    }
+    let mut reader = PacketReader::new(&ids);
+    (0..CHUNK_VOLUME).map(|_| reader.block()).collect()
+}
+
+// Length-prefixed packets over a non-blocking TCP stream.
+struct PacketStream {
+    stream: TcpStream,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    // The peer hung up; packets already buffered are still handed out.
+    closed: bool,
+}
+
+impl PacketStream {
+    fn new(stream: TcpStream) -> std::io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self { stream, incoming: Vec::new(), outgoing: Vec::new(), closed: false })
+    }
+
+    fn send(&mut self, packet: &[u8]) {
+        self.outgoing.extend_from_slice(&(packet.len() as u32).to_be_bytes());
+        self.outgoing.extend_from_slice(packet);
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err("Connection closed".to_string()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+        if self.outgoing.len() > MAX_OUTGOING_BYTES {
+            return Err("Connection is not keeping up".to_string());
+        }
+        Ok(())
+    }
+
+    // Reads whatever has arrived without blocking.
+    fn fill(&mut self) -> Result<(), String> {
+        let mut buffer = [0; 8192];
+        while !self.closed {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => self.closed = true,
+                Ok(read) => self.incoming.extend_from_slice(&buffer[..read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.to_string()),
+            }
+            if self.incoming.len() > MAX_PACKET_SIZE + 4 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<Option<Vec<u8>>, String> {
+        self.fill()?;
+        if self.incoming.len() >= 4 {
+            let len = u32::from_be_bytes(self.incoming[..4].try_into().unwrap()) as usize;
+            if len > MAX_PACKET_SIZE {
+                return Err(format!("Packet too large: {len} bytes"));
+            }
+            if self.incoming.len() >= 4 + len {
+                let packet = self.incoming[4..4 + len].to_vec();
+                self.incoming.drain(..4 + len);
+                return Ok(Some(packet));
+            }
+        }
+        if self.closed {
+            return Err("Connection closed".to_string());
+        }
+        Ok(None)
+    }
+}
+
+// Reads fields out of one vanilla packet body.
+struct JavaReader<'a>(&'a [u8]);
+
+impl JavaReader<'_> {
+    fn varint(&mut self) -> Result<i32, String> {
+        let (value, len) = peek_varint(self.0)?.ok_or("Packet ended early")?;
+        self.0 = &self.0[len..];
+        Ok(value as i32)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        if self.0.len() < len {
+            return Err("Packet ended early".to_string());
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn string(&mut self, max_len: usize) -> Result<String, String> {
+        let len = usize::try_from(self.varint()?).map_err(|_| "Negative string length")?;
+        if len > max_len * 4 {
+            return Err(format!("String too long: {len} bytes"));
+        }
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "Invalid UTF-8 in string".to_string())
+    }
+}
+
+// A length-prefixed vanilla packet: VarInt length, then the VarInt id and body.
+fn java_packet(id: i32, body: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_varint(&mut payload, id as u32);
+    payload.extend_from_slice(body);
+    let mut packet = Vec::new();
+    write_varint(&mut packet, payload.len() as u32);
+    packet.extend_from_slice(&payload);
+    packet
+}
+
+fn java_string(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_varint(&mut bytes, text.len() as u32);
+    bytes.extend_from_slice(text.as_bytes());
+    bytes
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JavaStage {
+    Handshake,
+    // Remembers the client's protocol to echo back, so it lists us rather than showing "incompatible".
+    Status { protocol: i32 },
+    // Sent the status; only a ping may follow, as on a vanilla server.
+    Ping,
+    // Answered; the client hangs up next.
+    Done,
+}
+
+struct JavaServerInfo {
+    motd: String,
+    online: u32,
+    max_players: u32,
+    // Names for the hover list in the server browser.
+    sample: Vec<String>,
+}
+
+impl JavaServerInfo {
+    fn status_json(&self, protocol: i32) -> String {
+        let sample: Vec<_> = self
+            .sample
+            .iter()
+            .enumerate()
+            .map(|(index, name)| serde_json::json!({ "name": name, "id": format!("00000000-0000-0000-0000-{:012x}", index) }))
+            .collect();
+        serde_json::json!({
+            "version": { "name": format!("Minceraft {}", built_info::BUILD_VERSION), "protocol": protocol },
+            "players": { "max": self.max_players, "online": self.online, "sample": sample },
+            "description": { "text": self.motd },
+        })
+        .to_string()
+    }
+
+    // The pre-1.7 kick packet: 0xFF, then a UTF-16 string of NUL-separated fields after a `§1` marker.
+    fn legacy_status(&self) -> Vec<u8> {
+        let version = format!("Minceraft {}", built_info::BUILD_VERSION);
+        let fields = ["\u{a7}1", "127", &version, &self.motd, &self.online.to_string(), &self.max_players.to_string()];
+        let text: Vec<u16> = fields.join("\0").encode_utf16().collect();
+        let mut packet = vec![0xff];
+        packet.extend_from_slice(&(text.len() as u16).to_be_bytes());
+        packet.extend(text.iter().flat_map(|unit| unit.to_be_bytes()));
+        packet
+    }
+}
+
+// Runs the server list ping for a vanilla client: handshake, status request, then ping. Consumes whole
+// packets from `incoming` and returns the bytes to send back; anything else is an error.
+fn answer_java_ping(incoming: &mut Vec<u8>, stage: &mut JavaStage, info: &JavaServerInfo) -> Result<Vec<u8>, String> {
+    // Clients from before 1.7 open with 0xFE instead of a handshake, and newer ones fall back to it.
+    if *stage == JavaStage::Handshake && incoming.first() == Some(&0xfe) {
+        incoming.clear();
+        *stage = JavaStage::Done;
+        return Ok(info.legacy_status());
+    }
+    let mut replies = Vec::new();
+    while let Some((len, prefix)) = peek_varint(incoming)? {
+        let len = len as usize;
+        if len > JAVA_MAX_PACKET_SIZE {
+            return Err(format!("Packet too large: {len} bytes"));
+        }
+        if incoming.len() < prefix + len {
+            break;
+        }
+        let packet: Vec<u8> = incoming.drain(..prefix + len).skip(prefix).collect();
+        let mut reader = JavaReader(&packet);
+        match (*stage, reader.varint()?) {
+            (JavaStage::Handshake, 0x00) => {
+                let protocol = reader.varint()?;
+                reader.string(255)?;
+                reader.take(2)?;
+                match reader.varint()? {
+                    1 => *stage = JavaStage::Status { protocol },
+                    // Login or transfer: say why they can't join, rather than just hanging up.
+                    2 | 3 => {
+                        let reason = serde_json::json!({ "text": "This is a Minceraft server; join it with the Minceraft client." });
+                        replies.extend(java_packet(0x00, &java_string(&reason.to_string())));
+                        *stage = JavaStage::Done;
+                    }
+                    next => return Err(format!("Unknown next state {next}")),
+                }
+            }
+            (JavaStage::Status { protocol }, 0x00) => {
+                replies.extend(java_packet(0x00, &java_string(&info.status_json(protocol))));
+                *stage = JavaStage::Ping;
+            }
+            (JavaStage::Status { .. } | JavaStage::Ping, 0x01) => {
+                replies.extend(java_packet(0x01, reader.take(8)?));
+                *stage = JavaStage::Done;
+            }
+            (stage, id) => return Err(format!("Unexpected packet {id:#04x} during {stage:?}")),
+        }
+        if *stage == JavaStage::Done {
+            break;
+        }
+    }
+    Ok(replies)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionStage {
+    Handshake,
+    // Answered a status request; the client hangs up next.
+    Status,
+    Login,
+    Play,
+    // A vanilla Minecraft client pinging for the server list.
+    Java(JavaStage),
+}
+
+struct Connection {
+    stream: PacketStream,
+    address: SocketAddr,
+    stage: ConnectionStage,
+    player_id: u32,
+    name: String,
+    // Proves a datagram came from this player; UDP has no connection to tie it to.
+    token: u64,
+    udp_address: Option<SocketAddr>,
+    position: Vec3,
+    velocity: Vec3,
+    yaw: f32,
+    pitch: f32,
+    last_input: u32,
+    // Seconds of movement the player may still simulate. It refills with wall-clock time, so sending
+    // more inputs doesn't move anyone faster.
+    move_allowance: f32,
+    allowance_updated: Instant,
+    connected_at: Instant,
+}
+
+// Accepts players and keeps them in sync with the server's world. Logins, chunks and block changes go over
+// TCP; movement goes over a UDP socket on the same port.
+#[derive(Resource)]
+struct NetworkServer {
+    listener: TcpListener,
+    socket: UdpSocket,
+    connections: Vec<Connection>,
+    next_id: u32,
+    max_players: u32,
+    motd: String,
+    // Whether players may fly. Without it the server drops flight from their inputs.
+    allow_flight: bool,
+    login_timeout: Duration,
+    // The player whose game is hosting, when opened to LAN; they play directly on the server's world.
+    host: Option<(String, PlayerState)>,
+    // Joins, leaves and dropped connections, for the console or chat.
+    messages: Vec<String>,
+}
+
+impl NetworkServer {
+    fn bind(address: SocketAddr, max_players: u32, motd: String) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let socket = UdpSocket::bind(listener.local_addr()?)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            socket,
+            connections: Vec::new(),
+            next_id: HOST_PLAYER_ID + 1,
+            max_players,
+            motd,
+            allow_flight: false,
+            login_timeout: LOGIN_TIMEOUT,
+            host: None,
+            messages: Vec::new(),
+        })
+    }
+
+    fn online(&self) -> usize {
+        self.players().count() + self.host.is_some() as usize
+    }
+
+    fn local_addr(&self) -> SocketAddr {
+        self.listener.local_addr().unwrap()
+    }
+
+    fn players(&self) -> impl Iterator<Item = &Connection> {
+        self.connections.iter().filter(|connection| connection.stage == ConnectionStage::Play)
+    }
+
+    fn broadcast(&mut self, packet: &ServerPacket) {
+        let bytes = packet.encode();
+        for connection in self.connections.iter_mut().filter(|connection| connection.stage == ConnectionStage::Play) {
+            connection.stream.send(&bytes);
+        }
+    }
+
+    fn update(&mut self, world: &mut WorldBlocks, registry: &BlockRegistry, time: &WorldTime, seed: WorldSeed) {
+        while let Ok((stream, address)) = self.listener.accept() {
+            match PacketStream::new(stream) {
+                Ok(stream) => self.connections.push(Connection {
+                    stream,
+                    address,
+                    stage: ConnectionStage::Handshake,
+                    player_id: 0,
+                    name: String::new(),
+                    token: 0,
+                    udp_address: None,
+                    position: Vec3::ZERO,
+                    velocity: Vec3::ZERO,
+                    yaw: 0.0,
+                    pitch: 0.0,
+                    last_input: 0,
+                    move_allowance: MAX_MOVE_ALLOWANCE,
+                    allowance_updated: Instant::now(),
+                    connected_at: Instant::now(),
+                }),
+                Err(err) => eprintln!("Could not accept {address}: {err}"),
+            }
+        }
+
+        self.receive_inputs(world, registry);
+
+        let mut index = 0;
+        while index < self.connections.len() {
+            let connection = &self.connections[index];
+            if connection.stage != ConnectionStage::Play && connection.connected_at.elapsed() > self.login_timeout {
+                self.disconnect(index, "Took too long to log in".to_string());
+                continue;
+            }
+            let result = if self.is_java(index) {
+                self.answer_java(index)
+            } else {
+                loop {
+                    match self.connections[index].stream.receive() {
+                        Ok(Some(bytes)) => {
+                            if let Err(reason) = ClientPacket::decode(&bytes).and_then(|packet| self.handle(index, packet, world, registry, time, seed)) {
+                                break Err(reason);
+                            }
+                        }
+                        Ok(None) => break self.connections[index].stream.flush(),
+                        Err(reason) => break Err(reason),
+                    }
+                }
+            };
+            match result {
+                Ok(()) => index += 1,
+                Err(reason) => self.disconnect(index, reason),
+            }
+        }
+    }
+
+    fn handle(
+        &mut self,
+        index: usize,
+        packet: ClientPacket,
+        world: &mut WorldBlocks,
+        registry: &BlockRegistry,
+        time: &WorldTime,
+        seed: WorldSeed,
+    ) -> Result<(), String> {
+        let stage = self.connections[index].stage;
+        match (stage, packet) {
+            (ConnectionStage::Handshake, ClientPacket::StatusRequest) => {
+                let status = ServerPacket::Status {
+                    protocol: PROTOCOL_VERSION,
+                    motd: self.motd.clone(),
+                    online: self.online() as u32,
+                    max_players: self.max_players,
+                };
+                let connection = &mut self.connections[index];
+                connection.stream.send(&status.encode());
+                connection.stage = ConnectionStage::Status;
+            }
+            (ConnectionStage::Handshake, ClientPacket::Handshake { protocol }) => {
+                if protocol != PROTOCOL_VERSION {
+                    return Err(format!("Incompatible version: server uses protocol {PROTOCOL_VERSION}, client uses {protocol}"));
+                }
+                self.connections[index].stage = ConnectionStage::Login;
+            }
+            (ConnectionStage::Login, ClientPacket::Login { name }) => {
+                if name.is_empty() || name.len() > MAX_NAME_LENGTH || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!("Invalid name: {name}"));
+                }
+                let host_name = self.host.as_ref().map(|(host_name, _)| host_name.as_str());
+                if self.players().map(|player| player.name.as_str()).chain(host_name).any(|other| other.eq_ignore_ascii_case(&name)) {
+                    return Err(format!("{name} is already playing"));
+                }
+                if self.online() >= self.max_players as usize {
+                    return Err("Server is full".to_string());
+                }
+                self.login(index, name, world, registry, time, seed);
+            }
+            (ConnectionStage::Play, ClientPacket::SetBlock { pos, kind }) => {
+                let connection = &self.connections[index];
+                let allowed = connection.position.distance(pos.as_vec3()) <= PLAYER_REACH + 1.0
+                    && (WORLD_MIN_Y..=WORLD_MAX_Y).contains(&pos.y)
+                    && match kind {
+                        Some(kind) => {
+                            let min_dist = (Vec3::new(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH) + Vec3::ONE) * 0.5;
+                            let host = self.host.as_ref().map(|(_, host)| host.position);
+                            world.is_replaceable(registry, pos)
+                                && (!registry.get(kind).solid
+                                    || self
+                                        .players()
+                                        .map(|player| player.position)
+                                        .chain(host)
+                                        .all(|position| !(pos.as_vec3() - position).abs().cmplt(min_dist).all()))
+                        }
+                        None => world.is_targetable(registry, pos),
+                    };
+                if allowed {
+                    world.set_block(registry, pos, kind);
+                    self.broadcast(&ServerPacket::BlockUpdate { pos, kind });
+                } else {
+                    // Undo the client's prediction.
+                    let packet = ServerPacket::BlockUpdate { pos, kind: world.get(pos) };
+                    self.connections[index].stream.send(&packet.encode());
+                }
+            }
+            (_, ClientPacket::Disconnect) => return Err("Left the game".to_string()),
+            (stage, packet) => return Err(format!("Unexpected {packet:?} during {stage:?}")),
+        }
+        Ok(())
+    }
+
+    fn login(&mut self, index: usize, name: String, world: &WorldBlocks, registry: &BlockRegistry, time: &WorldTime, seed: WorldSeed) {
+        let player_id = self.next_id;
+        self.next_id += 1;
+        let spawn = spawn_point(world, registry);
+        let host = self.host.as_ref().map(|(name, host)| ServerPacket::PlayerJoined { id: host.id, name: name.clone() });
+        let others: Vec<ServerPacket> = self
+            .players()
+            .map(|player| ServerPacket::PlayerJoined { id: player.player_id, name: player.name.clone() })
+            .chain(host)
+            .collect();
+        self.broadcast(&ServerPacket::PlayerJoined { id: player_id, name: name.clone() });
+
+        let connection = &mut self.connections[index];
+        connection.token = random();
+        connection.stream.send(
+            &ServerPacket::LoginSuccess {
+                player_id,
+                token: connection.token,
+                spawn,
+                time: time.ticks,
+                seed: seed.0,
+                allow_flight: self.allow_flight,
+            }
+            .encode(),
+        );
+        let mut chunks: Vec<_> = world.chunks.iter().filter(|(_, chunk)| chunk.blocks.iter().any(Option::is_some)).collect();
+        chunks.sort_by_key(|(pos, _)| (pos.x, pos.z, pos.y));
+        for (pos, chunk) in chunks {
+            connection.stream.send(&ServerPacket::ChunkData { pos: *pos, data: encode_chunk(chunk) }.encode());
+        }
+        connection.stream.send(&ServerPacket::WorldReady.encode());
+        for packet in others {
+            connection.stream.send(&packet.encode());
+        }
+        self.messages.push(format!("{name} joined the game from {}", connection.address));
+        let connection = &mut self.connections[index];
+        (connection.stage, connection.player_id, connection.name, connection.position) = (ConnectionStage::Play, player_id, name, spawn);
+    }
+
+    // Looks at the first byte of a new connection to tell vanilla clients from ours.
+    fn is_java(&mut self, index: usize) -> bool {
+        let connection = &mut self.connections[index];
+        match connection.stage {
+            ConnectionStage::Java(_) => true,
+            ConnectionStage::Handshake => {
+                let _ = connection.stream.fill();
+                connection.stream.incoming.first().is_some_and(|byte| *byte != 0)
+            }
+            _ => false,
+        }
+    }
+
+    fn answer_java(&mut self, index: usize) -> Result<(), String> {
+        let host = self.host.as_ref().map(|(name, _)| name.clone());
+        let info = JavaServerInfo {
+            motd: self.motd.clone(),
+            online: self.online() as u32,
+            max_players: self.max_players,
+            sample: self.players().map(|player| player.name.clone()).chain(host).take(JAVA_PLAYER_SAMPLE).collect(),
+        };
+        let connection = &mut self.connections[index];
+        connection.stream.fill()?;
+        let mut stage = match connection.stage {
+            ConnectionStage::Java(stage) => stage,
+            _ => JavaStage::Handshake,
+        };
+        let replies = answer_java_ping(&mut connection.stream.incoming, &mut stage, &info)?;
+        connection.stage = ConnectionStage::Java(stage);
+        connection.stream.outgoing.extend_from_slice(&replies);
+        connection.stream.flush()?;
+        if connection.stream.closed || (stage == JavaStage::Done && connection.stream.outgoing.is_empty()) {
+            return Err("Answered server list ping".to_string());
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self, index: usize, reason: String) {
+        let mut connection = self.connections.remove(index);
+        // Vanilla clients wouldn't understand our packets.
+        if !matches!(connection.stage, ConnectionStage::Java(_)) {
+            connection.stream.send(&ServerPacket::Disconnect { reason: reason.clone() }.encode());
+        }
+        let _ = connection.stream.flush();
+        match connection.stage {
+            ConnectionStage::Play => {
+                self.messages.push(format!("{} left the game: {reason}", connection.name));
+                self.broadcast(&ServerPacket::PlayerLeft { id: connection.player_id });
+            }
+            ConnectionStage::Handshake | ConnectionStage::Login => {
+                self.messages.push(format!("Dropped {} while connecting: {reason}", connection.address));
+            }
+            ConnectionStage::Status | ConnectionStage::Java(_) => {}
+        }
+    }
+
+    fn receive_inputs(&mut self, world: &WorldBlocks, registry: &BlockRegistry) {
+        let mut buffer = [0; 2048];
+        while let Ok((len, address)) = self.socket.recv_from(&mut buffer) {
+            let Ok(datagram) = InputDatagram::decode(&buffer[..len]) else {
+                continue;
+            };
+            let Some(connection) = self
+                .connections
+                .iter_mut()
+                .find(|connection| connection.stage == ConnectionStage::Play && connection.token == datagram.token)
+            else {
+                continue;
+            };
+            connection.udp_address = Some(address);
+            let now = Instant::now();
+            let elapsed = now.duration_since(connection.allowance_updated).as_secs_f32();
+            connection.move_allowance = (connection.move_allowance + elapsed).min(MAX_MOVE_ALLOWANCE);
+            connection.allowance_updated = now;
+            for input in datagram.inputs {
+                if !sequence_after(input.sequence, connection.last_input) {
+                    continue;
+                }
+                let input = input.sanitized(self.allow_flight);
+                // Left unacknowledged, so an honest client resends it once the allowance has refilled.
+                if input.dt > connection.move_allowance {
+                    break;
+                }
+                connection.move_allowance -= input.dt;
+                connection.position = apply_move_input(world, registry, connection.position, &mut connection.velocity, &input);
+                (connection.yaw, connection.pitch, connection.last_input) = (input.yaw, input.pitch, input.sequence);
+            }
+        }
+    }
+
+    fn send_snapshots(&mut self) {
+        let players: Vec<PlayerState> = self
+            .players()
+            .map(|player| PlayerState { id: player.player_id, position: player.position, yaw: player.yaw, pitch: player.pitch })
+            .chain(self.host.as_ref().map(|(_, host)| *host))
+            .collect();
+        for connection in self.connections.iter().filter(|connection| connection.stage == ConnectionStage::Play) {
+            let Some(address) = connection.udp_address else {
+                continue;
+            };
+            let snapshot = Snapshot {
+                ack: connection.last_input,
+                position: connection.position,
+                velocity: connection.velocity,
+                players: players.iter().copied().filter(|player| player.id != connection.player_id).collect(),
+            };
+            let _ = self.socket.send_to(&snapshot.encode(), address);
+        }
+    }
+}
+
+// Just above the highest solid block at the world's centre.
+fn spawn_point(world: &WorldBlocks, registry: &BlockRegistry) -> Vec3 {
+    let ground = (WORLD_MIN_Y..=WORLD_MAX_Y).rev().find(|&y| world.is_solid(registry, IVec3::new(0, y, 0)));
+    Vec3::new(0.0, ground.unwrap_or(WORLD_MAX_Y) as f32 + 0.5 + PLAYER_HEIGHT * 0.5, 0.0)
+}
+
+fn serve_network(
+    mut server: ResMut<NetworkServer>,
+    mut world: ResMut<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    world_time: Res<WorldTime>,
+    world_seed: Res<WorldSeed>,
+) {
+    server.update(&mut world, &registry, &world_time, *world_seed);
+    for message in server.messages.drain(..) {
+        println!("{message}");
+    }
+}
+
+fn send_snapshots(mut server: ResMut<NetworkServer>) {
+    server.send_snapshots();
+}
+
+fn close_server_on_exit(mut exit_events: EventReader<AppExit>, server: Option<ResMut<NetworkServer>>) {
+    if let (Some(mut server), Some(_)) = (server, exit_events.read().next()) {
+        while !server.connections.is_empty() {
+            server.disconnect(0, "Server closed".to_string());
+        }
+    }
+}
+
+// Moves a player by one frame of input, colliding with the world. The client predicts with this and the
+// server replays the same inputs, so both arrive at the same place.
+fn apply_move_input(world: &WorldBlocks, registry: &BlockRegistry, position: Vec3, velocity: &mut Vec3, input: &MoveInput) -> Vec3 {
+    velocity.x = input.walk.x;
+    velocity.z = input.walk.y;
+    match input.lift {
+        Some(lift) => velocity.y = lift,
+        None if input.jump && velocity.y.abs() < 0.1 => velocity.y = PLAYER_JUMP_FORCE,
+        None => {}
+    }
+    if !input.flying {
+        velocity.y -= GRAVITY * input.dt;
+    }
+
+    let mut new_pos = position + *velocity * input.dt;
+
+    let center = new_pos.round().as_ivec3();
+    let nearby = (-1..=1).flat_map(|x| (-2..=2).flat_map(move |y| (-1..=1).map(move |z| IVec3::new(x, y, z))));
+    for offset in nearby {
+        if !world.is_solid(registry, center + offset) {
+            continue;
+        }
+        let block_pos = (center + offset).as_vec3();
+        let diff = new_pos - block_pos;
+
+        let player_size = Vec3::new(PLAYER_WIDTH, PLAYER_HEIGHT, PLAYER_WIDTH);
+        let block_size = Vec3::new(1.0, 1.0, 1.0);
+        
+        let min_dist = (player_size + block_size) * 0.5;
+        
+        if diff.x.abs() < min_dist.x && diff.y.abs() < min_dist.y && diff.z.abs() < min_dist.z {
+            if diff.y.abs() > diff.x.abs() && diff.y.abs() > diff.z.abs() {
+                if diff.y > 0.0 {
+                    new_pos.y = block_pos.y + min_dist.y;
+                    velocity.y = 0.0;
+                } else {
+                    new_pos.y = block_pos.y - min_dist.y;
+                    velocity.y = 0.0;
+                }
+            }
+            else if diff.x.abs() > diff.z.abs() {
+                new_pos.x = block_pos.x + min_dist.x * diff.x.signum();
+                velocity.x = 0.0;
+            } else {
+                new_pos.z = block_pos.z + min_dist.z * diff.z.signum();
+                velocity.z = 0.0;
+            }
+        }
+    }
+
+    new_pos
+}
+
+fn column_hash(seed: u32, x: i32, z: i32) -> u32 {
+    let mut hash = seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (z as u32).wrapping_mul(0x1656_67b1);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2c1b_3c6d);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297a_2d39);
+    hash ^ (hash >> 15)
+}
+
+fn generate_column(perlin: &Perlin, x: i32, z: i32) -> Vec<(IVec3, BlockKind)> {
+    let px = x as f64 * 0.1;
+    let pz = z as f64 * 0.1;
+    let height = (perlin.get([px, pz]) * 5.0).max(-4.0) as i32;
+
+    let mut blocks = Vec::new();
+    for y in -5..=height {
+        let ore = column_hash(perlin.seed().wrapping_add(y as u32), x, z);
+        let kind = if y == height && height >= SEA_LEVEL {
+            BlockKind::Grass
+        } else if y > height - 3 {
+            BlockKind::Dirt
+        } else if y == -5 && ore.is_multiple_of(LAVA_CHANCE) {
+            BlockKind::Lava
+        } else if ore.is_multiple_of(COAL_CHANCE) {
+            BlockKind::CoalOre
+        } else {
+            BlockKind::Stone
+        };
+        blocks.push((IVec3::new(x, y, z), kind));
+    }
+    for y in height + 1..=SEA_LEVEL {
+        blocks.push((IVec3::new(x, y, z), BlockKind::Water));
+    }
+
+    if height >= SEA_LEVEL && column_hash(perlin.seed(), x, z).is_multiple_of(TREE_CHANCE) {
+        let top = height + TREE_HEIGHT;
+        for dy in -1..=1 {
+            let radius = if dy == 1 { 1 } else { 2 };
+            for dx in -radius..=radius {
+                for dz in -radius..=radius {
+                    if dy < 1 && dx == 0 && dz == 0 {
+                        continue;
+                    }
+                    blocks.push((IVec3::new(x + dx, top + dy, z + dz), BlockKind::Leaves));
+                }
+            }
+        }
+        for y in height + 1..=top {
+            blocks.push((IVec3::new(x, y, z), BlockKind::Log));
+        }
+    }
+
+    blocks
+}
+
+fn spread_level(channel: LightChannel, level: u8, direction: IVec3) -> u8 {
+    // Full skylight travels straight down without fading.
+    if channel == LightChannel::Sky && direction == IVec3::NEG_Y && level == MAX_LIGHT {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(1)
+    }
+}
+
+fn propagate_light(grid: &mut impl LightGrid, channel: LightChannel, mut queue: VecDeque<IVec3>) {
+    while let Some(pos) = queue.pop_front() {
+        let level = grid.light(pos, channel);
+        for direction in NEIGHBOURS {
+            let next = pos + direction;
+            let spread = spread_level(channel, level, direction);
+            if spread == 0 || !grid.contains(next) || grid.is_opaque(next) {
+                continue;
+            }
+            if grid.light(next, channel) < spread {
+                grid.set_light(next, channel, spread);
+                queue.push_back(next);
+            }
+        }
+    }
+}
+
+// Darkens everything that was lit through `pos` and returns the brighter cells bordering the
+// cleared region, which need to spread their light back in.
+fn remove_light(grid: &mut impl LightGrid, channel: LightChannel, pos: IVec3) -> VecDeque<IVec3> {
+    let mut removal = VecDeque::from([(pos, grid.light(pos, channel))]);
+    let mut relight = VecDeque::new();
+    grid.set_light(pos, channel, 0);
+
+    while let Some((pos, level)) = removal.pop_front() {
+        for direction in NEIGHBOURS {
+            let next = pos + direction;
+            if !grid.contains(next) {
+                continue;
+            }
+            let neighbour = grid.light(next, channel);
+            if neighbour == 0 {
+                continue;
+            }
+            let lit_from_here = neighbour < level
+                || (channel == LightChannel::Sky && direction == IVec3::NEG_Y && level == MAX_LIGHT);
+            if lit_from_here {
+                grid.set_light(next, channel, 0);
+                removal.push_back((next, neighbour));
+            } else {
+                relight.push_back(next);
+            }
+        }
+    }
+
+    relight
+}
+
+// Recomputes light around `pos` after the block there changed; `emission` is the new block's.
+fn update_light(grid: &mut impl LightGrid, pos: IVec3, emission: u8) {
+    for channel in [LightChannel::Sky, LightChannel::Block] {
+        let mut relight = remove_light(grid, channel, pos);
+        if !grid.is_opaque(pos) {
+            relight.extend(NEIGHBOURS.iter().map(|direction| pos + *direction).filter(|next| {
+                grid.contains(*next) && grid.light(*next, channel) > 0
+            }));
+            if channel == LightChannel::Sky && !grid.contains(pos + IVec3::Y) {
+                grid.set_light(pos, channel, MAX_LIGHT);
+                relight.push_back(pos);
+            }
+        }
+        if channel == LightChannel::Block && emission > 0 {
+            grid.set_light(pos, channel, emission);
+            relight.push_back(pos);
+        }
+        propagate_light(grid, channel, relight);
+    }
+}
+
+fn initialize_light(world: &mut WorldBlocks, registry: &BlockRegistry) {
+    let mut sky = VecDeque::new();
+    let mut block = VecDeque::new();
+    let top_chunk = WORLD_MAX_Y.div_euclid(CHUNK_SIZE);
+
+    for (chunk_pos, chunk) in world.chunks.iter_mut() {
+        chunk.sky_light.fill(0);
+        chunk.block_light.fill(0);
+        for (index, kind) in chunk.blocks.iter().enumerate() {
+            let Some(kind) = kind else {
+                continue;
+            };
+            let emission = registry.get(*kind).emission;
+            if emission > 0 {
+                chunk.block_light[index] = emission;
+                block.push_back(chunk_origin(*chunk_pos) + chunk_local(index));
+            }
+        }
+
+        if chunk_pos.y == top_chunk {
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let local = IVec3::new(x, WORLD_MAX_Y.rem_euclid(CHUNK_SIZE), z);
+                    let index = chunk_position(local).1;
+                    if chunk.blocks[index].is_none_or(|kind| !registry.get(kind).opaque) {
+                        chunk.sky_light[index] = MAX_LIGHT;
+                        sky.push_back(chunk_origin(*chunk_pos) + local);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut grid = WorldLight { world, registry };
+    propagate_light(&mut grid, LightChannel::Sky, sky);
+    propagate_light(&mut grid, LightChannel::Block, block);
+}
+
+fn chunk_origin(chunk: IVec3) -> IVec3 {
+    chunk * CHUNK_SIZE
+}
+
+fn chunk_local(index: usize) -> IVec3 {
+    let index = index as i32;
+    IVec3::new(index % CHUNK_SIZE, index / (CHUNK_SIZE * CHUNK_SIZE), (index / CHUNK_SIZE) % CHUNK_SIZE)
+}
+
+fn is_solid_at(world: &WorldBlocks, registry: &BlockRegistry, position: Vec3) -> bool {
+    world.is_solid(registry, (position + Vec3::splat(0.5)).floor().as_ivec3())
+}
+
+fn dropped_item_physics(
+    time: Res<Time>,
+    world: Res<WorldBlocks>,
+    registry: Res<BlockRegistry>,
+    mut items: Query<(&mut Transform, &mut Velocity, &Gravity), With<DroppedItem>>,
+) {
+    let dt = time.delta_seconds();
+    let half = ITEM_SIZE * 0.5;
+
+    for (mut transform, mut velocity, gravity) in items.iter_mut() {
+        velocity.0.y -= gravity.0 * dt;
+        let mut position = transform.translation;
+
+        for axis in 0..3 {
+            let mut next = position;
+            next[axis] += velocity.0[axis] * dt;
+            let mut edge = next;
+            edge[axis] += half * velocity.0[axis].signum();
+            if is_solid_at(&world, &registry, edge) {
+                if axis == 1 && velocity.0.y < 0.0 {
+                    next.y = (edge.y + 0.5).floor() + 0.5 + half;
+                } else {
+                    next[axis] = position[axis];
+                }
+                velocity.0[axis] = 0.0;
+            }
+            position = next;
+        }
+
+        let grounded = is_solid_at(&world, &registry, position - Vec3::Y * (half + 0.01));
+        if grounded {
+            let friction = (1.0 - 8.0 * dt).max(0.0);
+            velocity.0.x *= friction;
+            velocity.0.z *= friction;
+        }
+
+        transform.translation = position;
+    }
+}
+
+fn merge_dropped_items(
+    mut commands: Commands,
+    mut items: Query<(Entity, &Transform, &mut DroppedItem)>,
+) {
+    let mut merged = Vec::new();
+    let mut combinations = items.iter_combinations_mut();
+    while let Some([(entity_a, transform_a, mut item_a), (entity_b, transform_b, item_b)]) = combinations.fetch_next() {
+        if merged.contains(&entity_a) || merged.contains(&entity_b) {
+            continue;
+        }
+        if item_a.stack.item != item_b.stack.item
+            || item_a.stack.damage != item_b.stack.damage
+            || item_a.stack.count + item_b.stack.count > item_a.stack.item.max_stack()
+            || transform_a.translation.distance(transform_b.translation) > ITEM_MERGE_RADIUS
+        {
+            continue;
+        }
+        item_a.stack.count += item_b.stack.count;
+        item_a.age = item_a.age.min(item_b.age);
+        merged.push(entity_b);
+        commands.entity(entity_b).despawn_recursive();
+    }
+}
+
+fn despawn_old_items(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<SimulationSettings>,
+    mut items: Query<(Entity, &mut DroppedItem)>,
+) {
+    for (entity, mut item) in items.iter_mut() {
+        item.age += time.delta_seconds();
+        if item.age > settings.item_lifetime {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn advance_time(mut world_time: ResMut<WorldTime>, settings: Res<SimulationSettings>) {
+    world_time.ticks += TICKS_PER_DAY / (settings.day_length as f64 * TICKS_PER_SECOND);
+}
+
+// Rain can turn into a storm or clear up; storms always calm to rain first. `roll` is uniform in [0, 1).
+fn next_weather(current: WeatherKind, roll: f32) -> WeatherKind {
+    match current {
+        WeatherKind::Clear if roll < 0.2 => WeatherKind::Thunder,
+        WeatherKind::Clear => WeatherKind::Rain,
+        WeatherKind::Rain if roll < 0.3 => WeatherKind::Thunder,
+        WeatherKind::Rain => WeatherKind::Clear,
+        WeatherKind::Thunder => WeatherKind::Rain,
+    }
+}
+
+fn weather_duration(kind: WeatherKind, roll: f32) -> f64 {
+    let (min, max) = match kind {
+        WeatherKind::Clear => (12000.0, 96000.0),
+        WeatherKind::Rain => (6000.0, 18000.0),
+        WeatherKind::Thunder => (3600.0, 12000.0),
+    };
+    min + (max - min) * roll as f64
+}
+
+fn advance_weather(mut weather: ResMut<Weather>) {
+    weather.remaining -= 1.0;
+    if weather.remaining <= 0.0 {
+        weather.kind = next_weather(weather.kind, random());
+        weather.remaining = weather_duration(weather.kind, random());
+    }
+}
+
+// Minecraft's VarInt, used by schematic block data and the Java server list ping: seven bits per
+// byte, least significant first, with the high bit set while more follow.
+fn write_varint(bytes: &mut Vec<u8>, value: u32) {
+    let mut value = value;
+    loop {
+        if value < 0x80 {
+            bytes.push(value as u8);
+            return;
+        }
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+// Returns the value and its length in bytes, or None if `bytes` stops partway through, which a
+// socket read may well do.
+fn peek_varint(bytes: &[u8]) -> Result<Option<(u32, usize)>, String> {
+    let mut value = 0u32;
+    for (index, byte) in bytes.iter().take(5).enumerate() {
+        value |= ((byte & 0x7f) as u32) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok(Some((value, index + 1)));
+        }
+    }
+    if bytes.len() >= 5 {
+        return Err("VarInt is too long".to_string());
+    }
+    Ok(None)
+}
+
+fn regenerate_world(world: &mut WorldBlocks, registry: &BlockRegistry, seed: u32) {
+    world.clear();
+    let perlin = Perlin::new(seed);
+    let size = WORLD_SIZE / 2;
+    for x in -size..size {
+        for z in -size..size {
+            for (pos, kind) in generate_column(&perlin, x, z) {
+                world.set_raw(pos, Some(kind));
+            }
+        }
+    }
+    initialize_light(world, registry);
+}
+
+fn save_level(path: &Path, world_time: &WorldTime, world_seed: &WorldSeed, weather: &Weather) -> std::io::Result<()> {
+    let mut content = String::new();
+    content.push_str(&format!("time={}\n", world_time.ticks));
+    content.push_str(&format!("seed={}\n", world_seed.0));
+    content.push_str(&format!("weather={}\n", format!("{:?}", weather.kind).to_lowercase()));
+    content.push_str(&format!("weather_time={}\n", weather.remaining));
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)
+}
+
+fn load_level(path: &Path) -> Option<LevelData> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut level = LevelData::default();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key {
+            "time" => level.time.ticks = value.trim().parse().unwrap_or_default(),
+            "seed" => level.seed = value.trim().parse().map(WorldSeed).unwrap_or_default(),
+            "weather" => {
+                if let Ok(kind) = value.trim().parse() {
+                    level.weather.kind = kind;
+                    // Resume mid-shower rather than fading back in.
+                    level.weather.intensity = if kind == WeatherKind::Clear { 0.0 } else { 1.0 };
+                }
+            }
+            "weather_time" => {
+                if let Ok(remaining) = value.trim().parse() {
+                    level.weather.remaining = remaining;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRID_SIZE: i32 = 16;
+
+    #[derive(Default)]
+    struct TestGrid {
+        opaque: HashSet<IVec3>,
+        sky: HashMap<IVec3, u8>,
+        block: HashMap<IVec3, u8>,
+    }
+
+    impl LightGrid for TestGrid {
+        fn contains(&self, pos: IVec3) -> bool {
+            pos.cmpge(IVec3::ZERO).all() && pos.cmplt(IVec3::splat(GRID_SIZE)).all()
+        }
+
+        fn is_opaque(&self, pos: IVec3) -> bool {
+            self.opaque.contains(&pos)
+        }
+
+        fn light(&self, pos: IVec3, channel: LightChannel) -> u8 {
+            let levels = match channel {
+                LightChannel::Sky => &self.sky,
+                LightChannel::Block => &self.block,
+            };
+            levels.get(&pos).copied().unwrap_or(0)
+        }
+
+        fn set_light(&mut self, pos: IVec3, channel: LightChannel, level: u8) {
+            match channel {
+                LightChannel::Sky => self.sky.insert(pos, level),
+                LightChannel::Block => self.block.insert(pos, level),
+            };
+        }
+    }
+
+    impl TestGrid {
+        fn place(&mut self, pos: IVec3, opaque: bool, emission: u8) {
+            if opaque {
+                self.opaque.insert(pos);
+            } else {
+                self.opaque.remove(&pos);
+            }
+            update_light(self, pos, emission);
+        }
+
+        fn light_sky(&mut self) {
+            let mut queue = VecDeque::new();
+            for x in 0..GRID_SIZE {
+                for z in 0..GRID_SIZE {
+                    let top = IVec3::new(x, GRID_SIZE - 1, z);
+                    if !self.opaque.contains(&top) {
+                        self.sky.insert(top, MAX_LIGHT);
+                        queue.push_back(top);
+                    }
+                }
+            }
+            propagate_light(self, LightChannel::Sky, queue);
+        }
+    }
+
+    #[test]
+    fn block_light_fades_with_distance() {
+        let mut grid = TestGrid::default();
+        let torch = IVec3::new(8, 8, 8);
+        grid.place(torch, false, 14);
+        assert_eq!(grid.light(torch, LightChannel::Block), 14);
+        assert_eq!(grid.light(torch + IVec3::X, LightChannel::Block), 13);
+        assert_eq!(grid.light(torch + IVec3::new(2, 1, -3), LightChannel::Block), 8);
+        assert_eq!(grid.light(IVec3::new(0, 0, 0), LightChannel::Block), 0);
+    }
+
+    #[test]
+    fn block_light_goes_around_walls() {
+        let mut grid = TestGrid::default();
+        for y in 0..GRID_SIZE {
+            for z in 0..GRID_SIZE - 1 {
+                grid.opaque.insert(IVec3::new(8, y, z));
+            }
+        }
+        grid.place(IVec3::new(7, 4, 4), false, 14);
+        // The only way past the wall is through the gap at z = 15, thirteen steps away.
+        assert_eq!(grid.light(IVec3::new(9, 4, 15), LightChannel::Block), 1);
+        assert_eq!(grid.light(IVec3::new(9, 4, 4), LightChannel::Block), 0);
+        assert_eq!(grid.light(IVec3::new(7, 4, 14), LightChannel::Block), 4);
+    }
+
+    #[test]
+    fn removing_a_light_source_darkens_its_area() {
+        let mut grid = TestGrid::default();
+        let torch = IVec3::new(8, 8, 8);
+        grid.place(torch, false, 14);
+        grid.place(IVec3::new(2, 8, 8), false, 14);
+        grid.place(torch, false, 0);
+        assert_eq!(grid.light(torch, LightChannel::Block), 8);
+        assert_eq!(grid.light(IVec3::new(14, 8, 8), LightChannel::Block), 2);
+    }
+
+    #[test]
+    fn sky_light_falls_straight_down_and_spreads_under_roofs() {
+        let mut grid = TestGrid::default();
+        grid.light_sky();
+        assert_eq!(grid.light(IVec3::new(3, 0, 3), LightChannel::Sky), MAX_LIGHT);
+
+        for x in 4..=10 {
+            for z in 4..=10 {
+                grid.place(IVec3::new(x, 10, z), true, 0);
+            }
+        }
+        assert_eq!(grid.light(IVec3::new(7, 10, 7), LightChannel::Sky), 0);
+        // Nearest open column is four blocks away from the middle of the roof.
+        assert_eq!(grid.light(IVec3::new(7, 5, 7), LightChannel::Sky), MAX_LIGHT - 4);
+        assert_eq!(grid.light(IVec3::new(3, 5, 7), LightChannel::Sky), MAX_LIGHT);
+
+        grid.place(IVec3::new(7, 10, 7), false, 0);
+        assert_eq!(grid.light(IVec3::new(7, 5, 7), LightChannel::Sky), MAX_LIGHT);
+    }
+
+    #[test]
+    fn weather_transitions() {
+        assert_eq!(next_weather(WeatherKind::Clear, 0.1), WeatherKind::Thunder);
+        assert_eq!(next_weather(WeatherKind::Clear, 0.9), WeatherKind::Rain);
+        assert_eq!(next_weather(WeatherKind::Rain, 0.1), WeatherKind::Thunder);
+        assert_eq!(next_weather(WeatherKind::Rain, 0.9), WeatherKind::Clear);
+        assert_eq!(next_weather(WeatherKind::Thunder, 0.0), WeatherKind::Rain);
+        assert!(weather_duration(WeatherKind::Thunder, 0.99) < weather_duration(WeatherKind::Clear, 0.0));
+    }
+
+    #[test]
+    fn parses_server_properties() {
+        let properties = ServerProperties::parse(
+            "# comment\nlevel-name=soak\nlevel-seed=7\nmotd=Hello = world\nserver-port=25566\nday-length=0\nfuture-key=1\n",
+        )
+        .unwrap();
+        assert_eq!(properties.level_name, "soak");
+        assert_eq!(properties.level_seed, Some(7));
+        assert_eq!(properties.motd, "Hello = world");
+        assert_eq!(properties.server_port, 25566);
+        assert_eq!(properties.day_length, 1.0);
+        assert_eq!(properties.level_path(), Path::new("saves/soak/level.txt"));
+        assert_eq!(ServerProperties::parse(&properties.to_text()), Ok(properties));
+
+        assert_eq!(ServerProperties::parse("level-seed=").unwrap().level_seed, None);
+        assert!(ServerProperties::parse("server-port=lots").is_err());
+        assert!(ServerProperties::parse("level-name=../escape").is_err());
+        assert!(ServerProperties::parse("motd").is_err());
+    }
+
+    fn test_input() -> MoveInput {
+        MoveInput {
+            sequence: 7,
+            dt: 0.016,
+            walk: Vec2::new(1.5, -2.0),
+            jump: true,
+            lift: Some(3.0),
+            flying: false,
+            yaw: 0.5,
+            pitch: -0.25,
+        }
+    }
+
+    // Both directions of every packet, so this needs the client's half of the codec.
+    #[cfg(feature = "client")]
+    #[test]
+    fn network_packets_round_trip() {
+        let input = test_input();
+        let client_packets = [
+            ClientPacket::Handshake { protocol: PROTOCOL_VERSION },
+            ClientPacket::Login { name: "alice".to_string() },
+            ClientPacket::SetBlock { pos: IVec3::new(-3, 4, 5), kind: Some(BlockKind::Water) },
+            ClientPacket::SetBlock { pos: IVec3::ZERO, kind: None },
+            ClientPacket::Disconnect,
+        ];
+        for packet in client_packets {
+            assert_eq!(ClientPacket::decode(&packet.encode()), Ok(packet));
+        }
+        let server_packets = [
+            ServerPacket::LoginSuccess {
+                player_id: 3,
+                token: u64::MAX,
+                spawn: Vec3::new(0.0, 1.5, 0.0),
+                time: 1234.5,
+                seed: 42,
+                allow_flight: true,
+            },
+            ServerPacket::ChunkData { pos: IVec3::new(1, -1, 2), data: vec![1, 2, 3] },
+            ServerPacket::WorldReady,
+            ServerPacket::BlockUpdate { pos: IVec3::new(9, 9, 9), kind: Some(BlockKind::Grass) },
+            ServerPacket::PlayerJoined { id: 3, name: "bob".to_string() },
+            ServerPacket::PlayerLeft { id: 3 },
+            ServerPacket::Disconnect { reason: "Server closed".to_string() },
+        ];
+        for packet in server_packets {
+            assert_eq!(ServerPacket::decode(&packet.encode()), Ok(packet));
+        }
+        let datagram = InputDatagram { token: 99, inputs: vec![input, MoveInput { sequence: 8, lift: None, ..input }] };
+        assert_eq!(InputDatagram::decode(&datagram.encode()), Ok(datagram));
+        let snapshot = Snapshot {
+            ack: 8,
+            position: Vec3::ONE,
+            velocity: Vec3::NEG_Y,
+            players: vec![PlayerState { id: 2, position: Vec3::X, yaw: 1.0, pitch: 0.5 }],
+        };
+        assert_eq!(Snapshot::decode(&snapshot.encode()), Ok(snapshot));
+
+        let mut chunk = Chunk::default();
+        chunk.blocks[5] = Some(BlockKind::Torch);
+        assert_eq!(decode_chunk(&encode_chunk(&chunk)), Ok(chunk.blocks));
+        assert!(decode_chunk(&[0x78, 0x9c]).is_err());
+
+        let login = ClientPacket::Login { name: "alice".to_string() }.encode();
+        assert!(ClientPacket::decode(&login[..login.len() - 1]).is_err());
+        assert!(ClientPacket::decode(&[login.as_slice(), &[0]].concat()).is_err());
+        assert!(ClientPacket::decode(&[200]).is_err());
+        assert!(ClientPacket::decode(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 200]).is_err());
+        assert!(ServerPacket::decode(&[]).is_err());
+        assert!(InputDatagram::decode(&[0, 0, 0, 0, 0, 0, 0, 0, 200]).is_err());
+    }
+
+    #[test]
+    fn move_inputs_are_sanitized() {
+        let input = test_input();
+        let wild = MoveInput { dt: 5.0, walk: Vec2::new(1e9, 0.0), lift: Some(f32::NAN), pitch: 9.0, ..input }.sanitized(true);
+        assert_eq!(wild.dt, MAX_INPUT_DT);
+        assert!((wild.walk.length() - PLAYER_BASE_SPEED * SPRINT_MULTIPLIER * MAX_INPUT_DT).abs() < 0.01);
+        assert_eq!((wild.lift, wild.pitch), (Some(0.0), 1.5));
+        let grounded = MoveInput { flying: true, ..input }.sanitized(false);
+        assert_eq!((grounded.lift, grounded.flying), (None, false));
+        assert!(sequence_after(0, u32::MAX) && !sequence_after(5, 5) && !sequence_after(4, 5));
+    }
+
+    // Steps a server until `done` holds, which polls whatever clients the test is watching.
+    pub(crate) fn pump(server: &mut NetworkServer, world: &mut WorldBlocks, registry: &BlockRegistry, mut done: impl FnMut() -> bool) {
+        for _ in 0..500 {
+            server.update(world, registry, &WorldTime::default(), WorldSeed(7));
+            server.send_snapshots();
+            if done() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("timed out waiting for the network");
+    }
+
+    // Captured from a vanilla 1.20.4 client refreshing its server list against localhost:25565.
+    const JAVA_HANDSHAKE: [u8; 17] = [0x10, 0x00, 0xfd, 0x05, 0x09, b'l', b'o', b'c', b'a', b'l', b'h', b'o', b's', b't', 0x63, 0xdd, 0x01];
+    const JAVA_STATUS_REQUEST: [u8; 2] = [0x01, 0x00];
+    const JAVA_PING: [u8; 10] = [0x09, 0x01, 0x00, 0x00, 0x01, 0x8b, 0x4f, 0x2a, 0x9c, 0x11];
+
+    fn java_info() -> JavaServerInfo {
+        JavaServerInfo { motd: "A Minceraft Server".to_string(), online: 1, max_players: 20, sample: vec!["Steve".to_string()] }
+    }
+
+    // Splits one reply into its id and body.
+    fn split_java_packet(bytes: &[u8]) -> (i32, Vec<u8>) {
+        let (len, prefix) = peek_varint(bytes).unwrap().unwrap();
+        assert_eq!(bytes.len(), prefix + len as usize);
+        let mut reader = JavaReader(&bytes[prefix..]);
+        let id = reader.varint().unwrap();
+        (id, reader.0.to_vec())
+    }
+
+    #[test]
+    fn varints_match_the_java_encoding() {
+        let cases: [(i32, &[u8]); 7] = [
+            (0, &[0x00]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (255, &[0xff, 0x01]),
+            (25565, &[0xdd, 0xc7, 0x01]),
+            (2147483647, &[0xff, 0xff, 0xff, 0xff, 0x07]),
+            (-1, &[0xff, 0xff, 0xff, 0xff, 0x0f]),
+        ];
+        for (value, bytes) in cases {
+            let mut written = Vec::new();
+            write_varint(&mut written, value as u32);
+            assert_eq!(written, bytes);
+            assert_eq!(peek_varint(bytes).unwrap(), Some((value as u32, bytes.len())));
+        }
+        assert_eq!(peek_varint(&[0xdd, 0xc7]).unwrap(), None);
+        assert!(peek_varint(&[0xff, 0xff, 0xff, 0xff, 0xff, 0x01]).is_err());
+    }
+
+    #[test]
+    fn answers_a_captured_server_list_ping() {
+        let mut stage = JavaStage::Handshake;
+        let mut incoming = [&JAVA_HANDSHAKE[..], &JAVA_STATUS_REQUEST].concat();
+        let replies = answer_java_ping(&mut incoming, &mut stage, &java_info()).unwrap();
+        assert!(incoming.is_empty());
+        assert_eq!(stage, JavaStage::Ping);
+
+        let (id, body) = split_java_packet(&replies);
+        assert_eq!(id, 0x00);
+        let json = JavaReader(&body).string(32767).unwrap();
+        let status: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(status["version"]["protocol"], 765);
+        assert_eq!(status["players"]["online"], 1);
+        assert_eq!(status["players"]["max"], 20);
+        assert_eq!(status["players"]["sample"][0]["name"], "Steve");
+        assert_eq!(status["description"]["text"], "A Minceraft Server");
+
+        let mut incoming = JAVA_PING.to_vec();
+        let pong = answer_java_ping(&mut incoming, &mut stage, &java_info()).unwrap();
+        assert_eq!(pong, JAVA_PING);
+        assert_eq!(stage, JavaStage::Done);
+    }
+
+    #[test]
+    fn answers_only_one_java_status_request() {
+        let mut stage = JavaStage::Handshake;
+        let mut incoming = [&JAVA_HANDSHAKE[..], &JAVA_STATUS_REQUEST, &JAVA_STATUS_REQUEST].concat();
+        assert!(answer_java_ping(&mut incoming, &mut stage, &java_info()).is_err());
+    }
+
+    #[test]
+    fn answers_legacy_server_list_pings() {
+        // The start of a 1.6 client's ping; the rest names the host and doesn't change the answer.
+        let mut incoming = vec![0xfe, 0x01, 0xfa, 0x00, 0x0b, 0x00, b'M', 0x00, b'C', 0x00, b'|'];
+        let mut stage = JavaStage::Handshake;
+        let reply = answer_java_ping(&mut incoming, &mut stage, &java_info()).unwrap();
+        assert_eq!(stage, JavaStage::Done);
+        assert!(incoming.is_empty());
+        assert_eq!(reply[0], 0xff);
+        let len = u16::from_be_bytes([reply[1], reply[2]]) as usize;
+        let units: Vec<u16> = reply[3..].chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+        assert_eq!(units.len(), len);
+        let text = String::from_utf16(&units).unwrap();
+        let fields: Vec<&str> = text.split('\0').collect();
+        assert_eq!(fields[0], "\u{a7}1");
+        assert_eq!(fields[3..], ["A Minceraft Server", "1", "20"]);
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_split_java_packet() {
+        let mut stage = JavaStage::Handshake;
+        let mut incoming = JAVA_HANDSHAKE[..7].to_vec();
+        assert!(answer_java_ping(&mut incoming, &mut stage, &java_info()).unwrap().is_empty());
+        assert_eq!(incoming, &JAVA_HANDSHAKE[..7]);
+        assert_eq!(stage, JavaStage::Handshake);
+
+        incoming.extend_from_slice(&JAVA_HANDSHAKE[7..]);
+        assert!(answer_java_ping(&mut incoming, &mut stage, &java_info()).unwrap().is_empty());
+        assert!(incoming.is_empty());
+        assert_eq!(stage, JavaStage::Status { protocol: 765 });
+    }
+
+    #[test]
+    fn turns_away_vanilla_logins() {
+        let mut handshake = JAVA_HANDSHAKE;
+        handshake[16] = 0x02;
+        let mut stage = JavaStage::Handshake;
+        let replies = answer_java_ping(&mut handshake.to_vec(), &mut stage, &java_info()).unwrap();
+        assert_eq!(stage, JavaStage::Done);
+        let (id, body) = split_java_packet(&replies);
+        assert_eq!(id, 0x00);
+        assert!(JavaReader(&body).string(32767).unwrap().contains("Minceraft client"));
+
+        let mut stage = JavaStage::Handshake;
+        assert!(answer_java_ping(&mut JAVA_STATUS_REQUEST.to_vec(), &mut stage, &java_info()).is_err());
+    }
+
+    #[test]
+    fn server_answers_vanilla_status_pings() {
+        let registry = BlockRegistry::default();
+        let mut world = WorldBlocks::default();
+        let mut server = NetworkServer::bind("127.0.0.1:0".parse().unwrap(), 20, "Java Test".to_string()).unwrap();
+        let address = server.local_addr();
+        let pinging = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(&[&JAVA_HANDSHAKE[..], &JAVA_STATUS_REQUEST, &JAVA_PING].concat()).unwrap();
+            let mut replies = Vec::new();
+            stream.read_to_end(&mut replies).unwrap();
+            replies
+        });
+        pump(&mut server, &mut world, &registry, || pinging.is_finished());
+        let replies = pinging.join().unwrap();
+        let (len, prefix) = peek_varint(&replies).unwrap().unwrap();
+        let (status, pong) = replies.split_at(prefix + len as usize);
+        let (_, body) = split_java_packet(status);
+        let json: serde_json::Value = serde_json::from_str(&JavaReader(&body).string(32767).unwrap()).unwrap();
+        assert_eq!(json["description"]["text"], "Java Test");
+        assert_eq!(pong, JAVA_PING);
+        assert!(server.messages.is_empty());
+    }
+
+    #[test]
+    fn drops_connections_that_never_log_in() {
+        let registry = BlockRegistry::default();
+        let mut world = WorldBlocks::default();
+        let mut server = NetworkServer::bind("127.0.0.1:0".parse().unwrap(), 2, "Test".to_string()).unwrap();
+        server.login_timeout = Duration::from_millis(50);
+        let _silent = TcpStream::connect(server.local_addr()).unwrap();
+        for _ in 0..500 {
+            server.update(&mut world, &registry, &WorldTime::default(), WorldSeed(7));
+            if !server.messages.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(server.connections.is_empty());
+        assert!(server.messages[0].ends_with("while connecting: Took too long to log in"), "{:?}", server.messages);
+    }
+
+    #[test]
+    fn simulation_runs_headless() {
+        use bevy::time::TimeUpdateStrategy;
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(SimulationPlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / TICKS_PER_SECOND)));
+        app.world.resource_mut::<WorldBlocks>().set_raw(IVec3::ZERO, Some(BlockKind::Stone));
+        let item = app
+            .world
+            .spawn((
+                Transform::from_xyz(0.0, 3.0, 0.0),
+                DroppedItem { stack: ItemStack { item: ItemKind::Dirt, count: 1, damage: 0 }, age: 0.0 },
+                Velocity(Vec3::ZERO),
+                Gravity(GRAVITY),
+            ))
+            .id();
+
+        for _ in 0..100 {
+            app.update();
+        }
+        assert!(app.world.resource::<WorldTime>().ticks > 0.0);
+        let resting = app.world.get::<Transform>(item).unwrap().translation.y;
+        assert!((resting - (0.5 + ITEM_SIZE * 0.5)).abs() < 0.01, "item at {resting}");
+    }
+}
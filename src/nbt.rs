@@ -0,0 +1,1225 @@
+// Minecraft's Named Binary Tag format: big-endian binary with a named root, its SNBT text form,
+// gzip/zlib streams, and serde support for turning Rust values into tags and back.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use flate2::{
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use serde::{de, ser, Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(BTreeMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+// Errors from SNBT parsing and serde conversion; binary reads report `io::Error` like any other stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Error(message.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(message: T) -> Self {
+        Error(message.to_string())
+    }
+}
+
+const END: u8 = 0;
+
+// Lengths come from the file, so allocate in steps rather than trusting them up front.
+const MAX_PREALLOCATION: usize = 4096;
+
+// Minecraft's own limit; stops hostile files from overflowing the stack.
+const MAX_DEPTH: usize = 512;
+
+impl Tag {
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(_) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.get(name),
+            _ => None,
+        }
+    }
+
+    // Any integer tag, widened.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Tag::Byte(value) => Some(value as i64),
+            Tag::Short(value) => Some(value as i64),
+            Tag::Int(value) => Some(value as i64),
+            Tag::Long(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&BTreeMap<String, Tag>> {
+        match self {
+            Tag::Compound(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn read_array<const N: usize>(reader: &mut dyn Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_length(reader: &mut dyn Read) -> io::Result<usize> {
+    let length = i32::from_be_bytes(read_array(reader)?);
+    usize::try_from(length).map_err(|_| invalid(format!("negative length {length}")))
+}
+
+fn read_vec<T>(reader: &mut dyn Read, mut read: impl FnMut(&mut dyn Read) -> io::Result<T>) -> io::Result<Vec<T>> {
+    let length = read_length(reader)?;
+    let mut values = Vec::with_capacity(length.min(MAX_PREALLOCATION));
+    for _ in 0..length {
+        values.push(read(reader)?);
+    }
+    Ok(values)
+}
+
+fn read_string(reader: &mut dyn Read) -> io::Result<String> {
+    let length = u16::from_be_bytes(read_array(reader)?) as usize;
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+    // Java writes modified UTF-8; plain UTF-8 covers everything but NUL and astral characters.
+    String::from_utf8(bytes).map_err(|_| invalid("string is not valid UTF-8"))
+}
+
+fn read_payload(reader: &mut dyn Read, id: u8, depth: usize) -> io::Result<Tag> {
+    if depth > MAX_DEPTH {
+        return Err(invalid(format!("tags nested deeper than {MAX_DEPTH}")));
+    }
+    Ok(match id {
+        1 => Tag::Byte(i8::from_be_bytes(read_array(reader)?)),
+        2 => Tag::Short(i16::from_be_bytes(read_array(reader)?)),
+        3 => Tag::Int(i32::from_be_bytes(read_array(reader)?)),
+        4 => Tag::Long(i64::from_be_bytes(read_array(reader)?)),
+        5 => Tag::Float(f32::from_be_bytes(read_array(reader)?)),
+        6 => Tag::Double(f64::from_be_bytes(read_array(reader)?)),
+        7 => Tag::ByteArray(read_vec(reader, |reader| Ok(i8::from_be_bytes(read_array(reader)?)))?),
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let element = read_array::<1>(reader)?[0];
+            let length = read_length(reader)?;
+            if element == END && length > 0 {
+                return Err(invalid("non-empty list of end tags"));
+            }
+            let mut values = Vec::with_capacity(length.min(MAX_PREALLOCATION));
+            for _ in 0..length {
+                values.push(read_payload(reader, element, depth + 1)?);
+            }
+            Tag::List(values)
+        }
+        10 => {
+            let mut entries = BTreeMap::new();
+            loop {
+                let id = read_array::<1>(reader)?[0];
+                if id == END {
+                    break;
+                }
+                let name = read_string(reader)?;
+                entries.insert(name, read_payload(reader, id, depth + 1)?);
+            }
+            Tag::Compound(entries)
+        }
+        11 => Tag::IntArray(read_vec(reader, |reader| Ok(i32::from_be_bytes(read_array(reader)?)))?),
+        12 => Tag::LongArray(read_vec(reader, |reader| Ok(i64::from_be_bytes(read_array(reader)?)))?),
+        id => return Err(invalid(format!("unknown tag id {id}"))),
+    })
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    let length = u16::try_from(value.len()).map_err(|_| invalid("string longer than 65535 bytes"))?;
+    writer.write_all(&length.to_be_bytes())?;
+    writer.write_all(value.as_bytes())
+}
+
+fn write_length(writer: &mut impl Write, length: usize) -> io::Result<()> {
+    let length = i32::try_from(length).map_err(|_| invalid("array too long"))?;
+    writer.write_all(&length.to_be_bytes())
+}
+
+fn write_payload(writer: &mut impl Write, tag: &Tag) -> io::Result<()> {
+    match tag {
+        Tag::Byte(value) => writer.write_all(&value.to_be_bytes()),
+        Tag::Short(value) => writer.write_all(&value.to_be_bytes()),
+        Tag::Int(value) => writer.write_all(&value.to_be_bytes()),
+        Tag::Long(value) => writer.write_all(&value.to_be_bytes()),
+        Tag::Float(value) => writer.write_all(&value.to_be_bytes()),
+        Tag::Double(value) => writer.write_all(&value.to_be_bytes()),
+        Tag::ByteArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| writer.write_all(&value.to_be_bytes()))
+        }
+        Tag::String(value) => write_string(writer, value),
+        Tag::List(values) => {
+            let element = values.first().map_or(END, Tag::id);
+            if values.iter().any(|value| value.id() != element) {
+                return Err(invalid("list elements must share one type"));
+            }
+            writer.write_all(&[element])?;
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| write_payload(writer, value))
+        }
+        Tag::Compound(entries) => {
+            for (name, value) in entries {
+                writer.write_all(&[value.id()])?;
+                write_string(writer, name)?;
+                write_payload(writer, value)?;
+            }
+            writer.write_all(&[END])
+        }
+        Tag::IntArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| writer.write_all(&value.to_be_bytes()))
+        }
+        Tag::LongArray(values) => {
+            write_length(writer, values.len())?;
+            values.iter().try_for_each(|value| writer.write_all(&value.to_be_bytes()))
+        }
+    }
+}
+
+// Reads an uncompressed document, returning the root's name and value.
+pub fn read(reader: &mut impl Read) -> io::Result<(String, Tag)> {
+    let reader: &mut dyn Read = reader;
+    let id = read_array::<1>(reader)?[0];
+    if id == END {
+        return Err(invalid("document starts with an end tag"));
+    }
+    let name = read_string(reader)?;
+    Ok((name, read_payload(reader, id, 0)?))
+}
+
+pub fn write(writer: &mut impl Write, name: &str, tag: &Tag) -> io::Result<()> {
+    writer.write_all(&[tag.id()])?;
+    write_string(writer, name)?;
+    write_payload(writer, tag)
+}
+
+pub fn read_gzip(bytes: &[u8]) -> io::Result<(String, Tag)> {
+    read(&mut GzDecoder::new(bytes))
+}
+
+pub fn read_zlib(bytes: &[u8]) -> io::Result<(String, Tag)> {
+    read(&mut ZlibDecoder::new(bytes))
+}
+
+// Picks gzip, zlib or no compression from the first bytes, as Minecraft does for its own files.
+pub fn read_compressed(bytes: &[u8]) -> io::Result<(String, Tag)> {
+    match bytes {
+        [0x1f, 0x8b, ..] => read_gzip(bytes),
+        [0x78, ..] => read_zlib(bytes),
+        _ => read(&mut &bytes[..]),
+    }
+}
+
+pub fn write_gzip(name: &str, tag: &Tag) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    write(&mut encoder, name, tag)?;
+    encoder.finish()
+}
+
+pub fn write_zlib(name: &str, tag: &Tag) -> io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    write(&mut encoder, name, tag)?;
+    encoder.finish()
+}
+
+fn is_unquoted(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+fn write_quoted(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+}
+
+fn write_joined<T>(
+    out: &mut String,
+    values: &[T],
+    mut write: impl FnMut(&mut String, &T) -> Result<(), Error>,
+) -> Result<(), Error> {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write(out, value)?;
+    }
+    Ok(())
+}
+
+fn write_snbt(out: &mut String, tag: &Tag) -> Result<(), Error> {
+    match tag {
+        Tag::Byte(value) => out.push_str(&format!("{value}b")),
+        Tag::Short(value) => out.push_str(&format!("{value}s")),
+        Tag::Int(value) => out.push_str(&value.to_string()),
+        Tag::Long(value) => out.push_str(&format!("{value}L")),
+        // SNBT has no spelling for NaN or infinity, so those are refused rather than written unreadably.
+        Tag::Float(value) if !value.is_finite() => return Err(Error(format!("{value} can't be written as SNBT"))),
+        Tag::Double(value) if !value.is_finite() => return Err(Error(format!("{value} can't be written as SNBT"))),
+        // Debug keeps the decimal point and enough digits to read back the same value.
+        Tag::Float(value) => out.push_str(&format!("{value:?}f")),
+        Tag::Double(value) => out.push_str(&format!("{value:?}d")),
+        Tag::String(value) => write_quoted(out, value),
+        Tag::ByteArray(values) => {
+            out.push_str("[B;");
+            write_joined(out, values, |out, value| {
+                out.push_str(&format!("{value}b"));
+                Ok(())
+            })?;
+            out.push(']');
+        }
+        Tag::IntArray(values) => {
+            out.push_str("[I;");
+            write_joined(out, values, |out, value| {
+                out.push_str(&value.to_string());
+                Ok(())
+            })?;
+            out.push(']');
+        }
+        Tag::LongArray(values) => {
+            out.push_str("[L;");
+            write_joined(out, values, |out, value| {
+                out.push_str(&format!("{value}L"));
+                Ok(())
+            })?;
+            out.push(']');
+        }
+        Tag::List(values) => {
+            out.push('[');
+            write_joined(out, values, write_snbt)?;
+            out.push(']');
+        }
+        Tag::Compound(entries) => {
+            out.push('{');
+            let entries: Vec<_> = entries.iter().collect();
+            write_joined(out, &entries, |out, (name, value)| {
+                if !name.is_empty() && name.chars().all(is_unquoted) {
+                    out.push_str(name);
+                } else {
+                    write_quoted(out, name);
+                }
+                out.push(':');
+                write_snbt(out, value)
+            })?;
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+// The text form used by commands and `/data` output, e.g. `{name:"Steve",pos:[I;1,2,3]}`.
+pub fn to_snbt(tag: &Tag) -> Result<String, Error> {
+    let mut out = String::new();
+    write_snbt(&mut out, tag)?;
+    Ok(out)
+}
+
+pub fn parse_snbt(text: &str) -> Result<Tag, Error> {
+    let mut parser = SnbtParser { text, pos: 0 };
+    let tag = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(tag)
+}
+
+struct SnbtParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl SnbtParser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error(format!("{message} at character {}", self.pos))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{expected}'")));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // Consumes a separating comma, or reports whether the closing bracket was reached.
+    fn next_or_close(&mut self, close: char) -> Result<bool, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(',') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(c) if c == close => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(self.error(&format!("expected ',' or '{close}'"))),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                let mut value = String::new();
+                loop {
+                    let c = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += c.len_utf8();
+                    match c {
+                        '\\' => {
+                            let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+                            if !matches!(escaped, '\\' | '"' | '\'') {
+                                return Err(self.error(&format!("invalid escape '\\{escaped}'")));
+                            }
+                            self.pos += 1;
+                            value.push(escaped);
+                        }
+                        c if c == quote => return Ok(value),
+                        c => value.push(c),
+                    }
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek().filter(|&c| is_unquoted(c)) {
+                    self.pos += c.len_utf8();
+                }
+                if start == self.pos {
+                    return Err(self.error("expected a value"));
+                }
+                Ok(self.text[start..self.pos].to_string())
+            }
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Tag, Error> {
+        if depth > MAX_DEPTH {
+            return Err(self.error(&format!("tags nested deeper than {MAX_DEPTH}")));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = BTreeMap::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(Tag::Compound(entries));
+                }
+                loop {
+                    let name = self.string()?;
+                    self.expect(':')?;
+                    let value = self.value(depth + 1)?;
+                    entries.insert(name, value);
+                    if self.next_or_close('}')? {
+                        return Ok(Tag::Compound(entries));
+                    }
+                }
+            }
+            Some('[') => {
+                self.pos += 1;
+                let rest = &self.text[self.pos..];
+                if let Some(kind) = ['B', 'I', 'L'].into_iter().find(|&kind| rest.starts_with(&format!("{kind};"))) {
+                    self.pos += 2;
+                    return self.array(kind, depth);
+                }
+                let mut values: Vec<Tag> = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                    return Ok(Tag::List(values));
+                }
+                loop {
+                    let value = self.value(depth + 1)?;
+                    if values.first().is_some_and(|first| first.id() != value.id()) {
+                        return Err(self.error("list elements must share one type"));
+                    }
+                    values.push(value);
+                    if self.next_or_close(']')? {
+                        return Ok(Tag::List(values));
+                    }
+                }
+            }
+            Some('"' | '\'') => Ok(Tag::String(self.string()?)),
+            _ => Ok(unquoted_value(self.string()?)),
+        }
+    }
+
+    // The body of a `[B;..]`, `[I;..]` or `[L;..]` array, whose elements must carry the matching suffix.
+    fn array(&mut self, kind: char, depth: usize) -> Result<Tag, Error> {
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+        } else {
+            loop {
+                values.push(self.value(depth + 1)?);
+                if self.next_or_close(']')? {
+                    break;
+                }
+            }
+        }
+        let mismatch = self.error(&format!("wrong element type in [{kind};] array"));
+        let array = match kind {
+            'B' => values.iter().map(|value| if let Tag::Byte(value) = value { Some(*value) } else { None }).collect::<Option<_>>().map(Tag::ByteArray),
+            'I' => values.iter().map(|value| if let Tag::Int(value) = value { Some(*value) } else { None }).collect::<Option<_>>().map(Tag::IntArray),
+            _ => values.iter().map(|value| if let Tag::Long(value) = value { Some(*value) } else { None }).collect::<Option<_>>().map(Tag::LongArray),
+        };
+        array.ok_or(mismatch)
+    }
+}
+
+fn is_integer(text: &str) -> bool {
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_decimal(text: &str) -> bool {
+    text.chars().any(|c| c.is_ascii_digit())
+        && text.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+        && text.parse::<f64>().is_ok()
+}
+
+// Unquoted tokens are numbers when they look like one and strings otherwise, like Minecraft's parser.
+fn unquoted_value(token: String) -> Tag {
+    match token.as_str() {
+        "true" => return Tag::Byte(1),
+        "false" => return Tag::Byte(0),
+        _ => {}
+    }
+    let (body, suffix) = match token.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&token[..i], Some(c.to_ascii_lowercase())),
+        _ => (token.as_str(), None),
+    };
+    let number = match suffix {
+        Some('b') if is_integer(body) => body.parse().ok().map(Tag::Byte),
+        Some('s') if is_integer(body) => body.parse().ok().map(Tag::Short),
+        Some('l') if is_integer(body) => body.parse().ok().map(Tag::Long),
+        Some('f') if is_decimal(body) => body.parse().ok().map(Tag::Float),
+        Some('d') if is_decimal(body) => body.parse().ok().map(Tag::Double),
+        None if is_integer(body) => body.parse().ok().map(Tag::Int),
+        None if is_decimal(body) => body.parse().ok().map(Tag::Double),
+        _ => None,
+    };
+    number.unwrap_or(Tag::String(token))
+}
+
+// Serializes a value into a tag: structs and maps become compounds, sequences lists, `None` fields are left out.
+pub fn to_tag<T: Serialize + ?Sized>(value: &T) -> Result<Tag, Error> {
+    value.serialize(TagSerializer)?.ok_or_else(|| Error("cannot serialize a missing value on its own".to_string()))
+}
+
+pub fn from_tag<'de, T: Deserialize<'de>>(tag: Tag) -> Result<T, Error> {
+    T::deserialize(tag)
+}
+
+struct TagSerializer;
+
+// `None` stands for a value that has no tag, such as an absent option.
+type Serialized = Result<Option<Tag>, Error>;
+
+fn required(tag: Option<Tag>) -> Result<Tag, Error> {
+    tag.ok_or_else(|| Error("lists cannot hold missing values".to_string()))
+}
+
+fn single_entry(name: &str, tag: Tag) -> Tag {
+    Tag::Compound(BTreeMap::from([(name.to_string(), tag)]))
+}
+
+struct ListSerializer {
+    variant: Option<&'static str>,
+    values: Vec<Tag>,
+}
+
+impl ListSerializer {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let tag = required(value.serialize(TagSerializer)?)?;
+        if self.values.first().is_some_and(|first| first.id() != tag.id()) {
+            return Err(Error("list elements must share one type".to_string()));
+        }
+        self.values.push(tag);
+        Ok(())
+    }
+
+    fn finish(self) -> Serialized {
+        let list = Tag::List(self.values);
+        Ok(Some(match self.variant {
+            Some(variant) => single_entry(variant, list),
+            None => list,
+        }))
+    }
+}
+
+struct CompoundSerializer {
+    variant: Option<&'static str>,
+    entries: BTreeMap<String, Tag>,
+    key: Option<String>,
+}
+
+impl CompoundSerializer {
+    fn new(variant: Option<&'static str>) -> Self {
+        CompoundSerializer { variant, entries: BTreeMap::new(), key: None }
+    }
+
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), Error> {
+        if let Some(tag) = value.serialize(TagSerializer)? {
+            self.entries.insert(key, tag);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Serialized {
+        let compound = Tag::Compound(self.entries);
+        Ok(Some(match self.variant {
+            Some(variant) => single_entry(variant, compound),
+            None => compound,
+        }))
+    }
+}
+
+impl ser::Serializer for TagSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = CompoundSerializer;
+    type SerializeStruct = CompoundSerializer;
+    type SerializeStructVariant = CompoundSerializer;
+
+    fn serialize_bool(self, value: bool) -> Serialized {
+        Ok(Some(Tag::Byte(value as i8)))
+    }
+
+    fn serialize_i8(self, value: i8) -> Serialized {
+        Ok(Some(Tag::Byte(value)))
+    }
+
+    fn serialize_i16(self, value: i16) -> Serialized {
+        Ok(Some(Tag::Short(value)))
+    }
+
+    fn serialize_i32(self, value: i32) -> Serialized {
+        Ok(Some(Tag::Int(value)))
+    }
+
+    fn serialize_i64(self, value: i64) -> Serialized {
+        Ok(Some(Tag::Long(value)))
+    }
+
+    // NBT has no unsigned types, so these widen to the next signed tag that holds every value.
+    fn serialize_u8(self, value: u8) -> Serialized {
+        Ok(Some(Tag::Short(value as i16)))
+    }
+
+    fn serialize_u16(self, value: u16) -> Serialized {
+        Ok(Some(Tag::Int(value as i32)))
+    }
+
+    fn serialize_u32(self, value: u32) -> Serialized {
+        Ok(Some(Tag::Long(value as i64)))
+    }
+
+    fn serialize_u64(self, value: u64) -> Serialized {
+        let value = i64::try_from(value).map_err(|_| Error(format!("{value} does not fit in a long")))?;
+        Ok(Some(Tag::Long(value)))
+    }
+
+    fn serialize_f32(self, value: f32) -> Serialized {
+        Ok(Some(Tag::Float(value)))
+    }
+
+    fn serialize_f64(self, value: f64) -> Serialized {
+        Ok(Some(Tag::Double(value)))
+    }
+
+    fn serialize_char(self, value: char) -> Serialized {
+        Ok(Some(Tag::String(value.to_string())))
+    }
+
+    fn serialize_str(self, value: &str) -> Serialized {
+        Ok(Some(Tag::String(value.to_string())))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Serialized {
+        Ok(Some(Tag::ByteArray(value.iter().map(|&byte| byte as i8).collect())))
+    }
+
+    fn serialize_none(self) -> Serialized {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Serialized {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Serialized {
+        Ok(Some(Tag::Compound(BTreeMap::new())))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Serialized {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Serialized {
+        Ok(Some(Tag::String(variant.to_string())))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Serialized {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Serialized {
+        Ok(Some(single_entry(variant, required(value.serialize(self)?)?)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, Error> {
+        Ok(ListSerializer { variant: None, values: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ListSerializer, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<ListSerializer, Error> {
+        Ok(ListSerializer { variant: Some(variant), values: Vec::with_capacity(len) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<CompoundSerializer, Error> {
+        Ok(CompoundSerializer::new(None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<CompoundSerializer, Error> {
+        Ok(CompoundSerializer::new(None))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<CompoundSerializer, Error> {
+        Ok(CompoundSerializer::new(Some(variant)))
+    }
+}
+
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Serialized {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Serialized {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Serialized {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for ListSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Serialized {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for CompoundSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match key.serialize(TagSerializer)? {
+            Some(Tag::String(key)) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(Error("compound keys must be strings".to_string())),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self.key.take().ok_or_else(|| Error("value serialized before its key".to_string()))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Serialized {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for CompoundSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Serialized {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for CompoundSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Serialized {
+        self.finish()
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Tag {
+    type Error = Error;
+
+    fn deserialize_any<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        use de::value::{MapDeserializer, SeqDeserializer};
+        match self {
+            Tag::Byte(value) => visitor.visit_i8(value),
+            Tag::Short(value) => visitor.visit_i16(value),
+            Tag::Int(value) => visitor.visit_i32(value),
+            Tag::Long(value) => visitor.visit_i64(value),
+            Tag::Float(value) => visitor.visit_f32(value),
+            Tag::Double(value) => visitor.visit_f64(value),
+            Tag::String(value) => visitor.visit_string(value),
+            Tag::ByteArray(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            Tag::IntArray(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            Tag::LongArray(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            Tag::List(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            Tag::Compound(entries) => visitor.visit_map(MapDeserializer::new(entries.into_iter())),
+        }
+    }
+
+    // Minecraft stores booleans as bytes.
+    fn deserialize_bool<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Tag::Byte(value) => visitor.visit_bool(value != 0),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    // A tag that is present is always `Some`; missing struct fields become `None` on their own.
+    fn deserialize_option<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_some(self)
+    }
+
+    // Units are written as empty compounds.
+    fn deserialize_unit<V: de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self {
+            Tag::Compound(entries) if entries.is_empty() => visitor.visit_unit(),
+            other => Err(Error(format!("expected an empty compound, found tag type {}", other.id()))),
+        }
+    }
+
+    fn deserialize_unit_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: de::Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self {
+            Tag::String(variant) => visitor.visit_enum(de::IntoDeserializer::<Error>::into_deserializer(variant)),
+            Tag::Compound(entries) if entries.len() == 1 => {
+                let (variant, value) = entries.into_iter().next().unwrap();
+                visitor.visit_enum(VariantTag { variant, value })
+            }
+            other => Err(Error(format!("expected an enum variant, found tag type {}", other.id()))),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl de::IntoDeserializer<'_, Error> for Tag {
+    type Deserializer = Tag;
+
+    fn into_deserializer(self) -> Tag {
+        self
+    }
+}
+
+// A `{variant: value}` compound standing for a data-carrying enum variant.
+struct VariantTag {
+    variant: String,
+    value: Tag,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantTag {
+    type Error = Error;
+    type Variant = Tag;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Tag), Error> {
+        let variant = seed.deserialize(de::IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Tag {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        Err(Error("unit variants are stored as strings".to_string()))
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: de::Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: de::Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compound(entries: Vec<(&str, Tag)>) -> Tag {
+        Tag::Compound(entries.into_iter().map(|(name, tag)| (name.to_string(), tag)).collect())
+    }
+
+    fn every_tag() -> Tag {
+        compound(vec![
+            ("byte", Tag::Byte(-5)),
+            ("short", Tag::Short(300)),
+            ("int", Tag::Int(-70000)),
+            ("long", Tag::Long(1 << 40)),
+            ("float", Tag::Float(0.1)),
+            ("double", Tag::Double(-1.5e-10)),
+            ("bytes", Tag::ByteArray(vec![1, -1, 127])),
+            ("string", Tag::String("quote \" and \\ and ü".to_string())),
+            ("list", Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+            ("empty list", Tag::List(Vec::new())),
+            ("nested", compound(vec![("inner", Tag::List(vec![compound(vec![])]))])),
+            ("ints", Tag::IntArray(vec![i32::MIN, 0, i32::MAX])),
+            ("longs", Tag::LongArray(vec![i64::MIN, i64::MAX])),
+        ])
+    }
+
+    #[test]
+    fn binary_round_trips_every_tag_type() {
+        let tag = every_tag();
+        let mut bytes = Vec::new();
+        write(&mut bytes, "root", &tag).unwrap();
+        assert_eq!(read(&mut &bytes[..]).unwrap(), ("root".to_string(), tag.clone()));
+        assert_eq!(read_compressed(&bytes).unwrap().1, tag);
+
+        let gzip = write_gzip("", &tag).unwrap();
+        let zlib = write_zlib("", &tag).unwrap();
+        assert_eq!(read_gzip(&gzip).unwrap().1, tag);
+        assert_eq!(read_zlib(&zlib).unwrap().1, tag);
+        assert_eq!(read_compressed(&gzip).unwrap().1, tag);
+        assert_eq!(read_compressed(&zlib).unwrap().1, tag);
+    }
+
+    #[test]
+    fn binary_layout_is_big_endian() {
+        let mut bytes = Vec::new();
+        write(&mut bytes, "hi", &compound(vec![("n", Tag::Short(0x0102))])).unwrap();
+        assert_eq!(bytes, [10, 0, 2, b'h', b'i', 2, 0, 1, b'n', 1, 2, 0]);
+    }
+
+    #[test]
+    fn rejects_malformed_binary() {
+        let mut valid = Vec::new();
+        write(&mut valid, "", &every_tag()).unwrap();
+        // Every truncation fails cleanly instead of panicking.
+        for length in 0..valid.len() {
+            assert!(read(&mut &valid[..length]).is_err(), "truncated to {length}");
+        }
+
+        let cases: [&[u8]; 6] = [
+            &[0],
+            &[13, 0, 0],
+            &[10, 0, 0, 7, 0, 1, b'a', 0xff, 0xff, 0xff, 0xff, 0],
+            &[9, 0, 0, 0, 0, 0, 0, 1],
+            &[8, 0, 2, 0xc3, 0x28],
+            &[10, 0, 0, 3, 0, 1],
+        ];
+        for bytes in cases {
+            assert!(read(&mut &bytes[..]).is_err(), "{bytes:?}");
+        }
+
+        // A huge declared length must not be allocated up front.
+        assert!(read(&mut &[11, 0, 0, 0x7f, 0xff, 0xff, 0xff, 0, 0, 0, 1][..]).is_err());
+
+        let mut deep = vec![9, 0, 0];
+        for _ in 0..MAX_DEPTH + 2 {
+            deep.extend([9, 0, 0, 0, 1]);
+        }
+        assert!(read(&mut &deep[..]).is_err());
+
+        assert!(write(&mut Vec::new(), "", &Tag::List(vec![Tag::Int(1), Tag::Byte(1)])).is_err());
+        assert!(read_gzip(&[0x1f, 0x8b, 1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn snbt_round_trips_every_tag_type() {
+        let tag = every_tag();
+        assert_eq!(parse_snbt(&to_snbt(&tag).unwrap()), Ok(tag));
+        let text = to_snbt(&compound(vec![("a b", Tag::Byte(1)), ("c", Tag::IntArray(vec![1, 2]))]));
+        assert_eq!(text.as_deref(), Ok(r#"{"a b":1b,c:[I;1,2]}"#));
+
+        // Values SNBT can't spell are refused instead of written unreadably.
+        for tag in [Tag::Float(f32::NAN), Tag::Double(f64::INFINITY), Tag::List(vec![Tag::Float(f32::NEG_INFINITY)])] {
+            assert!(to_snbt(&tag).is_err(), "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn parses_snbt_literals() {
+        assert_eq!(parse_snbt("1b"), Ok(Tag::Byte(1)));
+        assert_eq!(parse_snbt("-3S"), Ok(Tag::Short(-3)));
+        assert_eq!(parse_snbt("42"), Ok(Tag::Int(42)));
+        assert_eq!(parse_snbt("9L"), Ok(Tag::Long(9)));
+        assert_eq!(parse_snbt("1.5f"), Ok(Tag::Float(1.5)));
+        assert_eq!(parse_snbt("2.5"), Ok(Tag::Double(2.5)));
+        assert_eq!(parse_snbt("1e3d"), Ok(Tag::Double(1000.0)));
+        assert_eq!(parse_snbt("true"), Ok(Tag::Byte(1)));
+        assert_eq!(parse_snbt("stone"), Ok(Tag::String("stone".to_string())));
+        // Out of range or oddly shaped numbers stay strings, as in Minecraft.
+        assert_eq!(parse_snbt("300b"), Ok(Tag::String("300b".to_string())));
+        assert_eq!(parse_snbt("2147483648"), Ok(Tag::String("2147483648".to_string())));
+        assert_eq!(parse_snbt("1.2.3"), Ok(Tag::String("1.2.3".to_string())));
+        assert_eq!(parse_snbt(r#"'say "hi"'"#), Ok(Tag::String("say \"hi\"".to_string())));
+        assert_eq!(
+            parse_snbt(" { id : \"minecraft:stone\" , Count : 1b , tags : [ ] } "),
+            Ok(compound(vec![
+                ("id", Tag::String("minecraft:stone".to_string())),
+                ("Count", Tag::Byte(1)),
+                ("tags", Tag::List(Vec::new())),
+            ]))
+        );
+        assert_eq!(parse_snbt("[B;]"), Ok(Tag::ByteArray(Vec::new())));
+    }
+
+    #[test]
+    fn rejects_malformed_snbt() {
+        for text in [
+            "", "{", "{a}", "{a:1,}", "{a:1 b:2}", "[1,2b]", "[1,", "[I;1b]", "[B;1,2]", "\"open", "'\\n'", "1 2", "}", "{:1}",
+        ] {
+            assert!(parse_snbt(text).is_err(), "{text:?}");
+        }
+        let deep = "[".repeat(MAX_DEPTH + 2) + &"]".repeat(MAX_DEPTH + 2);
+        assert!(parse_snbt(&deep).is_err());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Plain,
+        Counted(i32),
+        Shaped { width: i16 },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Marker;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        count: u8,
+        enchanted: bool,
+        lore: Option<String>,
+        position: (i32, i32, i32),
+        tags: Vec<String>,
+        kind: Kind,
+        shapes: Vec<Kind>,
+        weights: BTreeMap<String, f64>,
+        nothing: (),
+        marker: Marker,
+    }
+
+    #[test]
+    fn serde_round_trips_through_tags() {
+        let item = Item {
+            name: "Pick".to_string(),
+            count: 200,
+            enchanted: true,
+            lore: None,
+            position: (1, -2, 3),
+            tags: vec!["tool".to_string()],
+            kind: Kind::Plain,
+            shapes: vec![Kind::Counted(3), Kind::Shaped { width: 2 }],
+            weights: BTreeMap::from([("a".to_string(), 0.5)]),
+            nothing: (),
+            marker: Marker,
+        };
+        let tag = to_tag(&item).unwrap();
+        assert_eq!(tag.get("count"), Some(&Tag::Short(200)));
+        assert_eq!(tag.get("enchanted"), Some(&Tag::Byte(1)));
+        assert_eq!(tag.get("lore"), None);
+        assert_eq!(tag.get("kind"), Some(&Tag::String("Plain".to_string())));
+        assert_eq!(tag.get("marker"), Some(&Tag::Compound(BTreeMap::new())));
+
+        let mut bytes = Vec::new();
+        write(&mut bytes, "", &tag).unwrap();
+        let (_, read_back) = read(&mut &bytes[..]).unwrap();
+        assert_eq!(from_tag::<Item>(read_back), Ok(item));
+    }
+
+    #[test]
+    fn serde_reports_mismatches() {
+        assert!(from_tag::<Item>(Tag::Int(1)).is_err());
+        assert!(from_tag::<Item>(compound(vec![("name", Tag::Int(1))])).is_err());
+        assert!(from_tag::<Kind>(Tag::String("Missing".to_string())).is_err());
+        assert!(from_tag::<()>(compound(vec![("a", Tag::Int(1))])).is_err());
+        assert!(from_tag::<Marker>(Tag::Byte(0)).is_err());
+        assert!(to_tag(&vec![Some(1), None]).is_err());
+        assert!(to_tag(&u64::MAX).is_err());
+        assert!(to_tag(&BTreeMap::from([(1, 2)])).is_err());
+    }
+}